@@ -1,11 +1,14 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer, Mint};
 use std::convert::TryFrom;
 
 declare_id!("LunarScryV3111111111111111111111111111111111");
 
 pub mod constants {
-    pub const PROGRAM_VERSION: u8 = 3; // Updated version
+    pub const PROGRAM_VERSION: u8 = 4; // Updated version
     pub const MAX_EMERGENCY_ADMINS: usize = 10;
     pub const MAX_CONTENT_HASH_LENGTH: usize = 64;
     pub const MIN_VOTING_PERIOD: i64 = 86400; // 1 day
@@ -13,13 +16,82 @@ pub mod constants {
     pub const MIN_QUORUM_PERCENTAGE: u8 = 10;
     pub const MAX_QUORUM_PERCENTAGE: u8 = 90;
     pub const STAKE_LOCKUP_PERIOD: i64 = 86400; // 1 day
-    pub const EARLY_VOTER_BONUS: u8 = 30; // 30% bonus
+    pub const EARLY_VOTER_BONUS: u8 = 30; // max 30% bonus
     pub const MAX_DAILY_SUBMISSIONS: u32 = 10000;
     pub const MAX_DAILY_VOTES: u32 = 100000;
     pub const MAX_STAKE_PER_USER: u64 = 10_000_000_000; // 10,000 tokens with 6 decimals
+    /// Content scoring below this is not flagged and so never submitted.
     pub const MIN_AI_CONFIDENCE: u8 = 50;
     pub const VOTE_COOLDOWN_PERIOD: i64 = 10; // 10 seconds between votes
     pub const REWARD_DISTRIBUTION_PERIOD: i64 = 86400; // 1 day
+    pub const CONFIG_UPDATE_DELAY: i64 = 172800; // 2 days
+    pub const MAX_ORACLES: usize = 10;
+    pub const MIN_PROPOSAL_STAKE: u64 = 1_000_000_000; // 1,000 tokens with 6 decimals
+    pub const GOVERNANCE_VOTING_PERIOD: i64 = 259200; // 3 days
+    pub const GOVERNANCE_EXECUTION_DELAY: i64 = 172800; // 2 days
+    pub const GOVERNANCE_QUORUM_PERCENTAGE: u8 = 4; // of GUARD supply
+    pub const ADMIN_ACTION_TTL: i64 = 604800; // 7 days
+    pub const MAX_ROLE_MEMBERS: usize = 32;
+    pub const PAUSE_SUBMISSIONS: u8 = 1 << 0;
+    pub const PAUSE_VOTING: u8 = 1 << 1;
+    pub const PAUSE_FINALIZATION: u8 = 1 << 2;
+    pub const PAUSE_CLAIMS: u8 = 1 << 3;
+    pub const PAUSE_DISTRIBUTION: u8 = 1 << 4;
+    pub const PAUSE_ALL: u8 = PAUSE_SUBMISSIONS
+        | PAUSE_VOTING
+        | PAUSE_FINALIZATION
+        | PAUSE_CLAIMS
+        | PAUSE_DISTRIBUTION;
+    pub const CIRCUIT_BREAKER_RESTRICTED: u8 = PAUSE_SUBMISSIONS | PAUSE_VOTING;
+    pub const TRAILING_ACTIVITY_DAYS: u32 = 7;
+    pub const REWARD_PRECISION: u128 = 1_000_000_000_000; // reward-per-weight scaling
+    pub const MAX_MERKLE_LEAVES: u32 = 65_536; // 8 KiB claim bitmap
+    pub const MIN_TREASURY_SPEND_PERIOD: i64 = 3600; // 1 hour
+    pub const ACCOUNT_CLOSE_GRACE_PERIOD: i64 = 2592000; // 30 days after finalization
+    pub const CALLBACK_DISPATCH_WINDOW: i64 = 86400; // 1 day after finalization
+    pub const MAX_BATCH_SUBMISSIONS: usize = 10; // bounded by transaction size and compute
+    pub const MAX_CONTENT_TREE_DEPTH: u8 = 20; // ~1M leaves per tree
+    pub const CONTENT_TREE_ROOT_HISTORY: usize = 32;
+    /// Flagged content scoring from `MIN_AI_CONFIDENCE` up to this is low
+    /// risk and may be submitted compressed. Anything higher needs its own
+    /// `Content` account so voting starts straight away.
+    pub const LOW_RISK_MAX_AI_SCORE: u8 = 70;
+    pub const MAX_VESTING_TRANCHES: usize = 8;
+}
+
+/// Hashing shared by the on-chain Merkle reward claims and the off-chain tree builder.
+pub mod merkle {
+    use anchor_lang::prelude::Pubkey;
+    use anchor_lang::solana_program::keccak;
+
+    // Distinct prefixes stop an inner node from being passed off as a leaf
+    const LEAF_PREFIX: &[u8] = &[0];
+    const NODE_PREFIX: &[u8] = &[1];
+    const CONTENT_LEAF_PREFIX: &[u8] = &[2];
+
+    pub fn leaf_hash(index: u32, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+        keccak::hashv(&[
+            LEAF_PREFIX,
+            &index.to_le_bytes(),
+            claimant.as_ref(),
+            &amount.to_le_bytes(),
+        ])
+        .0
+    }
+
+    /// Leaf of a compressed content tree; `leaf` is the Borsh-encoded `CompressedContent`.
+    pub fn content_leaf_hash(leaf: &[u8]) -> [u8; 32] {
+        keccak::hashv(&[CONTENT_LEAF_PREFIX, leaf]).0
+    }
+
+    pub fn node_hash(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+        let (left, right) = if a <= b { (a, b) } else { (b, a) };
+        keccak::hashv(&[NODE_PREFIX, left, right]).0
+    }
+
+    pub fn verify(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+        proof.iter().fold(leaf, |node, sibling| node_hash(&node, sibling)) == root
+    }
 }
 
 #[program]
@@ -32,31 +104,27 @@ pub mod lunar_scry {
     ) -> Result<()> {
         let protocol = &mut ctx.accounts.protocol_state;
 
-        require!(
-            config.quorum_percentage >= constants::MIN_QUORUM_PERCENTAGE
-                && config.quorum_percentage <= constants::MAX_QUORUM_PERCENTAGE,
-            ErrorCode::InvalidQuorumPercentage
-        );
-        require!(
-            config.voting_period >= constants::MIN_VOTING_PERIOD
-                && config.voting_period <= constants::MAX_VOTING_PERIOD,
-            ErrorCode::InvalidVotingPeriod
-        );
-        require!(
-            config.reward_per_vote > 0,
-            ErrorCode::InvalidRewardPerVote
-        );
+        config.validate()?;
 
         protocol.admin = ctx.accounts.admin.key();
-        protocol.stake_required = config.stake_required;
-        protocol.voting_period = config.voting_period;
-        protocol.quorum_percentage = config.quorum_percentage;
-        protocol.reward_per_vote = config.reward_per_vote;
+        protocol.apply_config(&config);
         protocol.treasury = ctx.accounts.treasury.key();
         protocol.is_paused = false;
         protocol.version = constants::PROGRAM_VERSION;
         protocol.bump = *ctx.bumps.get("protocol_state").unwrap();
+        protocol.pending_admin = None;
+        // Seeded with the deployer, but managed on its own from here on;
+        // admin transfers and renounces never touch this list
         protocol.emergency_admins = vec![ctx.accounts.admin.key()];
+        protocol.admin_threshold = 1;
+        protocol.current_epoch = 0;
+        protocol.last_reward_distribution_timestamp = Clock::get()?.unix_timestamp;
+
+        ctx.accounts.reward_epoch.initialize(
+            0,
+            protocol.last_reward_distribution_timestamp,
+            *ctx.bumps.get("reward_epoch").unwrap(),
+        );
 
         emit!(ProtocolInitialized {
             admin: protocol.admin,
@@ -80,32 +148,320 @@ pub mod lunar_scry {
         let content = &mut ctx.accounts.content;
         let clock = Clock::get()?;
 
-        protocol.check_active_status()?;
+        protocol.check_not_paused(constants::PAUSE_SUBMISSIONS)?;
         protocol.check_and_update_daily_limits(clock.unix_timestamp)?;
+        protocol.validate_submission(&content_data)?;
+        if let Some(app) = ctx.accounts.app_registration.as_ref() {
+            app.check_active()?;
+        }
 
+        content.initialize(
+            ctx.accounts.submitter.key(),
+            content_data,
+            protocol,
+            clock.unix_timestamp,
+            *ctx.bumps.get("content").unwrap(),
+        )?;
+        content.app = ctx.accounts.app_registration.as_ref().map(|app| app.key());
+
+        let submission_fee = protocol.treasury_config.submission_fee;
+        if submission_fee > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.submitter_token_account.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                        authority: ctx.accounts.submitter.to_account_info(),
+                    },
+                ),
+                submission_fee,
+            )?;
+
+            emit!(TreasuryMovement {
+                kind: TreasuryMovementKind::SubmissionFee,
+                amount: submission_fee,
+                counterparty: ctx.accounts.submitter_token_account.key(),
+                authorized_by: ctx.accounts.submitter.key(),
+                balance: ctx.accounts.treasury.amount
+                    .checked_add(submission_fee)
+                    .ok_or(ErrorCode::CalculationError)?,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        protocol.record_submissions(1)?;
+        protocol.check_circuit_breaker(clock.unix_timestamp);
+
+        emit!(ContentSubmitted {
+            content_id: content.key(),
+            submitter: content.submitter,
+            content_hash: content.content_hash,
+            content_type: content.content_type,
+            ai_score: content.ai_score,
+            timestamp: content.submission_time,
+            voting_period: content.voting_period,
+            app: content.app,
+        });
+
+        Ok(())
+    }
+
+    /// Submits up to `MAX_BATCH_SUBMISSIONS` items at once. Remaining accounts
+    /// are the `[content, verdict]` PDA pair for each item, in order.
+    pub fn submit_content_batch<'info>(
+        ctx: Context<'_, '_, '_, 'info, SubmitContentBatch<'info>>,
+        items: Vec<ContentData>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let submitter = &ctx.accounts.submitter;
+        let clock = Clock::get()?;
+
+        protocol.check_not_paused(constants::PAUSE_SUBMISSIONS)?;
+        protocol.check_and_update_daily_limits(clock.unix_timestamp)?;
+        // Reserve the whole batch against the daily limit before creating anything
+        let count = protocol.reserve_batch(items.len(), ctx.remaining_accounts.len())?;
+
+        if let Some(app) = ctx.accounts.app_registration.as_ref() {
+            app.check_active()?;
+        }
+        let app = ctx.accounts.app_registration.as_ref().map(|app| app.key());
+
+        for (content_data, accounts) in items.into_iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let (content_info, verdict_info) = (&accounts[0], &accounts[1]);
+            protocol.validate_submission(&content_data)?;
+
+            let (content_key, bump) = Pubkey::find_program_address(
+                &[b"content", content_data.content_hash.as_ref()],
+                &crate::ID,
+            );
+            let (verdict_key, _) = Pubkey::find_program_address(
+                &[b"verdict", content_key.as_ref()],
+                &crate::ID,
+            );
+            require!(
+                content_info.key() == content_key && verdict_info.key() == verdict_key,
+                ErrorCode::InvalidBatchAccounts
+            );
+            require!(verdict_info.data_is_empty(), ErrorCode::ContentAlreadyDecided);
+
+            create_pda_account(
+                submitter,
+                content_info,
+                &ctx.accounts.system_program,
+                Content::SIZE,
+                &[b"content", content_data.content_hash.as_ref(), &[bump]],
+            )?;
+
+            let mut content = Account::<Content>::try_from_unchecked(content_info)?;
+            content.initialize(
+                submitter.key(),
+                content_data,
+                protocol,
+                clock.unix_timestamp,
+                bump,
+            )?;
+            content.app = app;
+            content.exit(&crate::ID)?;
+
+            emit!(ContentSubmitted {
+                content_id: content.key(),
+                submitter: content.submitter,
+                content_hash: content.content_hash,
+                content_type: content.content_type,
+                ai_score: content.ai_score,
+                timestamp: content.submission_time,
+                voting_period: content.voting_period,
+                app: content.app,
+            });
+        }
+
+        let total_fee = protocol.treasury_config.submission_fee
+            .checked_mul(count as u64)
+            .ok_or(ErrorCode::CalculationError)?;
+        if total_fee > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.submitter_token_account.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                        authority: submitter.to_account_info(),
+                    },
+                ),
+                total_fee,
+            )?;
+
+            emit!(TreasuryMovement {
+                kind: TreasuryMovementKind::SubmissionFee,
+                amount: total_fee,
+                counterparty: ctx.accounts.submitter_token_account.key(),
+                authorized_by: submitter.key(),
+                balance: ctx.accounts.treasury.amount
+                    .checked_add(total_fee)
+                    .ok_or(ErrorCode::CalculationError)?,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        protocol.check_circuit_breaker(clock.unix_timestamp);
+
+        emit!(ContentBatchSubmitted {
+            submitter: submitter.key(),
+            count,
+            total_fee,
+            daily_submission_count: protocol.daily_submission_count,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_content_tree(
+        ctx: Context<InitializeContentTree>,
+        depth: u8,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let tree = &mut ctx.accounts.content_tree;
+
+        ctx.accounts.roles.require_role(&ctx.accounts.admin.key(), Role::ConfigManager)?;
         require!(
-            content_data.content_type != ContentType::Video
-                && content_data.content_type != ContentType::DeFi,
-            ErrorCode::UnsupportedContentType
-        );
-        require!(
-            content_data.content_hash.len() <= constants::MAX_CONTENT_HASH_LENGTH,
-            ErrorCode::ContentHashTooLong
+            depth > 0 && depth <= constants::MAX_CONTENT_TREE_DEPTH,
+            ErrorCode::InvalidTreeDepth
         );
-        require!(
-            content_data.ai_score >= constants::MIN_AI_CONFIDENCE,
-            ErrorCode::LowAIConfidence
+
+        tree.initialize(
+            protocol.content_tree_count,
+            depth,
+            *ctx.bumps.get("content_tree").unwrap(),
         );
+        protocol.content_tree_count = protocol.content_tree_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
 
-        content.initialize(
-            ctx.accounts.submitter.key(),
+        emit!(ContentTreeCreated {
+            tree: tree.key(),
+            tree_id: tree.id,
+            depth,
+            capacity: 1u64 << depth,
+            created_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Appends a low-risk item, one scoring `MIN_AI_CONFIDENCE` to
+    /// `LOW_RISK_MAX_AI_SCORE`, as a leaf of a content tree instead of creating
+    /// a `Content` account. The event carries the full leaf for indexers, which
+    /// serve the proofs needed to verify or escalate it later.
+    pub fn submit_content_compressed(
+        ctx: Context<SubmitContentCompressed>,
+        content_data: ContentData,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let tree = &mut ctx.accounts.content_tree;
+        let clock = Clock::get()?;
+
+        protocol.check_not_paused(constants::PAUSE_SUBMISSIONS)?;
+        protocol.check_and_update_daily_limits(clock.unix_timestamp)?;
+        protocol.validate_compressed_submission(&content_data)?;
+
+        let leaf = CompressedContent {
+            tree_id: tree.id,
+            leaf_index: tree.next_index,
+            submitter: ctx.accounts.submitter.key(),
+            submission_time: clock.unix_timestamp,
             content_data,
+        };
+        let root = tree.append(leaf.hash()?)?;
+
+        let submission_fee = protocol.treasury_config.submission_fee;
+        if submission_fee > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.submitter_token_account.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                        authority: ctx.accounts.submitter.to_account_info(),
+                    },
+                ),
+                submission_fee,
+            )?;
+
+            emit!(TreasuryMovement {
+                kind: TreasuryMovementKind::SubmissionFee,
+                amount: submission_fee,
+                counterparty: ctx.accounts.submitter_token_account.key(),
+                authorized_by: ctx.accounts.submitter.key(),
+                balance: ctx.accounts.treasury.amount
+                    .checked_add(submission_fee)
+                    .ok_or(ErrorCode::CalculationError)?,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        protocol.record_submissions(1)?;
+        protocol.check_circuit_breaker(clock.unix_timestamp);
+
+        emit!(ContentCompressed {
+            tree: tree.key(),
+            leaf,
+            root,
+        });
+
+        Ok(())
+    }
+
+    /// Fails unless `leaf` is in the tree under one of its recent roots.
+    pub fn verify_compressed_content(
+        ctx: Context<VerifyCompressedContent>,
+        leaf: CompressedContent,
+        root: [u8; 32],
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.content_tree.verify(&leaf, root, &proof)
+    }
+
+    /// Escalates a compressed item to community review by creating its full
+    /// `Content` account. Voting starts now, not at the original submission,
+    /// and the caller pays (and on close gets back) the rent. The new account
+    /// counts against the daily limit like any other submission.
+    pub fn decompress_content(
+        ctx: Context<DecompressContent>,
+        leaf: CompressedContent,
+        root: [u8; 32],
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let content = &mut ctx.accounts.content;
+        let clock = Clock::get()?;
+
+        protocol.check_not_paused(constants::PAUSE_SUBMISSIONS)?;
+        protocol.check_and_update_daily_limits(clock.unix_timestamp)?;
+        protocol.check_content_type_active(leaf.content_data.content_type)?;
+        ctx.accounts.content_tree.verify(&leaf, root, &proof)?;
+
+        content.initialize(
+            leaf.submitter,
+            leaf.content_data,
             protocol,
             clock.unix_timestamp,
             *ctx.bumps.get("content").unwrap(),
         )?;
+        content.rent_payer = ctx.accounts.payer.key();
 
-        protocol.increment_submission_count()?;
+        protocol.record_submissions(1)?;
+        protocol.check_circuit_breaker(clock.unix_timestamp);
+
+        emit!(ContentDecompressed {
+            content_id: content.key(),
+            tree: ctx.accounts.content_tree.key(),
+            leaf_index: leaf.leaf_index,
+            escalated_by: ctx.accounts.payer.key(),
+            timestamp: clock.unix_timestamp,
+        });
 
         emit!(ContentSubmitted {
             content_id: content.key(),
@@ -114,6 +470,8 @@ pub mod lunar_scry {
             content_type: content.content_type,
             ai_score: content.ai_score,
             timestamp: content.submission_time,
+            voting_period: content.voting_period,
+            app: content.app,
         });
 
         Ok(())
@@ -124,13 +482,27 @@ pub mod lunar_scry {
         vote_type: VoteType,
         stake_amount: u64,
     ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        ctx.accounts.protocol_state.check_and_update_daily_limits(clock.unix_timestamp)?;
+        ctx.accounts.protocol_state.validate_vote_transaction(
+            &ctx.accounts.content,
+            stake_amount,
+            clock.unix_timestamp,
+        )?;
+        transfer_stake_tokens(&ctx, stake_amount)?;
+
         let protocol = &mut ctx.accounts.protocol_state;
         let content = &mut ctx.accounts.content;
         let vote_account = &mut ctx.accounts.vote_account;
-        let clock = Clock::get()?;
+        let reward_epoch = &mut ctx.accounts.reward_epoch;
+
+        let early_bonus_percentage = protocol.early_voter_bonus.bonus_percentage(
+            clock.unix_timestamp - content.submission_time,
+            content.voting_period,
+            content.vote_count,
+        );
 
-        protocol.validate_vote_transaction(content, stake_amount, clock.unix_timestamp)?;
-        transfer_stake_tokens(ctx, stake_amount)?;
         content.process_vote(vote_type, stake_amount)?;
         vote_account.initialize(
             ctx.accounts.voter.key(),
@@ -138,8 +510,13 @@ pub mod lunar_scry {
             vote_type,
             stake_amount,
             clock.unix_timestamp,
+            reward_epoch.epoch,
+            early_bonus_percentage,
         )?;
+        reward_epoch.accrue_weight(vote_account.reward_weight)?;
         protocol.increment_vote_count()?;
+        protocol.record_vote_activity(ctx.accounts.voter.key(), stake_amount)?;
+        protocol.check_circuit_breaker(clock.unix_timestamp);
 
         emit!(VoteCast {
             content_id: content.key(),
@@ -148,6 +525,9 @@ pub mod lunar_scry {
             stake_amount,
             timestamp: clock.unix_timestamp,
             vote_number: content.vote_count,
+            reward_epoch: vote_account.reward_epoch,
+            reward_weight: vote_account.reward_weight,
+            early_bonus_percentage,
         });
 
         Ok(())
@@ -160,25 +540,10 @@ pub mod lunar_scry {
         let content = &mut ctx.accounts.content;
         let clock = Clock::get()?;
 
-        protocol.check_active_status()?;
-        require!(
-            clock.unix_timestamp > content.submission_time + content.voting_period,
-            ErrorCode::VotingPeriodActive
-        );
-
-        let total_stake = content.approve_votes + content.reject_votes;
-        require!(
-            total_stake >= (content.total_stake * content.quorum_percentage as u64) / 100,
-            ErrorCode::QuorumNotReached
-        );
-
-        let final_status = if content.approve_votes > content.reject_votes {
-            ContentStatus::Approved
-        } else {
-            ContentStatus::Rejected
-        };
-
-        content.status = final_status;
+        protocol.check_not_paused(constants::PAUSE_FINALIZATION)?;
+        // Fails for content that is already decided, so the keeper fee and
+        // the callback are paid and queued once per content
+        let (final_status, total_stake) = content.finalize(clock.unix_timestamp)?;
 
         emit!(DecisionFinalized {
             content_id: content.key(),
@@ -189,57 +554,14 @@ pub mod lunar_scry {
             timestamp: clock.unix_timestamp,
         });
 
-        Ok(())
-    }
-
-    pub fn claim_rewards(
-        ctx: Context<ClaimRewards>,
-    ) -> Result<()> {
-        let protocol = &mut ctx.accounts.protocol_state;
-        let content = &mut ctx.accounts.content;
-        let vote_account = &mut ctx.accounts.vote_account;
-        let voter = &mut ctx.accounts.voter;
-        let vote_vault = &mut ctx.accounts.vote_vault;
-        let reward_vault = &mut ctx.accounts.reward_vault;
-        let clock = Clock::get()?;
-
-        protocol.check_active_status()?;
-        require!(
-            clock.unix_timestamp >= vote_account.vote_timestamp + constants::STAKE_LOCKUP_PERIOD,
-            ErrorCode::StakeStillLocked
-        );
-        require!(
-            vote_account.voter == *voter.key,
-            ErrorCode::Unauthorized
-        );
-        require!(
-            vote_account.status == VoteStatus::Active,
-            ErrorCode::RewardsAlreadyClaimed
-        );
-
-        let reward_amount = (vote_account.stake_amount * protocol.reward_per_vote) / content.total_stake;
-
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: vote_vault.to_account_info(),
-                    to: reward_vault.to_account_info(),
-                    authority: protocol.to_account_info(),
-                },
-            ),
-            reward_amount,
+        pay_keeper_fee(
+            protocol,
+            &ctx.accounts.treasury,
+            &ctx.accounts.keeper_token_account,
+            &ctx.accounts.token_program,
+            clock.unix_timestamp,
         )?;
 
-        vote_account.status = VoteStatus::Rewarded;
-
-        emit!(RewardsClaimed {
-            voter: *voter.key,
-            content_id: content.key(),
-            reward_amount,
-            timestamp: clock.unix_timestamp,
-        });
-
         Ok(())
     }
 
@@ -248,10 +570,7 @@ pub mod lunar_scry {
     ) -> Result<()> {
         let protocol = &mut ctx.accounts.protocol_state;
 
-        require!(
-            protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
-            ErrorCode::Unauthorized
-        );
+        require_pauser(protocol, ctx.accounts.roles.as_ref(), &ctx.accounts.admin.key())?;
 
         protocol.is_paused = true;
 
@@ -272,6 +591,12 @@ pub mod lunar_scry {
             protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
             ErrorCode::Unauthorized
         );
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::Unpause,
+            Clock::get()?.unix_timestamp,
+        )?;
 
         protocol.is_paused = false;
 
@@ -292,10 +617,22 @@ pub mod lunar_scry {
             protocol.emergency_admins.len() < constants::MAX_EMERGENCY_ADMINS,
             ErrorCode::MaxEmergencyAdminsReached
         );
+        require!(
+            !protocol.emergency_admins.contains(&ctx.accounts.new_admin.key()),
+            ErrorCode::InvalidAdmin
+        );
         require!(
             protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
             ErrorCode::Unauthorized
         );
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::AddEmergencyAdmin {
+                admin: ctx.accounts.new_admin.key(),
+            },
+            Clock::get()?.unix_timestamp,
+        )?;
 
         protocol.emergency_admins.push(ctx.accounts.new_admin.key());
 
@@ -317,10 +654,27 @@ pub mod lunar_scry {
             protocol.emergency_admins.len() > 1,
             ErrorCode::CannotRemoveLastAdmin
         );
+        require!(
+            protocol.emergency_admins.contains(&ctx.accounts.admin_to_remove.key()),
+            ErrorCode::InvalidAdmin
+        );
+        // Removing an admin must never leave fewer admins than approvals required
+        require!(
+            protocol.emergency_admins.len() - 1 >= protocol.admin_threshold as usize,
+            ErrorCode::InvalidAdminThreshold
+        );
         require!(
             protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
             ErrorCode::Unauthorized
         );
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::RemoveEmergencyAdmin {
+                admin: ctx.accounts.admin_to_remove.key(),
+            },
+            Clock::get()?.unix_timestamp,
+        )?;
 
         protocol.emergency_admins.retain(|&admin| admin != ctx.accounts.admin_to_remove.key());
 
@@ -333,295 +687,5721 @@ pub mod lunar_scry {
         Ok(())
     }
 
-    pub fn distribute_rewards(
-    ctx: Context<DistributeRewards>,
-) -> Result<()> {
-    let protocol = &mut ctx.accounts.protocol_state;
-    let clock = Clock::get()?;
+    pub fn close_reward_epoch(
+        ctx: Context<CloseRewardEpoch>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let epoch = &mut ctx.accounts.reward_epoch;
+        let next_epoch = &mut ctx.accounts.next_reward_epoch;
+        let clock = Clock::get()?;
 
-    // Validation checks
-    require!(
-        !protocol.is_paused,
-        ErrorCode::ProtocolPaused
-    );
+        protocol.check_not_paused(constants::PAUSE_DISTRIBUTION)?;
+        require!(
+            clock.unix_timestamp >= epoch.start_timestamp + constants::REWARD_DISTRIBUTION_PERIOD,
+            ErrorCode::RewardDistributionNotDue
+        );
 
-    require!(
-        clock.unix_timestamp >= protocol.last_reward_distribution_timestamp + constants::REWARD_DISTRIBUTION_PERIOD,
-        ErrorCode::RewardDistributionNotDue
-    );
+        // Top up the reward vault from the treasury before the pool is measured
+        if protocol.emission_schedule_configured {
+            let schedule = ctx.accounts.emission_schedule
+                .as_mut()
+                .ok_or(ErrorCode::EmissionScheduleRequired)?;
+            let treasury = ctx.accounts.treasury
+                .as_ref()
+                .ok_or(ErrorCode::EmissionScheduleRequired)?;
 
-    // Calculate total rewards for the period
-    let total_stake = ctx.accounts.stake_vault.amount;
-    let reward_pool = ctx.accounts.reward_vault.amount;
-    
-    require!(reward_pool > 0, ErrorCode::InsufficientRewardPool);
-
-    // Process each eligible voter
-    let mut total_distributed: u64 = 0;
-    for vote_account in &ctx.remaining_accounts {
-        let vote = Account::<Vote>::try_from(vote_account)?;
-        
-        // Verify vote eligibility
-        if !is_vote_eligible(&vote, clock.unix_timestamp)? {
-            continue;
-        }
-
-        // Calculate voter's reward share
-        let reward_amount = calculate_voter_reward(
-            vote.stake_amount,
-            total_stake,
-            reward_pool,
-            vote.timestamp,
-            clock.unix_timestamp
-        )?;
+            let scheduled_amount = schedule.emission_for(epoch.start_timestamp);
+            let funded_amount = scheduled_amount.min(treasury.amount);
 
-        // Apply early voter bonus if applicable
-        let final_reward = if is_early_voter(&vote, protocol) {
-            reward_amount
-                .checked_mul(120)
-                .ok_or(ErrorCode::CalculationError)?
-                .checked_div(100)
-                .ok_or(ErrorCode::CalculationError)?
-        } else {
-            reward_amount
-        };
+            if funded_amount > 0 {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: treasury.to_account_info(),
+                            to: ctx.accounts.reward_vault.to_account_info(),
+                            authority: protocol.to_account_info(),
+                        },
+                        &[&[b"protocol", &[protocol.bump]]],
+                    ),
+                    funded_amount,
+                )?;
+                ctx.accounts.reward_vault.reload()?;
 
-        // Transfer rewards
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.reward_vault.to_account_info(),
-                    to: ctx.accounts.voter_token_account.to_account_info(),
-                    authority: protocol.to_account_info(),
-                },
-                &[&[b"protocol", &[protocol.bump]]],
-            ),
-            final_reward,
-        )?;
+                emit!(TreasuryMovement {
+                    kind: TreasuryMovementKind::EmissionFunding,
+                    amount: funded_amount,
+                    counterparty: ctx.accounts.reward_vault.key(),
+                    authorized_by: ctx.accounts.payer.key(),
+                    balance: treasury.amount - funded_amount,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+            schedule.record_emission(epoch.start_timestamp, funded_amount)?;
+
+            emit!(EpochEmissionFunded {
+                epoch: epoch.epoch,
+                scheduled_amount,
+                funded_amount,
+                total_emitted: schedule.total_emitted,
+                next_scheduled_emission: schedule.scheduled_emission(schedule.epochs_elapsed),
+                schedule_end_timestamp: schedule.end_timestamp,
+                timestamp: clock.unix_timestamp,
+            });
+        }
 
-        total_distributed = total_distributed
-            .checked_add(final_reward)
+        // Tokens already owed to voters of earlier epochs cannot fund this one
+        let reward_pool = ctx.accounts.reward_vault.amount
+            .checked_sub(protocol.reserved_rewards)
+            .and_then(|amount| amount.checked_sub(protocol.vesting_balance))
             .ok_or(ErrorCode::CalculationError)?;
 
-        emit!(RewardDistributed {
-            voter: vote.voter,
-            amount: final_reward,
-            timestamp: clock.unix_timestamp,
-        });
-    }
+        let allocated = epoch.close(reward_pool, clock.unix_timestamp)?;
 
-    // Update protocol state
-    protocol.last_reward_distribution_timestamp = clock.unix_timestamp;
-    protocol.total_rewards_distributed = protocol.total_rewards_distributed
-        .checked_add(total_distributed)
-        .ok_or(ErrorCode::CalculationError)?;
+        protocol.reserved_rewards = protocol.reserved_rewards
+            .checked_add(allocated)
+            .ok_or(ErrorCode::CalculationError)?;
+        protocol.last_reward_distribution_timestamp = clock.unix_timestamp;
+        protocol.current_epoch = protocol.current_epoch
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
 
-    emit!(RewardsDistributed {
-        total_amount: total_distributed,
-        timestamp: clock.unix_timestamp,
-    });
+        next_epoch.initialize(
+            protocol.current_epoch,
+            clock.unix_timestamp,
+            *ctx.bumps.get("next_reward_epoch").unwrap(),
+        );
 
-    Ok(())
-}
+        emit!(RewardsDistributed {
+            epoch: epoch.epoch,
+            total_amount: allocated,
+            total_weight: epoch.total_weight,
+            reward_per_weight: epoch.reward_per_weight,
+            timestamp: clock.unix_timestamp,
+        });
 
-// Helper functions
-fn is_vote_eligible(vote: &Vote, current_timestamp: i64) -> Result<bool> {
-    // Check if vote is within eligible period
-    Ok(
-        !vote.claimed &&
-        vote.timestamp + constants::REWARD_ELIGIBILITY_PERIOD > current_timestamp &&
-        vote.stake_amount >= constants::MIN_STAKE_FOR_REWARDS
-    )
-}
+        // Emission funding above may have drawn the treasury down
+        if let Some(treasury) = ctx.accounts.treasury.as_mut() {
+            treasury.reload()?;
+        }
+        pay_keeper_fee(
+            protocol,
+            &ctx.accounts.treasury,
+            &ctx.accounts.keeper_token_account,
+            &ctx.accounts.token_program,
+            clock.unix_timestamp,
+        )?;
 
-fn calculate_voter_reward(
-    stake_amount: u64,
-    total_stake: u64,
-    reward_pool: u64,
-    vote_timestamp: i64,
-    current_timestamp: i64,
-) -> Result<u64> {
-    // Calculate base reward share
-    let base_share = (stake_amount as u128)
-        .checked_mul(reward_pool as u128)
-        .ok_or(ErrorCode::CalculationError)?
-        .checked_div(total_stake as u128)
-        .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
 
-    // Apply time-weighted multiplier
-    let time_weight = calculate_time_weight(vote_timestamp, current_timestamp)?;
-    
-    let final_reward = base_share
-        .checked_mul(time_weight as u128)
-        .ok_or(ErrorCode::CalculationError)?
+    pub fn claim_epoch_rewards(
+        ctx: Context<ClaimEpochRewards>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let content = &ctx.accounts.content;
+        let epoch = &mut ctx.accounts.reward_epoch;
+        let vote_account = &mut ctx.accounts.vote_account;
+        let clock = Clock::get()?;
+
+        protocol.check_not_paused(constants::PAUSE_CLAIMS)?;
+        require!(epoch.is_closed, ErrorCode::RewardEpochNotClosed);
+        require!(
+            !epoch.merkle_root_posted,
+            ErrorCode::RewardEpochSettledByMerkle
+        );
+        require!(
+            content.status != ContentStatus::Pending,
+            ErrorCode::ContentNotFinalized
+        );
+        require!(
+            !vote_account.epoch_reward_claimed,
+            ErrorCode::RewardsAlreadyClaimed
+        );
+
+        let share = epoch.reward_for(vote_account.reward_weight)?;
+
+        protocol.reserved_rewards = protocol.reserved_rewards
+            .checked_sub(share)
+            .ok_or(ErrorCode::CalculationError)?;
+        vote_account.epoch_reward_claimed = true;
+        epoch.claimed_weight = epoch.claimed_weight
+            .checked_add(vote_account.reward_weight)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        // Votes against the final decision forfeit their share to the treasury
+        let reward_amount = if vote_account.is_aligned_with(content.status) {
+            share
+        } else {
+            0
+        };
+
+        if reward_amount == 0 && share > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.reward_vault.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                        authority: protocol.to_account_info(),
+                    },
+                    &[&[b"protocol", &[protocol.bump]]],
+                ),
+                share,
+            )?;
+
+            emit!(TreasuryMovement {
+                kind: TreasuryMovementKind::ForfeitedReward,
+                amount: share,
+                counterparty: vote_account.voter,
+                authorized_by: ctx.accounts.voter.key(),
+                balance: ctx.accounts.treasury.amount
+                    .checked_add(share)
+                    .ok_or(ErrorCode::CalculationError)?,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        if reward_amount > 0 {
+            if protocol.reward_vesting.enabled {
+                vest_reward(
+                    protocol,
+                    ctx.accounts.vesting_account.as_mut(),
+                    reward_amount,
+                    clock.unix_timestamp,
+                )?;
+            } else {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.reward_vault.to_account_info(),
+                            to: ctx.accounts.voter_token_account.to_account_info(),
+                            authority: protocol.to_account_info(),
+                        },
+                        &[&[b"protocol", &[protocol.bump]]],
+                    ),
+                    reward_amount,
+                )?;
+            }
+
+            protocol.total_rewards_distributed = protocol.total_rewards_distributed
+                .checked_add(reward_amount)
+                .ok_or(ErrorCode::CalculationError)?;
+        }
+
+        emit!(RewardDistributed {
+            voter: vote_account.voter,
+            content_id: content.key(),
+            epoch: epoch.epoch,
+            amount: reward_amount,
+            early_bonus_percentage: vote_account.early_bonus_percentage,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn post_merkle_root(
+        ctx: Context<PostMerkleRoot>,
+        epoch: u64,
+        root: [u8; 32],
+        total_amount: u64,
+        num_leaves: u32,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let reward_epoch = &mut ctx.accounts.reward_epoch;
+        let distribution = &mut ctx.accounts.merkle_distribution;
+        let clock = Clock::get()?;
+
+        ctx.accounts.roles.require_role(&ctx.accounts.admin.key(), Role::Keeper)?;
+        require!(
+            num_leaves > 0 && num_leaves <= constants::MAX_MERKLE_LEAVES,
+            ErrorCode::InvalidMerkleLeafCount
+        );
+        require!(reward_epoch.is_closed, ErrorCode::RewardEpochNotClosed);
+        require!(
+            !reward_epoch.merkle_root_posted && reward_epoch.claimed_weight == 0,
+            ErrorCode::RewardEpochAlreadyClaimed
+        );
+        require!(
+            total_amount <= reward_epoch.reward_pool,
+            ErrorCode::MerkleTotalExceedsPool
+        );
+
+        // The root replaces on-chain claims for this epoch. Whatever it does not
+        // allocate is the misaligned voters' shares, which go to the treasury
+        // as they would through `claim_epoch_rewards`
+        let forfeited = reward_epoch.reward_pool - total_amount;
+        protocol.reserved_rewards = protocol.reserved_rewards
+            .checked_sub(forfeited)
+            .ok_or(ErrorCode::CalculationError)?;
+        reward_epoch.merkle_root_posted = true;
+
+        if forfeited > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.reward_vault.to_account_info(),
+                        to: ctx.accounts.treasury.to_account_info(),
+                        authority: protocol.to_account_info(),
+                    },
+                    &[&[b"protocol", &[protocol.bump]]],
+                ),
+                forfeited,
+            )?;
+
+            emit!(TreasuryMovement {
+                kind: TreasuryMovementKind::ForfeitedReward,
+                amount: forfeited,
+                counterparty: ctx.accounts.reward_vault.key(),
+                authorized_by: ctx.accounts.admin.key(),
+                balance: ctx.accounts.treasury.amount
+                    .checked_add(forfeited)
+                    .ok_or(ErrorCode::CalculationError)?,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+
+        distribution.epoch = epoch;
+        distribution.root = root;
+        distribution.total_amount = total_amount;
+        distribution.claimed_amount = 0;
+        distribution.num_leaves = num_leaves;
+        distribution.posted_by = ctx.accounts.admin.key();
+        distribution.posted_at = clock.unix_timestamp;
+        distribution.bump = *ctx.bumps.get("merkle_distribution").unwrap();
+        distribution.claimed_bitmap = vec![0; MerkleDistribution::bitmap_len(num_leaves)];
+
+        emit!(MerkleRootPosted {
+            epoch,
+            root,
+            total_amount,
+            num_leaves,
+            forfeited_amount: forfeited,
+            posted_by: distribution.posted_by,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim_merkle_reward(
+        ctx: Context<ClaimMerkleReward>,
+        index: u32,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let distribution = &mut ctx.accounts.merkle_distribution;
+        let claimant = ctx.accounts.claimant.key();
+        let clock = Clock::get()?;
+
+        protocol.check_not_paused(constants::PAUSE_CLAIMS)?;
+        require!(
+            index < distribution.num_leaves,
+            ErrorCode::InvalidMerkleProof
+        );
+        require!(
+            !distribution.is_claimed(index),
+            ErrorCode::RewardsAlreadyClaimed
+        );
+        require!(
+            merkle::verify(&proof, distribution.root, merkle::leaf_hash(index, &claimant, amount)),
+            ErrorCode::InvalidMerkleProof
+        );
+
+        distribution.claimed_amount = distribution.claimed_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        require!(
+            distribution.claimed_amount <= distribution.total_amount,
+            ErrorCode::MerkleTotalExceedsPool
+        );
+        distribution.set_claimed(index);
+
+        protocol.reserved_rewards = protocol.reserved_rewards
+            .checked_sub(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        protocol.total_rewards_distributed = protocol.total_rewards_distributed
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        if protocol.reward_vesting.enabled {
+            vest_reward(
+                protocol,
+                ctx.accounts.vesting_account.as_mut(),
+                amount,
+                clock.unix_timestamp,
+            )?;
+        } else {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.reward_vault.to_account_info(),
+                        to: ctx.accounts.claimant_token_account.to_account_info(),
+                        authority: protocol.to_account_info(),
+                    },
+                    &[&[b"protocol", &[protocol.bump]]],
+                ),
+                amount,
+            )?;
+        }
+
+        emit!(MerkleRewardClaimed {
+            epoch: distribution.epoch,
+            index,
+            claimant,
+            amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_emission_schedule(
+        ctx: Context<InitializeEmissionSchedule>,
+        params: EmissionScheduleParams,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let schedule = &mut ctx.accounts.emission_schedule;
+
+        ctx.accounts.roles.require_role(&ctx.accounts.admin.key(), Role::ConfigManager)?;
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::InitializeEmissionSchedule { params },
+            Clock::get()?.unix_timestamp,
+        )?;
+        params.validate()?;
+
+        schedule.apply(&params);
+        schedule.epochs_elapsed = 0;
+        schedule.total_emitted = 0;
+        schedule.bump = *ctx.bumps.get("emission_schedule").unwrap();
+        protocol.emission_schedule_configured = true;
+
+        emit!(EmissionScheduleUpdated {
+            initial_emission: schedule.initial_emission,
+            decay: schedule.decay,
+            start_timestamp: schedule.start_timestamp,
+            end_timestamp: schedule.end_timestamp,
+            updated_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_emission_schedule(
+        ctx: Context<UpdateEmissionSchedule>,
+        params: EmissionScheduleParams,
+    ) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let schedule = &mut ctx.accounts.emission_schedule;
+
+        ctx.accounts.roles.require_role(&ctx.accounts.admin.key(), Role::ConfigManager)?;
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::UpdateEmissionSchedule { params },
+            Clock::get()?.unix_timestamp,
+        )?;
+        params.validate()?;
+
+        // Decay keeps counting from the epochs already emitted
+        schedule.apply(&params);
+
+        emit!(EmissionScheduleUpdated {
+            initial_emission: schedule.initial_emission,
+            decay: schedule.decay,
+            start_timestamp: schedule.start_timestamp,
+            end_timestamp: schedule.end_timestamp,
+            updated_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_reward_vesting(
+        ctx: Context<SetRewardVesting>,
+        config: VestingConfig,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+
+        ctx.accounts.roles.require_role(&ctx.accounts.admin.key(), Role::ConfigManager)?;
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::SetRewardVesting { config },
+            Clock::get()?.unix_timestamp,
+        )?;
+        config.validate()?;
+
+        protocol.reward_vesting = config;
+
+        emit!(RewardVestingUpdated {
+            enabled: config.enabled,
+            cliff_seconds: config.cliff_seconds,
+            duration_seconds: config.duration_seconds,
+            updated_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn open_vesting_account(
+        ctx: Context<OpenVestingAccount>,
+    ) -> Result<()> {
+        let vesting = &mut ctx.accounts.vesting_account;
+
+        vesting.owner = ctx.accounts.owner.key();
+        vesting.bump = *ctx.bumps.get("vesting_account").unwrap();
+
+        Ok(())
+    }
+
+    pub fn release_vested(
+        ctx: Context<ReleaseVested>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let vesting = &mut ctx.accounts.vesting_account;
+        let clock = Clock::get()?;
+
+        protocol.check_not_paused(constants::PAUSE_CLAIMS)?;
+
+        let amount = vesting.release(clock.unix_timestamp)?;
+        require!(amount > 0, ErrorCode::NothingToRelease);
+
+        protocol.vesting_balance = protocol.vesting_balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.owner_token_account.to_account_info(),
+                    authority: protocol.to_account_info(),
+                },
+                &[&[b"protocol", &[protocol.bump]]],
+            ),
+            amount,
+        )?;
+
+        emit!(VestedRewardsReleased {
+            owner: vesting.owner,
+            amount,
+            remaining_locked: vesting.locked_amount(clock.unix_timestamp),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Called when a user is slashed: everything still vesting goes to the
+    /// treasury, while rewards that already vested remain releasable.
+    pub fn forfeit_vesting(
+        ctx: Context<ForfeitVesting>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let vesting = &mut ctx.accounts.vesting_account;
+        let clock = Clock::get()?;
+
+        ctx.accounts.roles.require_role(&ctx.accounts.admin.key(), Role::TreasuryManager)?;
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::ForfeitVesting { owner: vesting.owner },
+            clock.unix_timestamp,
+        )?;
+
+        let amount = vesting.forfeit(clock.unix_timestamp)?;
+        require!(amount > 0, ErrorCode::NothingToRelease);
+
+        protocol.vesting_balance = protocol.vesting_balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: protocol.to_account_info(),
+                },
+                &[&[b"protocol", &[protocol.bump]]],
+            ),
+            amount,
+        )?;
+
+        emit!(TreasuryMovement {
+            kind: TreasuryMovementKind::ForfeitedVesting,
+            amount,
+            counterparty: ctx.accounts.reward_vault.key(),
+            authorized_by: ctx.accounts.admin.key(),
+            balance: ctx.accounts.treasury.amount
+                .checked_add(amount)
+                .ok_or(ErrorCode::CalculationError)?,
+            timestamp: clock.unix_timestamp,
+        });
+
+        emit!(VestingForfeited {
+            owner: vesting.owner,
+            amount,
+            forfeited_by: ctx.accounts.admin.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn propose_config_update(
+        ctx: Context<ProposeConfigUpdate>,
+        config: ProtocolConfig,
+    ) -> Result<()> {
+        let update = &mut ctx.accounts.config_update;
+        let clock = Clock::get()?;
+
+        ctx.accounts.roles.require_role(&ctx.accounts.admin.key(), Role::ConfigManager)?;
+        config.validate()?;
+        ctx.accounts.protocol_state.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::ProposeConfigUpdate {
+                config,
+                treasury: ctx.accounts.new_treasury.key(),
+            },
+            clock.unix_timestamp,
+        )?;
+
+        update.config = config;
+        update.treasury = ctx.accounts.new_treasury.key();
+        update.proposed_by = ctx.accounts.admin.key();
+        update.proposed_at = clock.unix_timestamp;
+        update.eta = clock.unix_timestamp + constants::CONFIG_UPDATE_DELAY;
+        update.bump = *ctx.bumps.get("config_update").unwrap();
+
+        emit!(ConfigUpdateProposed {
+            proposed_by: update.proposed_by,
+            stake_required: config.stake_required,
+            voting_period: config.voting_period,
+            quorum_percentage: config.quorum_percentage,
+            reward_per_vote: config.reward_per_vote,
+            early_voter_bonus: config.early_voter_bonus,
+            treasury: update.treasury,
+            eta: update.eta,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn execute_config_update(
+        ctx: Context<ExecuteConfigUpdate>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let update = &ctx.accounts.config_update;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp >= update.eta,
+            ErrorCode::TimelockNotExpired
+        );
+        // Re-check in case the bounds changed with a program upgrade
+        update.config.validate()?;
+
+        protocol.apply_config(&update.config);
+
+        if update.treasury != protocol.treasury {
+            // Carry the balance over so nothing is stranded in the old account
+            let balance = ctx.accounts.treasury.amount;
+            if balance > 0 {
+                let seeds = &[b"protocol".as_ref(), &[protocol.bump]];
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.treasury.to_account_info(),
+                            to: ctx.accounts.new_treasury.to_account_info(),
+                            authority: protocol.to_account_info(),
+                        },
+                        &[&seeds[..]],
+                    ),
+                    balance,
+                )?;
+            }
+            protocol.treasury = update.treasury;
+        }
+
+        emit!(ConfigUpdated {
+            executed_by: ctx.accounts.executor.key(),
+            stake_required: protocol.stake_required,
+            voting_period: protocol.voting_period,
+            quorum_percentage: protocol.quorum_percentage,
+            reward_per_vote: protocol.reward_per_vote,
+            early_voter_bonus: protocol.early_voter_bonus,
+            treasury: protocol.treasury,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_config_update(
+        ctx: Context<CancelConfigUpdate>,
+    ) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let clock = Clock::get()?;
+
+        require!(
+            protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
+            ErrorCode::Unauthorized
+        );
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::CancelConfigUpdate {
+                proposed_at: ctx.accounts.config_update.proposed_at,
+            },
+            clock.unix_timestamp,
+        )?;
+
+        emit!(ConfigUpdateCancelled {
+            cancelled_by: ctx.accounts.admin.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        action: ProposalAction,
+        description_hash: [u8; 32],
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(
+            ctx.accounts.proposer_token_account.amount >= constants::MIN_PROPOSAL_STAKE,
+            ErrorCode::InsufficientProposalStake
+        );
+        ctx.accounts.protocol_state.validate_proposal_action(&action)?;
+
+        // Locked until voting ends, so one balance backs one open proposal
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.proposer_token_account.to_account_info(),
+                    to: ctx.accounts.proposal_escrow.to_account_info(),
+                    authority: ctx.accounts.proposer.to_account_info(),
+                },
+            ),
+            constants::MIN_PROPOSAL_STAKE,
+        )?;
+
+        let protocol = &mut ctx.accounts.protocol_state;
+        let proposal = &mut ctx.accounts.proposal;
+
+        proposal.id = protocol.proposal_count;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.action = action.clone();
+        proposal.description_hash = description_hash;
+        proposal.for_votes = 0;
+        proposal.against_votes = 0;
+        proposal.created_at = clock.unix_timestamp;
+        proposal.voting_ends_at = clock.unix_timestamp + constants::GOVERNANCE_VOTING_PERIOD;
+        proposal.executable_at = proposal.voting_ends_at + constants::GOVERNANCE_EXECUTION_DELAY;
+        proposal.executed = false;
+        proposal.bump = *ctx.bumps.get("proposal").unwrap();
+
+        protocol.proposal_count = protocol.proposal_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        emit!(ProposalCreated {
+            proposal_id: proposal.id,
+            proposer: proposal.proposer,
+            action,
+            description_hash,
+            voting_ends_at: proposal.voting_ends_at,
+            executable_at: proposal.executable_at,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cast_governance_vote(
+        ctx: Context<CastGovernanceVote>,
+        support: bool,
+        stake_amount: u64,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp < ctx.accounts.proposal.voting_ends_at,
+            ErrorCode::VotingPeriodEnded
+        );
+        require!(
+            stake_amount > 0 && stake_amount <= constants::MAX_STAKE_PER_USER,
+            ErrorCode::InvalidStakeAmount
+        );
+
+        // Governance weight is GUARD locked in an escrow of its own, apart
+        // from content vote stakes
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.voter_token_account.to_account_info(),
+                    to: ctx.accounts.governance_escrow.to_account_info(),
+                    authority: ctx.accounts.voter.to_account_info(),
+                },
+            ),
+            stake_amount,
+        )?;
+
+        let proposal = &mut ctx.accounts.proposal;
+        let governance_vote = &mut ctx.accounts.governance_vote;
+
+        if support {
+            proposal.for_votes = proposal.for_votes
+                .checked_add(stake_amount)
+                .ok_or(ErrorCode::CalculationError)?;
+        } else {
+            proposal.against_votes = proposal.against_votes
+                .checked_add(stake_amount)
+                .ok_or(ErrorCode::CalculationError)?;
+        }
+
+        governance_vote.proposal = proposal.key();
+        governance_vote.voter = ctx.accounts.voter.key();
+        governance_vote.support = support;
+        governance_vote.stake_amount = stake_amount;
+        governance_vote.bump = *ctx.bumps.get("governance_vote").unwrap();
+
+        emit!(GovernanceVoteCast {
+            proposal_id: proposal.id,
+            voter: governance_vote.voter,
+            support,
+            stake_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn execute_proposal(
+        ctx: Context<ExecuteProposal>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
+
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(
+            clock.unix_timestamp >= proposal.executable_at,
+            ErrorCode::TimelockNotExpired
+        );
+
+        let total_votes = proposal.for_votes
+            .checked_add(proposal.against_votes)
+            .ok_or(ErrorCode::CalculationError)?;
+        let quorum = (ctx.accounts.guard_mint.supply as u128
+            * constants::GOVERNANCE_QUORUM_PERCENTAGE as u128)
+            / 100;
+        require!(total_votes as u128 >= quorum, ErrorCode::QuorumNotReached);
+        require!(
+            proposal.for_votes > proposal.against_votes,
+            ErrorCode::ProposalDefeated
+        );
+
+        // Re-validate against the current state, which may have moved on since creation
+        protocol.validate_proposal_action(&proposal.action)?;
+
+        match proposal.action.clone() {
+            ProposalAction::UpdateConfig { config } => {
+                protocol.apply_config(&config);
+            }
+            ProposalAction::TreasurySpend { recipient, amount } => {
+                let treasury = ctx.accounts.treasury
+                    .as_ref()
+                    .ok_or(ErrorCode::InvalidTreasury)?;
+                let recipient_account = ctx.accounts.recipient_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::InvalidRecipient)?;
+                require!(
+                    recipient_account.key() == recipient,
+                    ErrorCode::InvalidRecipient
+                );
+
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: treasury.to_account_info(),
+                            to: recipient_account.to_account_info(),
+                            authority: protocol.to_account_info(),
+                        },
+                        &[&[b"protocol", &[protocol.bump]]],
+                    ),
+                    amount,
+                )?;
+
+                emit!(TreasuryMovement {
+                    kind: TreasuryMovementKind::GovernanceSpend,
+                    amount,
+                    counterparty: recipient,
+                    authorized_by: proposal.key(),
+                    balance: treasury.amount
+                        .checked_sub(amount)
+                        .ok_or(ErrorCode::InsufficientTreasuryBalance)?,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+            ProposalAction::AddOracle { oracle } => {
+                protocol.oracles.push(oracle);
+                emit!(OracleAdded {
+                    oracle,
+                    added_by: proposal.key(),
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+            ProposalAction::RemoveOracle { oracle } => {
+                protocol.oracles.retain(|&existing| existing != oracle);
+                emit!(OracleRemoved {
+                    oracle,
+                    removed_by: proposal.key(),
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+        }
+
+        proposal.executed = true;
+
+        emit!(ProposalExecuted {
+            proposal_id: proposal.id,
+            action: proposal.action.clone(),
+            for_votes: proposal.for_votes,
+            against_votes: proposal.against_votes,
+            executed_by: ctx.accounts.executor.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the proposer's locked stake once voting on the proposal ends,
+    /// whatever the outcome.
+    pub fn withdraw_proposal_stake(
+        ctx: Context<WithdrawProposalStake>,
+    ) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let proposal = &ctx.accounts.proposal;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp >= proposal.voting_ends_at,
+            ErrorCode::VotingPeriodActive
+        );
+
+        let stake_amount = ctx.accounts.proposal_escrow.amount;
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.proposal_escrow.to_account_info(),
+                    to: ctx.accounts.proposer_token_account.to_account_info(),
+                    authority: protocol.to_account_info(),
+                },
+                &[&[b"protocol", &[protocol.bump]]],
+            ),
+            stake_amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.proposal_escrow.to_account_info(),
+                destination: ctx.accounts.proposer.to_account_info(),
+                authority: protocol.to_account_info(),
+            },
+            &[&[b"protocol", &[protocol.bump]]],
+        ))?;
+
+        emit!(ProposalStakeWithdrawn {
+            proposal_id: proposal.id,
+            proposer: proposal.proposer,
+            stake_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw_governance_stake(
+        ctx: Context<WithdrawGovernanceStake>,
+    ) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let proposal = &ctx.accounts.proposal;
+        let governance_vote = &ctx.accounts.governance_vote;
+        let clock = Clock::get()?;
+
+        require!(
+            clock.unix_timestamp >= proposal.voting_ends_at,
+            ErrorCode::VotingPeriodActive
+        );
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.governance_escrow.to_account_info(),
+                    to: ctx.accounts.voter_token_account.to_account_info(),
+                    authority: protocol.to_account_info(),
+                },
+                &[&[b"protocol", &[protocol.bump]]],
+            ),
+            governance_vote.stake_amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.governance_escrow.to_account_info(),
+                destination: ctx.accounts.voter.to_account_info(),
+                authority: protocol.to_account_info(),
+            },
+            &[&[b"protocol", &[protocol.bump]]],
+        ))?;
+
+        emit!(GovernanceStakeWithdrawn {
+            proposal_id: proposal.id,
+            voter: governance_vote.voter,
+            stake_amount: governance_vote.stake_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn propose_admin_transfer(
+        ctx: Context<ProposeAdminTransfer>,
+        new_admin: Pubkey,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+
+        require!(
+            protocol.admin == ctx.accounts.admin.key(),
+            ErrorCode::Unauthorized
+        );
+        require!(
+            new_admin != Pubkey::default() && new_admin != protocol.admin,
+            ErrorCode::InvalidAdmin
+        );
+
+        protocol.pending_admin = Some(new_admin);
+
+        emit!(AdminTransferProposed {
+            current_admin: protocol.admin,
+            pending_admin: new_admin,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_admin_transfer(
+        ctx: Context<AcceptAdminTransfer>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+
+        require!(
+            protocol.pending_admin == Some(ctx.accounts.new_admin.key()),
+            ErrorCode::Unauthorized
+        );
+
+        let previous_admin = protocol.admin;
+        protocol.admin = ctx.accounts.new_admin.key();
+        protocol.pending_admin = None;
+
+        emit!(AdminTransferred {
+            previous_admin,
+            new_admin: protocol.admin,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn renounce_admin(
+        ctx: Context<RenounceAdmin>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+
+        require!(
+            protocol.admin == ctx.accounts.admin.key(),
+            ErrorCode::Unauthorized
+        );
+
+        protocol.admin = Pubkey::default();
+        protocol.pending_admin = None;
+
+        emit!(AdminRenounced {
+            previous_admin: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn propose_admin_action(
+        ctx: Context<ProposeAdminAction>,
+        kind: AdminActionKind,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let action = &mut ctx.accounts.admin_action;
+        let clock = Clock::get()?;
+
+        require!(
+            protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
+            ErrorCode::Unauthorized
+        );
+
+        action.id = protocol.admin_action_count;
+        action.kind = kind.clone();
+        action.proposer = ctx.accounts.admin.key();
+        action.approvals = vec![ctx.accounts.admin.key()];
+        action.created_at = clock.unix_timestamp;
+        action.executed = false;
+        action.bump = *ctx.bumps.get("admin_action").unwrap();
+
+        protocol.admin_action_count = protocol.admin_action_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        emit!(AdminActionProposed {
+            action_id: action.id,
+            kind,
+            proposer: action.proposer,
+            threshold: protocol.admin_threshold,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn approve_admin_action(
+        ctx: Context<ApproveAdminAction>,
+    ) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let action = &mut ctx.accounts.admin_action;
+        let approver = ctx.accounts.admin.key();
+        let clock = Clock::get()?;
+
+        require!(
+            protocol.emergency_admins.contains(&approver),
+            ErrorCode::Unauthorized
+        );
+        require!(!action.executed, ErrorCode::AdminActionAlreadyExecuted);
+        require!(
+            clock.unix_timestamp <= action.created_at + constants::ADMIN_ACTION_TTL,
+            ErrorCode::AdminActionExpired
+        );
+        require!(
+            !action.approvals.contains(&approver),
+            ErrorCode::AdminActionAlreadyApproved
+        );
+
+        action.approvals.push(approver);
+
+        emit!(AdminActionApproved {
+            action_id: action.id,
+            approver,
+            approvals: action.approvals.len() as u8,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_admin_threshold(
+        ctx: Context<SetAdminThreshold>,
+        threshold: u8,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+
+        require!(
+            threshold > 0 && threshold as usize <= protocol.emergency_admins.len(),
+            ErrorCode::InvalidAdminThreshold
+        );
+        require!(
+            protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
+            ErrorCode::Unauthorized
+        );
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::SetThreshold { threshold },
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        let previous_threshold = protocol.admin_threshold;
+        protocol.admin_threshold = threshold;
+
+        emit!(AdminThresholdUpdated {
+            previous_threshold,
+            new_threshold: threshold,
+            updated_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn initialize_roles(
+        ctx: Context<InitializeRoles>,
+    ) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let roles = &mut ctx.accounts.roles;
+        let admin = ctx.accounts.admin.key();
+        let timestamp = Clock::get()?.unix_timestamp;
+
+        require!(protocol.admin == admin, ErrorCode::Unauthorized);
+
+        roles.bump = *ctx.bumps.get("roles").unwrap();
+
+        // The primary admin starts with every role and emergency admins keep
+        // the ability to pause; everything else is granted explicitly
+        let mut grants = Role::ALL.iter().map(|&role| (admin, role)).collect::<Vec<_>>();
+        grants.extend(protocol.emergency_admins.iter().map(|&account| (account, Role::Pauser)));
+
+        for (account, role) in grants {
+            if roles.has_role(&account, role) {
+                continue;
+            }
+            roles.grant(account, role)?;
+            emit!(RoleGranted {
+                account,
+                role,
+                granted_by: admin,
+                timestamp,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn grant_role(
+        ctx: Context<UpdateRole>,
+        account: Pubkey,
+        role: Role,
+    ) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let roles = &mut ctx.accounts.roles;
+
+        require!(
+            protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
+            ErrorCode::Unauthorized
+        );
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::GrantRole { account, role },
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        roles.grant(account, role)?;
+
+        emit!(RoleGranted {
+            account,
+            role,
+            granted_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn revoke_role(
+        ctx: Context<UpdateRole>,
+        account: Pubkey,
+        role: Role,
+    ) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let roles = &mut ctx.accounts.roles;
+
+        require!(
+            protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
+            ErrorCode::Unauthorized
+        );
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::RevokeRole { account, role },
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        roles.revoke(&account, role)?;
+
+        emit!(RoleRevoked {
+            account,
+            role,
+            revoked_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_pause_flags(
+        ctx: Context<SetPauseFlags>,
+        pause_flags: u8,
+        paused_content_types: u8,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let admin = ctx.accounts.admin.key();
+
+        require!(
+            pause_flags & !constants::PAUSE_ALL == 0,
+            ErrorCode::InvalidPauseFlags
+        );
+
+        // Pausing more stays single-signer; lifting any switch is an unpause
+        let lifts_pause = protocol.pause_flags & !pause_flags != 0
+            || protocol.paused_content_types & !paused_content_types != 0;
+        if lifts_pause {
+            require!(
+                protocol.emergency_admins.contains(&admin),
+                ErrorCode::Unauthorized
+            );
+            protocol.authorize_admin_action(
+                &admin,
+                ctx.accounts.admin_action.as_mut(),
+                &AdminActionKind::SetPauseFlags {
+                    pause_flags,
+                    paused_content_types,
+                },
+                Clock::get()?.unix_timestamp,
+            )?;
+        } else {
+            require_pauser(protocol, ctx.accounts.roles.as_ref(), &admin)?;
+        }
+
+        let previous_pause_flags = protocol.pause_flags;
+        let previous_paused_content_types = protocol.paused_content_types;
+        protocol.pause_flags = pause_flags;
+        protocol.paused_content_types = paused_content_types;
+
+        emit!(PauseFlagsUpdated {
+            previous_pause_flags,
+            pause_flags,
+            previous_paused_content_types,
+            paused_content_types,
+            updated_by: admin,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Stops or resumes submissions made through one app. Like
+    /// `set_pause_flags`, pausing is single-signer and resuming is not.
+    pub fn set_app_paused(
+        ctx: Context<SetAppPaused>,
+        paused: bool,
+    ) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let app = &mut ctx.accounts.app_registration;
+        let admin = ctx.accounts.admin.key();
+        let clock = Clock::get()?;
+
+        if paused {
+            require_pauser(protocol, ctx.accounts.roles.as_ref(), &admin)?;
+        } else {
+            require!(
+                protocol.emergency_admins.contains(&admin),
+                ErrorCode::Unauthorized
+            );
+            protocol.authorize_admin_action(
+                &admin,
+                ctx.accounts.admin_action.as_mut(),
+                &AdminActionKind::UnpauseApp { app: app.key() },
+                clock.unix_timestamp,
+            )?;
+        }
+
+        app.paused = paused;
+
+        emit!(AppPauseUpdated {
+            app: app.key(),
+            paused,
+            updated_by: admin,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_circuit_breaker_config(
+        ctx: Context<SetCircuitBreakerConfig>,
+        config: CircuitBreakerConfig,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+
+        ctx.accounts.roles.require_role(&ctx.accounts.admin.key(), Role::ConfigManager)?;
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::SetCircuitBreakerConfig { config },
+            Clock::get()?.unix_timestamp,
+        )?;
+        config.validate()?;
+
+        protocol.circuit_breaker = config;
+
+        emit!(CircuitBreakerConfigUpdated {
+            config,
+            updated_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn reset_circuit_breaker(
+        ctx: Context<ResetCircuitBreaker>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+
+        require!(
+            protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
+            ErrorCode::Unauthorized
+        );
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::ResetCircuitBreaker,
+            Clock::get()?.unix_timestamp,
+        )?;
+
+        protocol.circuit_breaker_tripped = false;
+        // Start a fresh dominance window so the same votes do not re-trip it
+        protocol.window_vote_stake = 0;
+        protocol.dominant_voter = Pubkey::default();
+        protocol.dominant_voter_weight = 0;
+        protocol.dominant_voter_stake = 0;
+
+        emit!(CircuitBreakerReset {
+            reset_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_treasury_config(
+        ctx: Context<SetTreasuryConfig>,
+        config: TreasuryConfig,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let clock = Clock::get()?;
+
+        ctx.accounts.roles.require_role(&ctx.accounts.admin.key(), Role::ConfigManager)?;
+        protocol.authorize_admin_action(
+            &ctx.accounts.admin.key(),
+            ctx.accounts.admin_action.as_mut(),
+            &AdminActionKind::SetTreasuryConfig { config },
+            clock.unix_timestamp,
+        )?;
+        config.validate()?;
+
+        protocol.treasury_config = config;
+        // A new limit applies to a fresh period
+        protocol.treasury_period_start = clock.unix_timestamp;
+        protocol.treasury_period_spent = 0;
+
+        emit!(TreasuryConfigUpdated {
+            config,
+            updated_by: ctx.accounts.admin.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Discretionary spend by a treasury manager, capped per period. Larger
+    /// spends go through governance as a `TreasurySpend` proposal.
+    pub fn withdraw_treasury(
+        ctx: Context<WithdrawTreasury>,
+        amount: u64,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let treasury = &ctx.accounts.treasury;
+        let clock = Clock::get()?;
+
+        protocol.check_active_status()?;
+        ctx.accounts.roles.require_role(&ctx.accounts.admin.key(), Role::TreasuryManager)?;
+        require!(amount > 0, ErrorCode::InvalidAmount);
+        require!(treasury.amount >= amount, ErrorCode::InsufficientTreasuryBalance);
+
+        protocol.record_treasury_spend(amount, clock.unix_timestamp)?;
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: treasury.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: protocol.to_account_info(),
+                },
+                &[&[b"protocol", &[protocol.bump]]],
+            ),
+            amount,
+        )?;
+
+        emit!(TreasuryMovement {
+            kind: TreasuryMovementKind::Withdrawal,
+            amount,
+            counterparty: ctx.accounts.recipient_token_account.key(),
+            authorized_by: ctx.accounts.admin.key(),
+            balance: treasury.amount - amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the voter's stake and the vote's rent. An epoch share still
+    /// unclaimed after the grace period is forfeited to the treasury, so one
+    /// absent voter cannot keep the content open.
+    pub fn close_vote(
+        ctx: Context<CloseVote>,
+    ) -> Result<()> {
+        let protocol = &mut ctx.accounts.protocol_state;
+        let content = &mut ctx.accounts.content;
+        let epoch = &mut ctx.accounts.reward_epoch;
+        let vote_account = &mut ctx.accounts.vote_account;
+        let clock = Clock::get()?;
+
+        protocol.check_active_status()?;
+        content.check_closable(clock.unix_timestamp)?;
+
+        if !vote_account.epoch_reward_claimed && !epoch.merkle_root_posted {
+            require!(epoch.is_closed, ErrorCode::VoteNotSettled);
+            let share = epoch.reward_for(vote_account.reward_weight)?;
+
+            protocol.reserved_rewards = protocol.reserved_rewards
+                .checked_sub(share)
+                .ok_or(ErrorCode::CalculationError)?;
+            vote_account.epoch_reward_claimed = true;
+            epoch.claimed_weight = epoch.claimed_weight
+                .checked_add(vote_account.reward_weight)
+                .ok_or(ErrorCode::CalculationError)?;
+
+            if share > 0 {
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.reward_vault.to_account_info(),
+                            to: ctx.accounts.treasury.to_account_info(),
+                            authority: protocol.to_account_info(),
+                        },
+                        &[&[b"protocol", &[protocol.bump]]],
+                    ),
+                    share,
+                )?;
+
+                emit!(TreasuryMovement {
+                    kind: TreasuryMovementKind::ForfeitedReward,
+                    amount: share,
+                    counterparty: vote_account.voter,
+                    authorized_by: ctx.accounts.closer.key(),
+                    balance: ctx.accounts.treasury.amount
+                        .checked_add(share)
+                        .ok_or(ErrorCode::CalculationError)?,
+                    timestamp: clock.unix_timestamp,
+                });
+            }
+        }
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vote_escrow.to_account_info(),
+                    to: ctx.accounts.voter_token_account.to_account_info(),
+                    authority: protocol.to_account_info(),
+                },
+                &[&[b"protocol", &[protocol.bump]]],
+            ),
+            vote_account.stake_amount,
+        )?;
+        token::close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.vote_escrow.to_account_info(),
+                destination: ctx.accounts.voter.to_account_info(),
+                authority: protocol.to_account_info(),
+            },
+            &[&[b"protocol", &[protocol.bump]]],
+        ))?;
+
+        content.closed_vote_count = content.closed_vote_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        emit!(VoteClosed {
+            vote: vote_account.key(),
+            content_id: content.key(),
+            voter: vote_account.voter,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the content's rent to whoever paid for it once all of its votes
+    /// are closed, leaving a `Verdict` behind as the permanent record.
+    pub fn close_content(
+        ctx: Context<CloseContent>,
+    ) -> Result<()> {
+        let content = &ctx.accounts.content;
+        let verdict = &mut ctx.accounts.verdict;
+        let clock = Clock::get()?;
+
+        ctx.accounts.protocol_state.check_active_status()?;
+        content.check_closable(clock.unix_timestamp)?;
+        require!(
+            content.closed_vote_count == content.vote_count,
+            ErrorCode::OpenVotesRemaining
+        );
+
+        verdict.content_id = content.key();
+        verdict.content_hash = content.content_hash;
+        verdict.content_type = content.content_type;
+        verdict.status = content.status;
+        verdict.ai_score = content.ai_score;
+        verdict.approve_votes = content.approve_votes;
+        verdict.reject_votes = content.reject_votes;
+        verdict.finalized_at = content.finalized_at;
+        verdict.bump = *ctx.bumps.get("verdict").unwrap();
+
+        emit!(ContentClosed {
+            content_id: content.key(),
+            content_hash: content.content_hash,
+            status: content.status,
+            approve_votes: content.approve_votes,
+            reject_votes: content.reject_votes,
+            finalized_at: content.finalized_at,
+            rent_recipient: content.rent_payer,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn register_app(
+        ctx: Context<RegisterApp>,
+        callback_program: Pubkey,
+        callback_discriminator: [u8; 8],
+    ) -> Result<()> {
+        let app = &mut ctx.accounts.app_registration;
+        let clock = Clock::get()?;
+
+        require!(callback_program != crate::ID, ErrorCode::InvalidCallbackProgram);
+
+        app.authority = ctx.accounts.authority.key();
+        app.callback_program = callback_program;
+        app.callback_discriminator = callback_discriminator;
+        app.created_at = clock.unix_timestamp;
+        app.bump = *ctx.bumps.get("app_registration").unwrap();
+        app.paused = false;
+
+        emit!(AppCallbackUpdated {
+            app: app.key(),
+            authority: app.authority,
+            callback_program,
+            callback_discriminator,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Only affects content finalized after the change; pending callbacks go
+    /// to whatever is registered when they are dispatched.
+    pub fn update_app_callback(
+        ctx: Context<UpdateAppCallback>,
+        callback_program: Pubkey,
+        callback_discriminator: [u8; 8],
+    ) -> Result<()> {
+        let app = &mut ctx.accounts.app_registration;
+
+        require!(callback_program != crate::ID, ErrorCode::InvalidCallbackProgram);
+
+        app.callback_program = callback_program;
+        app.callback_discriminator = callback_discriminator;
+
+        emit!(AppCallbackUpdated {
+            app: app.key(),
+            authority: app.authority,
+            callback_program,
+            callback_discriminator,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Calls the app's callback with the serialized `VerdictInfo`, signed by
+    /// the `[b"callback"]` PDA so the callee can tell it came from us. That
+    /// PDA has no authority over any funds, unlike the protocol PDA. Any extra
+    /// accounts the callee needs are passed through as remaining accounts.
+    pub fn dispatch_callback<'info>(
+        ctx: Context<'_, '_, '_, 'info, DispatchCallback<'info>>,
+    ) -> Result<()> {
+        // Marked before the call; a revert rolls the mark back with it
+        ctx.accounts.content.take_pending_callback()?;
+
+        let content = &ctx.accounts.content;
+        let app = &ctx.accounts.app_registration;
+        let callback_authority = &ctx.accounts.callback_authority;
+
+        let mut data = app.callback_discriminator.to_vec();
+        content.verdict_info(content.key()).serialize(&mut data)?;
+
+        let mut account_infos = vec![
+            callback_authority.to_account_info(),
+            content.to_account_info(),
+        ];
+        account_infos.extend(ctx.remaining_accounts.iter().cloned());
+        account_infos.push(ctx.accounts.callback_program.to_account_info());
+
+        invoke_signed(
+            &Instruction {
+                program_id: app.callback_program,
+                accounts: callback_account_metas(
+                    callback_authority.key(),
+                    content.key(),
+                    ctx.remaining_accounts,
+                ),
+                data,
+            },
+            &account_infos,
+            &[&[b"callback", &[*ctx.bumps.get("callback_authority").unwrap()]]],
+        )?;
+
+        emit!(CallbackDelivered {
+            content_id: ctx.accounts.content.key(),
+            app: app.key(),
+            callback_program: app.callback_program,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// A reverted CPI cannot be observed on-chain, so a callback still pending
+    /// once the dispatch window has passed is recorded as failed.
+    pub fn mark_callback_failed(
+        ctx: Context<MarkCallbackFailed>,
+    ) -> Result<()> {
+        let content = &mut ctx.accounts.content;
+        let clock = Clock::get()?;
+
+        require!(
+            content.callback_status == CallbackStatus::Pending,
+            ErrorCode::NoPendingCallback
+        );
+        require!(
+            clock.unix_timestamp >= content.finalized_at + constants::CALLBACK_DISPATCH_WINDOW,
+            ErrorCode::CallbackWindowOpen
+        );
+
+        content.callback_status = CallbackStatus::Failed;
+
+        emit!(CallbackFailed {
+            content_id: content.key(),
+            app: content.app.unwrap_or_default(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Read-only verdict lookup for other programs, answered from the content
+    /// account while it is open and from its `Verdict` once closed. The result
+    /// is returned through `set_return_data`.
+    pub fn get_verdict(
+        ctx: Context<GetVerdict>,
+    ) -> Result<VerdictInfo> {
+        let content_info = ctx.accounts.content.to_account_info();
+        if content_info.owner == &crate::ID && !content_info.data_is_empty() {
+            let content = Account::<Content>::try_from(&content_info)?;
+            return Ok(content.verdict_info(content_info.key()));
+        }
+
+        let verdict_info = ctx.accounts.verdict.to_account_info();
+        require!(
+            verdict_info.owner == &crate::ID && !verdict_info.data_is_empty(),
+            ErrorCode::ContentNotFound
+        );
+        let verdict = Account::<Verdict>::try_from(&verdict_info)?;
+        Ok(verdict.info())
+    }
+
+    pub fn migrate_protocol_state(
+        ctx: Context<MigrateProtocolState>,
+    ) -> Result<()> {
+        let info = ctx.accounts.protocol_state.to_account_info();
+        let clock = Clock::get()?;
+
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            let version =
+                check_legacy_account(&data, &ProtocolState::discriminator(), ProtocolState::VERSION_OFFSET)?;
+            require!(version == 3, ErrorCode::UnsupportedAccountVersion);
+            ProtocolStateV3::deserialize(&mut &data[8..])?
+        };
+        require!(
+            legacy.emergency_admins.contains(&ctx.accounts.admin.key()),
+            ErrorCode::Unauthorized
+        );
+
+        let from_version = legacy.version;
+        let mut protocol = legacy.into_current(clock.unix_timestamp);
+        // v3 only recorded an external address that nothing ever paid into
+        protocol.treasury = ctx.accounts.treasury.key();
+
+        // Accounts created before epoch accounting never got these
+        ctx.accounts.reward_epoch.initialize(
+            0,
+            clock.unix_timestamp,
+            *ctx.bumps.get("reward_epoch").unwrap(),
+        );
+
+        grow_account(
+            &info,
+            &ctx.accounts.admin,
+            &ctx.accounts.system_program,
+            ProtocolState::SIZE,
+        )?;
+        protocol.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: constants::PROGRAM_VERSION,
+            migrated_by: ctx.accounts.admin.key(),
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_content(
+        ctx: Context<MigrateContent>,
+    ) -> Result<()> {
+        let info = ctx.accounts.content.to_account_info();
+
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            let version =
+                check_legacy_account(&data, &Content::discriminator(), Content::VERSION_OFFSET)?;
+            require!(version == 3, ErrorCode::UnsupportedAccountVersion);
+            ContentV3::deserialize(&mut &data[8..])?
+        };
+        let from_version = legacy.version;
+        let content = legacy.into_current();
+
+        grow_account(
+            &info,
+            &ctx.accounts.payer,
+            &ctx.accounts.system_program,
+            Content::SIZE,
+        )?;
+        content.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version,
+            to_version: constants::PROGRAM_VERSION,
+            migrated_by: ctx.accounts.payer.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Moves a v3 vote's stake out of the vault every v3 vote was pooled in
+    /// and into the vote's own escrow, so `close_vote` can return it. v3 votes
+    /// carried no version and are recognised by their size. The pooled
+    /// vault's address was never recorded, so only an emergency admin may
+    /// name it.
+    pub fn migrate_vote(
+        ctx: Context<MigrateVote>,
+    ) -> Result<()> {
+        let protocol = &ctx.accounts.protocol_state;
+        let info = ctx.accounts.vote_account.to_account_info();
+
+        require!(
+            protocol.emergency_admins.contains(&ctx.accounts.admin.key()),
+            ErrorCode::Unauthorized
+        );
+
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == Vote::discriminator(),
+                ErrorCode::AccountDidNotDeserialize
+            );
+            require!(data.len() == VoteV3::SIZE, ErrorCode::AccountAlreadyMigrated);
+            VoteV3::deserialize(&mut &data[8..])?
+        };
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.legacy_vote_vault.to_account_info(),
+                    to: ctx.accounts.vote_escrow.to_account_info(),
+                    authority: protocol.to_account_info(),
+                },
+                &[&[b"protocol", &[protocol.bump]]],
+            ),
+            legacy.stake_amount,
+        )?;
+
+        grow_account(
+            &info,
+            &ctx.accounts.admin,
+            &ctx.accounts.system_program,
+            Vote::SIZE,
+        )?;
+        legacy.into_current().try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(AccountMigrated {
+            account: info.key(),
+            from_version: 3,
+            to_version: constants::PROGRAM_VERSION,
+            migrated_by: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// The callback authority is the only signer. Pass-through accounts keep
+/// their writability but never their signer flag, so a callee cannot act with
+/// a signature the dispatcher only meant for this program.
+fn callback_account_metas(
+    callback_authority: Pubkey,
+    content: Pubkey,
+    remaining_accounts: &[AccountInfo],
+) -> Vec<AccountMeta> {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(callback_authority, true),
+        AccountMeta::new_readonly(content, false),
+    ];
+    for account in remaining_accounts {
+        accounts.push(if account.is_writable {
+            AccountMeta::new(account.key(), false)
+        } else {
+            AccountMeta::new_readonly(account.key(), false)
+        });
+    }
+    accounts
+}
+
+/// Emergency admins may always pause, so pausing works before the roles
+/// account exists. Anyone else needs the `Pauser` role.
+fn require_pauser(protocol: &ProtocolState, roles: Option<&Account<Roles>>, signer: &Pubkey) -> Result<()> {
+    if protocol.emergency_admins.contains(signer) {
+        return Ok(());
+    }
+    roles
+        .ok_or(ErrorCode::MissingRole)?
+        .require_role(signer, Role::Pauser)
+}
+
+/// Returns the layout version of a legacy account this program can migrate.
+fn check_legacy_account(data: &[u8], discriminator: &[u8; 8], version_offset: usize) -> Result<u8> {
+    require!(
+        data.len() > version_offset && data[..8] == discriminator[..],
+        ErrorCode::AccountDidNotDeserialize
+    );
+    let version = data[version_offset];
+    require!(
+        version != constants::PROGRAM_VERSION,
+        ErrorCode::AccountAlreadyMigrated
+    );
+    require!(version == 3, ErrorCode::UnsupportedAccountVersion);
+    Ok(version)
+}
+
+/// Creates a program-owned PDA the way `init` does. An address that already
+/// holds lamports cannot be created outright, so it is topped up to rent
+/// exemption, allocated and assigned instead.
+fn create_pda_account<'info>(
+    payer: &Signer<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    let rent_exempt = Rent::get()?.minimum_balance(space);
+    let current_lamports = account.lamports();
+
+    if current_lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+                &[signer_seeds],
+            ),
+            rent_exempt,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    let shortfall = rent_exempt.saturating_sub(current_lamports);
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Allocate {
+                account_to_allocate: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Assign {
+                account_to_assign: account.clone(),
+            },
+            &[signer_seeds],
+        ),
+        &crate::ID,
+    )
+}
+
+/// Tops up rent for `new_size` from `payer` and reallocs, zeroing the new bytes.
+fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    new_size: usize,
+) -> Result<()> {
+    let required_lamports = Rent::get()?.minimum_balance(new_size);
+    let shortfall = required_lamports.saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.realloc(new_size, true)?;
+    Ok(())
+}
+
+/// Pays `treasury_config.keeper_fee` to the caller of a permissionless upkeep
+/// instruction. Skipped when no fee is set or the caller passed no accounts
+/// for it; capped at the treasury balance so upkeep never fails for lack of
+/// funds. Not counted against the spend limit, like governance spends.
+fn pay_keeper_fee<'info>(
+    protocol: &Account<'info, ProtocolState>,
+    treasury: &Option<Account<'info, TokenAccount>>,
+    keeper_token_account: &Option<Account<'info, TokenAccount>>,
+    token_program: &Program<'info, Token>,
+    current_timestamp: i64,
+) -> Result<()> {
+    let (Some(treasury), Some(keeper_token_account)) = (treasury, keeper_token_account) else {
+        return Ok(());
+    };
+    let amount = protocol.treasury_config.keeper_fee.min(treasury.amount);
+    if amount == 0 {
+        return Ok(());
+    }
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: treasury.to_account_info(),
+                to: keeper_token_account.to_account_info(),
+                authority: protocol.to_account_info(),
+            },
+            &[&[b"protocol", &[protocol.bump]]],
+        ),
+        amount,
+    )?;
+
+    emit!(TreasuryMovement {
+        kind: TreasuryMovementKind::KeeperFee,
+        amount,
+        counterparty: keeper_token_account.key(),
+        authorized_by: keeper_token_account.owner,
+        balance: treasury.amount - amount,
+        timestamp: current_timestamp,
+    });
+
+    Ok(())
+}
+
+fn trailing_average(average: u32, latest: u32) -> u32 {
+    let days = constants::TRAILING_ACTIVITY_DAYS as u64;
+    ((average as u64 * (days - 1) + latest as u64) / days) as u32
+}
+
+fn apply_early_bonus(amount: u64, bonus_percentage: u8) -> Result<u64> {
+    let boosted = (amount as u128)
+        .checked_mul(100 + bonus_percentage as u128)
+        .ok_or(ErrorCode::CalculationError)?
         .checked_div(100)
         .ok_or(ErrorCode::CalculationError)?;
+    u64::try_from(boosted).map_err(|_| error!(ErrorCode::CalculationError))
+}
+
+fn vest_reward(
+    protocol: &mut ProtocolState,
+    vesting_account: Option<&mut Account<VestingAccount>>,
+    amount: u64,
+    current_timestamp: i64,
+) -> Result<()> {
+    let vesting = vesting_account.ok_or(ErrorCode::VestingAccountRequired)?;
+
+    let tranche = vesting.deposit(amount, current_timestamp, &protocol.reward_vesting)?;
+    // The tokens stay in the reward vault until released or forfeited
+    protocol.vesting_balance = protocol.vesting_balance
+        .checked_add(amount)
+        .ok_or(ErrorCode::CalculationError)?;
+
+    emit!(RewardVested {
+        owner: vesting.owner,
+        amount,
+        locked_amount: vesting.locked_amount(current_timestamp),
+        cliff_timestamp: tranche.cliff_timestamp(),
+        end_timestamp: tranche.end_timestamp(),
+        timestamp: current_timestamp,
+    });
+
+    Ok(())
+}
+
+fn transfer_stake_tokens(ctx: &Context<CastVote>, stake_amount: u64) -> Result<()> {
+    token::transfer(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.voter_token_account.to_account_info(),
+                to: ctx.accounts.vote_escrow.to_account_info(),
+                authority: ctx.accounts.voter.to_account_info(),
+            },
+        ),
+        stake_amount,
+    )
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = ProtocolState::SIZE,
+        seeds = [b"protocol"],
+        bump,
+    )]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        init,
+        payer = admin,
+        space = RewardEpoch::SIZE,
+        seeds = [b"reward_epoch", &0u64.to_le_bytes()],
+        bump,
+    )]
+    pub reward_epoch: Account<'info, RewardEpoch>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"reward_vault"],
+        bump,
+        token::mint = guard_mint,
+        token::authority = protocol_state,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    pub guard_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"treasury_vault"],
+        bump,
+        token::mint = guard_mint,
+        token::authority = protocol_state,
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CastVote<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(mut)]
+    pub content: Account<'info, Content>,
+    #[account(
+        init,
+        payer = voter,
+        space = Vote::SIZE,
+        seeds = [b"vote", content.key().as_ref(), voter.key().as_ref()],
+        bump,
+    )]
+    pub vote_account: Account<'info, Vote>,
+    #[account(
+        mut,
+        seeds = [b"reward_epoch", &protocol_state.current_epoch.to_le_bytes()],
+        bump = reward_epoch.bump,
+    )]
+    pub reward_epoch: Account<'info, RewardEpoch>,
+    #[account(
+        init,
+        payer = voter,
+        seeds = [b"vote_escrow", vote_account.key().as_ref()],
+        bump,
+        token::mint = guard_mint,
+        token::authority = protocol_state,
+    )]
+    pub vote_escrow: Account<'info, TokenAccount>,
+    #[account(seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(address = reward_vault.mint @ ErrorCode::InvalidMint)]
+    pub guard_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = voter_token_account.owner == voter.key() @ ErrorCode::Unauthorized,
+        constraint = voter_token_account.mint == guard_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeDecision<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"content", content.content_hash.as_ref()],
+        bump = content.bump,
+    )]
+    pub content: Account<'info, Content>,
+    /// Both this and `keeper_token_account` are only needed to collect the keeper fee.
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub keeper_token_account: Option<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseRewardEpoch<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"reward_epoch", &protocol_state.current_epoch.to_le_bytes()],
+        bump = reward_epoch.bump,
+    )]
+    pub reward_epoch: Account<'info, RewardEpoch>,
+    #[account(
+        init,
+        payer = payer,
+        space = RewardEpoch::SIZE,
+        seeds = [b"reward_epoch", &(protocol_state.current_epoch + 1).to_le_bytes()],
+        bump,
+    )]
+    pub next_reward_epoch: Account<'info, RewardEpoch>,
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"emission_schedule"], bump = emission_schedule.bump)]
+    pub emission_schedule: Option<Account<'info, EmissionSchedule>>,
+    /// Required with an emission schedule, and to collect the keeper fee.
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub keeper_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeEmissionSchedule<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        init,
+        payer = admin,
+        space = EmissionSchedule::SIZE,
+        seeds = [b"emission_schedule"],
+        bump,
+    )]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEmissionSchedule<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(mut, seeds = [b"emission_schedule"], bump = emission_schedule.bump)]
+    pub emission_schedule: Account<'info, EmissionSchedule>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimEpochRewards<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(constraint = content.key() == vote_account.content_id)]
+    pub content: Account<'info, Content>,
+    #[account(mut, constraint = vote_account.voter == voter.key() @ ErrorCode::Unauthorized)]
+    pub vote_account: Account<'info, Vote>,
+    #[account(
+        mut,
+        seeds = [b"reward_epoch", &vote_account.reward_epoch.to_le_bytes()],
+        bump = reward_epoch.bump,
+    )]
+    pub reward_epoch: Account<'info, RewardEpoch>,
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(mut, constraint = voter_token_account.owner == voter.key() @ ErrorCode::Unauthorized)]
+    pub voter_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"vesting", voter.key().as_ref()], bump = vesting_account.bump)]
+    pub vesting_account: Option<Account<'info, VestingAccount>>,
+    pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch: u64, root: [u8; 32], total_amount: u64, num_leaves: u32)]
+pub struct PostMerkleRoot<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        mut,
+        seeds = [b"reward_epoch", &epoch.to_le_bytes()],
+        bump = reward_epoch.bump,
+    )]
+    pub reward_epoch: Account<'info, RewardEpoch>,
+    #[account(
+        init,
+        payer = admin,
+        space = MerkleDistribution::space(num_leaves),
+        seeds = [b"merkle_distribution", &epoch.to_le_bytes()],
+        bump,
+    )]
+    pub merkle_distribution: Account<'info, MerkleDistribution>,
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMerkleReward<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"merkle_distribution", &merkle_distribution.epoch.to_le_bytes()],
+        bump = merkle_distribution.bump,
+    )]
+    pub merkle_distribution: Account<'info, MerkleDistribution>,
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = claimant_token_account.owner == claimant.key() @ ErrorCode::Unauthorized)]
+    pub claimant_token_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"vesting", claimant.key().as_ref()], bump = vesting_account.bump)]
+    pub vesting_account: Option<Account<'info, VestingAccount>>,
+    pub claimant: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeConfigUpdate<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        init,
+        payer = admin,
+        space = PendingConfigUpdate::SIZE,
+        seeds = [b"config_update"],
+        bump,
+    )]
+    pub config_update: Account<'info, PendingConfigUpdate>,
+    #[account(seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    /// Only the protocol may move funds out of the treasury, and the reward
+    /// pool must stay separate from it.
+    #[account(
+        constraint = new_treasury.owner == protocol_state.key() @ ErrorCode::InvalidTreasury,
+        constraint = new_treasury.delegate.is_none() @ ErrorCode::InvalidTreasury,
+        constraint = new_treasury.close_authority.is_none() @ ErrorCode::InvalidTreasury,
+        constraint = new_treasury.key() != reward_vault.key() @ ErrorCode::InvalidTreasury,
+        constraint = new_treasury.mint == reward_vault.mint @ ErrorCode::InvalidMint,
+    )]
+    pub new_treasury: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteConfigUpdate<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"config_update"],
+        bump = config_update.bump,
+    )]
+    pub config_update: Account<'info, PendingConfigUpdate>,
+    /// CHECK: receives the rent of the closed proposal
+    #[account(mut, address = config_update.proposed_by)]
+    pub proposer: UncheckedAccount<'info>,
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(mut, address = config_update.treasury @ ErrorCode::InvalidTreasury)]
+    pub new_treasury: Account<'info, TokenAccount>,
+    pub executor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CancelConfigUpdate<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"config_update"],
+        bump = config_update.bump,
+    )]
+    pub config_update: Account<'info, PendingConfigUpdate>,
+    /// CHECK: receives the rent of the closed proposal
+    #[account(mut, address = config_update.proposed_by)]
+    pub proposer: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        init,
+        payer = proposer,
+        space = Proposal::SIZE,
+        seeds = [b"proposal", &protocol_state.proposal_count.to_le_bytes()],
+        bump,
+    )]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        init,
+        payer = proposer,
+        seeds = [b"proposal_escrow", proposal.key().as_ref()],
+        bump,
+        token::mint = guard_mint,
+        token::authority = protocol_state,
+    )]
+    pub proposal_escrow: Account<'info, TokenAccount>,
+    #[account(seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(address = reward_vault.mint @ ErrorCode::InvalidMint)]
+    pub guard_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = proposer_token_account.owner == proposer.key() @ ErrorCode::Unauthorized,
+        constraint = proposer_token_account.mint == guard_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub proposer_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CastGovernanceVote<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        init,
+        payer = voter,
+        space = GovernanceVote::SIZE,
+        seeds = [b"governance_vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump,
+    )]
+    pub governance_vote: Account<'info, GovernanceVote>,
+    #[account(
+        init,
+        payer = voter,
+        seeds = [b"governance_escrow", governance_vote.key().as_ref()],
+        bump,
+        token::mint = guard_mint,
+        token::authority = protocol_state,
+    )]
+    pub governance_escrow: Account<'info, TokenAccount>,
+    #[account(seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(address = reward_vault.mint @ ErrorCode::InvalidMint)]
+    pub guard_mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        constraint = voter_token_account.owner == voter.key() @ ErrorCode::Unauthorized,
+        constraint = voter_token_account.mint == guard_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(mut, seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    /// Quorum is measured against GUARD's supply, never a mint the caller picks
+    #[account(address = reward_vault.mint @ ErrorCode::InvalidMint)]
+    pub guard_mint: Account<'info, Mint>,
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+    pub executor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawProposalStake<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(mut, seeds = [b"proposal_escrow", proposal.key().as_ref()], bump)]
+    pub proposal_escrow: Account<'info, TokenAccount>,
+    #[account(mut, constraint = proposer_token_account.owner == proposer.key() @ ErrorCode::Unauthorized)]
+    pub proposer_token_account: Account<'info, TokenAccount>,
+    #[account(mut, address = proposal.proposer @ ErrorCode::Unauthorized)]
+    pub proposer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawGovernanceStake<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"proposal", &proposal.id.to_le_bytes()], bump = proposal.bump)]
+    pub proposal: Account<'info, Proposal>,
+    #[account(
+        mut,
+        close = voter,
+        seeds = [b"governance_vote", proposal.key().as_ref(), voter.key().as_ref()],
+        bump = governance_vote.bump,
+    )]
+    pub governance_vote: Account<'info, GovernanceVote>,
+    #[account(mut, seeds = [b"governance_escrow", governance_vote.key().as_ref()], bump)]
+    pub governance_escrow: Account<'info, TokenAccount>,
+    #[account(mut, constraint = voter_token_account.owner == voter.key() @ ErrorCode::Unauthorized)]
+    pub voter_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdminTransfer<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdminTransfer<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    pub new_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RenounceAdmin<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardVesting<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenVestingAccount<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = VestingAccount::SIZE,
+        seeds = [b"vesting", owner.key().as_ref()],
+        bump,
+    )]
+    pub vesting_account: Account<'info, VestingAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseVested<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"vesting", owner.key().as_ref()],
+        bump = vesting_account.bump,
+    )]
+    pub vesting_account: Account<'info, VestingAccount>,
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, constraint = owner_token_account.owner == owner.key() @ ErrorCode::Unauthorized)]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    pub owner: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ForfeitVesting<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        mut,
+        seeds = [b"vesting", vesting_account.owner.as_ref()],
+        bump = vesting_account.bump,
+    )]
+    pub vesting_account: Account<'info, VestingAccount>,
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[event]
+pub struct VoteCast {
+    pub content_id: Pubkey,
+    pub voter: Pubkey,
+    pub vote_type: VoteType,
+    pub stake_amount: u64,
+    pub timestamp: i64,
+    pub vote_number: u32,
+    pub reward_epoch: u64,
+    pub reward_weight: u64,
+    pub early_bonus_percentage: u8,
+}
+
+#[event]
+pub struct DecisionFinalized {
+    pub content_id: Pubkey,
+    pub final_status: ContentStatus,
+    pub approve_votes: u64,
+    pub reject_votes: u64,
+    pub total_stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmissionScheduleUpdated {
+    pub initial_emission: u64,
+    pub decay: EmissionDecay,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EpochEmissionFunded {
+    pub epoch: u64,
+    pub scheduled_amount: u64,
+    pub funded_amount: u64,
+    pub total_emitted: u64,
+    pub next_scheduled_emission: u64,
+    pub schedule_end_timestamp: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigUpdateProposed {
+    pub proposed_by: Pubkey,
+    pub stake_required: u64,
+    pub voting_period: i64,
+    pub quorum_percentage: u8,
+    pub reward_per_vote: u64,
+    pub early_voter_bonus: EarlyVoterBonus,
+    pub treasury: Pubkey,
+    pub eta: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub executed_by: Pubkey,
+    pub stake_required: u64,
+    pub voting_period: i64,
+    pub quorum_percentage: u8,
+    pub reward_per_vote: u64,
+    pub early_voter_bonus: EarlyVoterBonus,
+    pub treasury: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigUpdateCancelled {
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalCreated {
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    pub description_hash: [u8; 32],
+    pub voting_ends_at: i64,
+    pub executable_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GovernanceVoteCast {
+    pub proposal_id: u64,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub stake_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+    pub proposal_id: u64,
+    pub action: ProposalAction,
+    pub for_votes: u64,
+    pub against_votes: u64,
+    pub executed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalStakeWithdrawn {
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub stake_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GovernanceStakeWithdrawn {
+    pub proposal_id: u64,
+    pub voter: Pubkey,
+    pub stake_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardVestingUpdated {
+    pub enabled: bool,
+    pub cliff_seconds: i64,
+    pub duration_seconds: i64,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardVested {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub locked_amount: u64,
+    pub cliff_timestamp: i64,
+    pub end_timestamp: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestedRewardsReleased {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub remaining_locked: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestingForfeited {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub forfeited_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerkleRootPosted {
+    pub epoch: u64,
+    pub root: [u8; 32],
+    pub total_amount: u64,
+    pub num_leaves: u32,
+    pub forfeited_amount: u64,
+    pub posted_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerkleRewardClaimed {
+    pub epoch: u64,
+    pub index: u32,
+    pub claimant: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardDistributed {
+    pub voter: Pubkey,
+    pub content_id: Pubkey,
+    pub epoch: u64,
+    pub amount: u64,
+    pub early_bonus_percentage: u8,
+    pub timestamp: i64,
+}
+
+/// Emitted by the retired per-vote `claim_rewards`. Kept so older
+/// transactions still decode.
+#[event]
+pub struct RewardsClaimed {
+    pub voter: Pubkey,
+    pub content_id: Pubkey,
+    pub reward_amount: u64,
+    pub early_bonus_percentage: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardsDistributed {
+    pub epoch: u64,
+    pub total_amount: u64,
+    pub total_weight: u64,
+    pub reward_per_weight: u128,
+    pub timestamp: i64,
+}
+
+#[derive(Accounts)]
+pub struct PauseProtocol<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    /// Only needed by pausers who are not emergency admins.
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Option<Account<'info, Roles>>,
+    #[account(signer)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnpauseProtocol<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    #[account(signer)]
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AddEmergencyAdmin<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    #[account(signer)]
+    pub admin: Signer<'info>,
+    pub new_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveEmergencyAdmin<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    #[account(signer)]
+    pub admin: Signer<'info>,
+    /// CHECK: only compared against the emergency admin list
+    pub admin_to_remove: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdminAction<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        init,
+        payer = admin,
+        space = AdminAction::SIZE,
+        seeds = [b"admin_action", &protocol_state.admin_action_count.to_le_bytes()],
+        bump,
+    )]
+    pub admin_action: Account<'info, AdminAction>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveAdminAction<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Account<'info, AdminAction>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRoles<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        init,
+        payer = admin,
+        space = Roles::SIZE,
+        seeds = [b"roles"],
+        bump,
+    )]
+    pub roles: Account<'info, Roles>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRole<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(mut, seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPauseFlags<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    /// Only needed by pausers who are not emergency admins.
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Option<Account<'info, Roles>>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAppPaused<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    /// Only needed by pausers who are not emergency admins.
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Option<Account<'info, Roles>>,
+    #[account(
+        mut,
+        seeds = [b"app", app_registration.authority.as_ref()],
+        bump = app_registration.bump,
+    )]
+    pub app_registration: Account<'info, AppRegistration>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCircuitBreakerConfig<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResetCircuitBreaker<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetTreasuryConfig<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = recipient_token_account.mint == treasury.mint @ ErrorCode::InvalidMint,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(content_data: ContentData)]
+pub struct SubmitContent<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        init,
+        payer = submitter,
+        space = Content::SIZE,
+        seeds = [b"content", content_data.content_hash.as_ref()],
+        bump,
+    )]
+    pub content: Account<'info, Content>,
+    /// CHECK: only checked to be empty; a closed content address keeps its verdict
+    #[account(
+        seeds = [b"verdict", content.key().as_ref()],
+        bump,
+        constraint = verdict.data_is_empty() @ ErrorCode::ContentAlreadyDecided,
+    )]
+    pub verdict: UncheckedAccount<'info>,
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = submitter_token_account.owner == submitter.key() @ ErrorCode::Unauthorized,
+        constraint = submitter_token_account.mint == treasury.mint @ ErrorCode::InvalidMint,
+    )]
+    pub submitter_token_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [b"app", submitter.key().as_ref()],
+        bump = app_registration.bump,
+    )]
+    pub app_registration: Option<Account<'info, AppRegistration>>,
+    #[account(mut)]
+    pub submitter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitContentBatch<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = submitter_token_account.owner == submitter.key() @ ErrorCode::Unauthorized,
+        constraint = submitter_token_account.mint == treasury.mint @ ErrorCode::InvalidMint,
+    )]
+    pub submitter_token_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [b"app", submitter.key().as_ref()],
+        bump = app_registration.bump,
+    )]
+    pub app_registration: Option<Account<'info, AppRegistration>>,
+    #[account(mut)]
+    pub submitter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeContentTree<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(seeds = [b"roles"], bump = roles.bump)]
+    pub roles: Account<'info, Roles>,
+    #[account(
+        init,
+        payer = admin,
+        space = ContentTree::SIZE,
+        seeds = [b"content_tree", &protocol_state.content_tree_count.to_le_bytes()],
+        bump,
+    )]
+    pub content_tree: Account<'info, ContentTree>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SubmitContentCompressed<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"content_tree", &content_tree.id.to_le_bytes()],
+        bump = content_tree.bump,
+    )]
+    pub content_tree: Account<'info, ContentTree>,
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = submitter_token_account.owner == submitter.key() @ ErrorCode::Unauthorized,
+        constraint = submitter_token_account.mint == treasury.mint @ ErrorCode::InvalidMint,
+    )]
+    pub submitter_token_account: Account<'info, TokenAccount>,
+    pub submitter: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyCompressedContent<'info> {
+    #[account(seeds = [b"content_tree", &content_tree.id.to_le_bytes()], bump = content_tree.bump)]
+    pub content_tree: Account<'info, ContentTree>,
+}
+
+#[derive(Accounts)]
+#[instruction(leaf: CompressedContent)]
+pub struct DecompressContent<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        seeds = [b"content_tree", &leaf.tree_id.to_le_bytes()],
+        bump = content_tree.bump,
+    )]
+    pub content_tree: Account<'info, ContentTree>,
+    #[account(
+        init,
+        payer = payer,
+        space = Content::SIZE,
+        seeds = [b"content", leaf.content_data.content_hash.as_ref()],
+        bump,
+    )]
+    pub content: Account<'info, Content>,
+    /// CHECK: only checked to be empty; a closed content address keeps its verdict
+    #[account(
+        seeds = [b"verdict", content.key().as_ref()],
+        bump,
+        constraint = verdict.data_is_empty() @ ErrorCode::ContentAlreadyDecided,
+    )]
+    pub verdict: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVote<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(mut, address = vote_account.content_id @ ErrorCode::InvalidContent)]
+    pub content: Account<'info, Content>,
+    #[account(
+        mut,
+        close = voter,
+        seeds = [b"vote", content.key().as_ref(), voter.key().as_ref()],
+        bump,
+    )]
+    pub vote_account: Account<'info, Vote>,
+    #[account(
+        mut,
+        seeds = [b"reward_epoch", &vote_account.reward_epoch.to_le_bytes()],
+        bump = reward_epoch.bump,
+    )]
+    pub reward_epoch: Account<'info, RewardEpoch>,
+    #[account(mut, seeds = [b"vote_escrow", vote_account.key().as_ref()], bump)]
+    pub vote_escrow: Account<'info, TokenAccount>,
+    #[account(mut, seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut, address = protocol_state.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = voter_token_account.owner == voter.key() @ ErrorCode::Unauthorized,
+        constraint = voter_token_account.mint == vote_escrow.mint @ ErrorCode::InvalidMint,
+    )]
+    pub voter_token_account: Account<'info, TokenAccount>,
+    /// CHECK: the voter who paid for the vote account; receives its rent and stake
+    #[account(mut, address = vote_account.voter @ ErrorCode::Unauthorized)]
+    pub voter: UncheckedAccount<'info>,
+    /// Anyone may close a vote once the grace period is over.
+    pub closer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseContent<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"content", content.content_hash.as_ref()],
+        bump = content.bump,
+    )]
+    pub content: Account<'info, Content>,
+    #[account(
+        init,
+        payer = payer,
+        space = Verdict::SIZE,
+        seeds = [b"verdict", content.key().as_ref()],
+        bump,
+    )]
+    pub verdict: Account<'info, Verdict>,
+    /// CHECK: whoever paid for the content account; only receives its rent
+    #[account(mut, address = content.rent_payer @ ErrorCode::Unauthorized)]
+    pub rent_payer: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterApp<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = AppRegistration::SIZE,
+        seeds = [b"app", authority.key().as_ref()],
+        bump,
+    )]
+    pub app_registration: Account<'info, AppRegistration>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAppCallback<'info> {
+    #[account(
+        mut,
+        seeds = [b"app", authority.key().as_ref()],
+        bump = app_registration.bump,
+        has_one = authority,
+    )]
+    pub app_registration: Account<'info, AppRegistration>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DispatchCallback<'info> {
+    /// CHECK: signs the callback; owns nothing
+    #[account(seeds = [b"callback"], bump)]
+    pub callback_authority: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        constraint = content.app == Some(app_registration.key()) @ ErrorCode::InvalidCallbackProgram,
+    )]
+    pub content: Account<'info, Content>,
+    pub app_registration: Account<'info, AppRegistration>,
+    /// CHECK: must be the registered callback program
+    #[account(
+        executable,
+        address = app_registration.callback_program @ ErrorCode::InvalidCallbackProgram,
+    )]
+    pub callback_program: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MarkCallbackFailed<'info> {
+    #[account(mut)]
+    pub content: Account<'info, Content>,
+}
+
+#[derive(Accounts)]
+pub struct GetVerdict<'info> {
+    /// CHECK: either a live `Content` account or, once closed, an empty address
+    pub content: UncheckedAccount<'info>,
+    /// CHECK: read only when the content account has been closed
+    #[account(seeds = [b"verdict", content.key().as_ref()], bump)]
+    pub verdict: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateProtocolState<'info> {
+    /// CHECK: still in a legacy layout; checked and deserialized by hand
+    #[account(mut, seeds = [b"protocol"], bump, owner = crate::ID)]
+    pub protocol_state: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = RewardEpoch::SIZE,
+        seeds = [b"reward_epoch", &0u64.to_le_bytes()],
+        bump,
+    )]
+    pub reward_epoch: Account<'info, RewardEpoch>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"reward_vault"],
+        bump,
+        token::mint = guard_mint,
+        token::authority = protocol_state,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"treasury_vault"],
+        bump,
+        token::mint = guard_mint,
+        token::authority = protocol_state,
+    )]
+    pub treasury: Account<'info, TokenAccount>,
+    pub guard_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateContent<'info> {
+    /// CHECK: still in a legacy layout; checked and deserialized by hand
+    #[account(mut, owner = crate::ID)]
+    pub content: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVote<'info> {
+    #[account(seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    /// CHECK: still in a legacy layout; checked and deserialized by hand
+    #[account(mut, owner = crate::ID)]
+    pub vote_account: UncheckedAccount<'info>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"vote_escrow", vote_account.key().as_ref()],
+        bump,
+        token::mint = guard_mint,
+        token::authority = protocol_state,
+    )]
+    pub vote_escrow: Account<'info, TokenAccount>,
+    /// The v3 vault that held every vote's stake.
+    #[account(
+        mut,
+        constraint = legacy_vote_vault.owner == protocol_state.key() @ ErrorCode::Unauthorized,
+        constraint = legacy_vote_vault.mint == guard_mint.key() @ ErrorCode::InvalidMint,
+    )]
+    pub legacy_vote_vault: Account<'info, TokenAccount>,
+    #[account(seeds = [b"reward_vault"], bump)]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(address = reward_vault.mint @ ErrorCode::InvalidMint)]
+    pub guard_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SetAdminThreshold<'info> {
+    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
+    pub protocol_state: Account<'info, ProtocolState>,
+    #[account(
+        mut,
+        seeds = [b"admin_action", &admin_action.id.to_le_bytes()],
+        bump = admin_action.bump,
+    )]
+    pub admin_action: Option<Account<'info, AdminAction>>,
+    pub admin: Signer<'info>,
+}
+
+#[error_code]
+pub enum ErrorCode {
+    // Previous errors
+    #[msg("Unsupported content type")]
+    UnsupportedContentType,
+    #[msg("AI confidence below the submission threshold")]
+    LowAIConfidence,
+    #[msg("Stake is still locked")]
+    StakeStillLocked,
+    #[msg("Voting too frequently")]
+    VotingTooFrequent,
+    #[msg("Content hash too long")]
+    ContentHashTooLong,
+    #[msg("Voting period is still active")]
+    VotingPeriodActive,
+    #[msg("Rewards already claimed")]
+    RewardsAlreadyClaimed,
+    #[msg("Invalid quorum percentage")]
+    InvalidQuorumPercentage,
+    #[msg("Invalid voting period")]
+    InvalidVotingPeriod,
+    #[msg("Invalid reward per vote")]
+    InvalidRewardPerVote,
+    #[msg("Maximum emergency admins reached")]
+    MaxEmergencyAdminsReached,
+    #[msg("Cannot remove the last emergency admin")]
+    CannotRemoveLastAdmin,
+    #[msg("Reward distribution not due yet")]
+    RewardDistributionNotDue,
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    #[msg("Calculation overflow")]
+    CalculationError,
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Reward epoch is already closed")]
+    RewardEpochClosed,
+    #[msg("Reward epoch has not been closed yet")]
+    RewardEpochNotClosed,
+    #[msg("Content decision has not been finalized")]
+    ContentNotFinalized,
+    #[msg("Reward epoch is settled by a Merkle distribution")]
+    RewardEpochSettledByMerkle,
+    #[msg("Reward epoch already has claims")]
+    RewardEpochAlreadyClaimed,
+    #[msg("Invalid Merkle leaf count")]
+    InvalidMerkleLeafCount,
+    #[msg("Merkle distribution exceeds the epoch reward pool")]
+    MerkleTotalExceedsPool,
+    #[msg("Invalid Merkle proof")]
+    InvalidMerkleProof,
+    #[msg("Invalid emission schedule")]
+    InvalidEmissionSchedule,
+    #[msg("Emission schedule and treasury accounts are required")]
+    EmissionScheduleRequired,
+    #[msg("Invalid treasury account")]
+    InvalidTreasury,
+    #[msg("Invalid early voter bonus configuration")]
+    InvalidEarlyVoterBonus,
+    #[msg("Invalid vesting configuration")]
+    InvalidVestingConfig,
+    #[msg("A vesting account is required while reward vesting is enabled")]
+    VestingAccountRequired,
+    #[msg("Nothing to release")]
+    NothingToRelease,
+    #[msg("Timelock has not expired yet")]
+    TimelockNotExpired,
+    #[msg("Invalid stake amount")]
+    InvalidStakeAmount,
+    #[msg("Voting period has ended")]
+    VotingPeriodEnded,
+    #[msg("Quorum not reached")]
+    QuorumNotReached,
+    #[msg("Insufficient GUARD balance to create a proposal")]
+    InsufficientProposalStake,
+    #[msg("Invalid proposal")]
+    InvalidProposal,
+    #[msg("Proposal did not pass")]
+    ProposalDefeated,
+    #[msg("Proposal already executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Maximum oracles reached")]
+    MaxOraclesReached,
+    #[msg("Invalid token mint")]
+    InvalidMint,
+    #[msg("Invalid recipient account")]
+    InvalidRecipient,
+    #[msg("Invalid admin")]
+    InvalidAdmin,
+    #[msg("Invalid admin approval threshold")]
+    InvalidAdminThreshold,
+    #[msg("An approved admin action is required")]
+    AdminActionRequired,
+    #[msg("Admin action does not match this instruction")]
+    AdminActionMismatch,
+    #[msg("Admin action already executed")]
+    AdminActionAlreadyExecuted,
+    #[msg("Admin action already approved by this admin")]
+    AdminActionAlreadyApproved,
+    #[msg("Admin action has expired")]
+    AdminActionExpired,
+    #[msg("Not enough admin approvals")]
+    InsufficientApprovals,
+    #[msg("Signer is missing the required role")]
+    MissingRole,
+    #[msg("Role already granted")]
+    RoleAlreadyGranted,
+    #[msg("Maximum role members reached")]
+    MaxRoleMembersReached,
+    #[msg("This part of the protocol is paused")]
+    SubsystemPaused,
+    #[msg("This content type is paused")]
+    ContentTypePaused,
+    #[msg("Invalid pause flags")]
+    InvalidPauseFlags,
+    #[msg("Circuit breaker tripped; submissions and votes are restricted")]
+    CircuitBreakerActive,
+    #[msg("Invalid circuit breaker configuration")]
+    InvalidCircuitBreakerConfig,
+    #[msg("Account uses an older layout; run the matching migrate instruction first")]
+    AccountNotMigrated,
+    #[msg("Account is already migrated")]
+    AccountAlreadyMigrated,
+    #[msg("Unsupported account version")]
+    UnsupportedAccountVersion,
+    #[msg("Account data could not be read")]
+    AccountDidNotDeserialize,
+    #[msg("Invalid treasury configuration")]
+    InvalidTreasuryConfig,
+    #[msg("Treasury spend limit for this period exceeded")]
+    TreasurySpendLimitExceeded,
+    #[msg("Insufficient treasury balance")]
+    InsufficientTreasuryBalance,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Vote does not belong to this content")]
+    InvalidContent,
+    #[msg("Settled accounts cannot be closed until the grace period ends")]
+    CloseGracePeriodActive,
+    #[msg("A vote cannot be closed while its reward epoch is open")]
+    VoteNotSettled,
+    #[msg("All votes must be closed before the content")]
+    OpenVotesRemaining,
+    #[msg("Content with this hash has already been decided")]
+    ContentAlreadyDecided,
+    #[msg("No content or verdict exists at this address")]
+    ContentNotFound,
+    #[msg("Invalid callback program")]
+    InvalidCallbackProgram,
+    #[msg("Content has no pending callback")]
+    NoPendingCallback,
+    #[msg("Callback can still be dispatched")]
+    CallbackWindowOpen,
+    #[msg("Daily submission limit reached")]
+    DailyLimitExceeded,
+    #[msg("Batch must contain between one and the maximum number of items")]
+    InvalidBatchSize,
+    #[msg("Batch accounts do not match the submitted items")]
+    InvalidBatchAccounts,
+    #[msg("Invalid content tree depth")]
+    InvalidTreeDepth,
+    #[msg("Content tree is full")]
+    ContentTreeFull,
+    #[msg("Root is not in the content tree's recent history")]
+    UnknownTreeRoot,
+    #[msg("Only low-risk content can be submitted compressed")]
+    NotLowRisk,
+    #[msg("Role is retired and can no longer be granted")]
+    RoleRetired,
+    #[msg("Submissions through this app are paused")]
+    AppPaused,
+    #[msg("Content has already been finalized")]
+    ContentAlreadyFinalized,
+}
+
+#[event]
+pub struct ProtocolInitialized {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub stake_required: u64,
+    pub voting_period: i64,
+    pub quorum_percentage: u8,
+    pub reward_per_vote: u64,
+    pub version: u8,
+    pub timestamp: i64,
+}
+
+/// `voting_period` and `app` were appended after the original fields, so
+/// decoders written for the shorter event still read everything they know.
+#[event]
+pub struct ContentSubmitted {
+    pub content_id: Pubkey,
+    pub submitter: Pubkey,
+    pub content_hash: [u8; 32],
+    pub content_type: ContentType,
+    pub ai_score: u8,
+    pub timestamp: i64,
+    pub voting_period: i64,
+    pub app: Option<Pubkey>,
+}
+
+#[event]
+pub struct ProtocolPaused {
+    pub paused_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProtocolUnpaused {
+    pub unpaused_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerTripped {
+    pub reason: CircuitBreakerReason,
+    pub observed: u64,
+    pub threshold: u64,
+    pub voter: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerReset {
+    pub reset_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContentTreeCreated {
+    pub tree: Pubkey,
+    pub tree_id: u64,
+    pub depth: u8,
+    pub capacity: u64,
+    pub created_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContentCompressed {
+    pub tree: Pubkey,
+    pub leaf: CompressedContent,
+    pub root: [u8; 32],
+}
+
+#[event]
+pub struct ContentDecompressed {
+    pub content_id: Pubkey,
+    pub tree: Pubkey,
+    pub leaf_index: u32,
+    pub escalated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContentBatchSubmitted {
+    pub submitter: Pubkey,
+    pub count: u32,
+    pub total_fee: u64,
+    pub daily_submission_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AppCallbackUpdated {
+    pub app: Pubkey,
+    pub authority: Pubkey,
+    pub callback_program: Pubkey,
+    pub callback_discriminator: [u8; 8],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CallbackDelivered {
+    pub content_id: Pubkey,
+    pub app: Pubkey,
+    pub callback_program: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CallbackFailed {
+    pub content_id: Pubkey,
+    pub app: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VoteClosed {
+    pub vote: Pubkey,
+    pub content_id: Pubkey,
+    pub voter: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ContentClosed {
+    pub content_id: Pubkey,
+    pub content_hash: [u8; 32],
+    pub status: ContentStatus,
+    pub approve_votes: u64,
+    pub reject_votes: u64,
+    pub finalized_at: i64,
+    pub rent_recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryConfigUpdated {
+    pub config: TreasuryConfig,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted for every transfer into or out of the treasury vault; `balance` is
+/// the vault balance after the transfer.
+#[event]
+pub struct TreasuryMovement {
+    pub kind: TreasuryMovementKind,
+    pub amount: u64,
+    pub counterparty: Pubkey,
+    pub authorized_by: Pubkey,
+    pub balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerConfigUpdated {
+    pub config: CircuitBreakerConfig,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseFlagsUpdated {
+    pub previous_pause_flags: u8,
+    pub pause_flags: u8,
+    pub previous_paused_content_types: u8,
+    pub paused_content_types: u8,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AppPauseUpdated {
+    pub app: Pubkey,
+    pub paused: bool,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyAdminAdded {
+    pub new_admin: Pubkey,
+    pub added_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EmergencyAdminRemoved {
+    pub removed_admin: Pubkey,
+    pub removed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminActionProposed {
+    pub action_id: u64,
+    pub kind: AdminActionKind,
+    pub proposer: Pubkey,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminActionApproved {
+    pub action_id: u64,
+    pub approver: Pubkey,
+    pub approvals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminActionExecuted {
+    pub action_id: u64,
+    pub executed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminThresholdUpdated {
+    pub previous_threshold: u8,
+    pub new_threshold: u8,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleGranted {
+    pub account: Pubkey,
+    pub role: Role,
+    pub granted_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleRevoked {
+    pub account: Pubkey,
+    pub role: Role,
+    pub revoked_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OracleAdded {
+    pub oracle: Pubkey,
+    pub added_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OracleRemoved {
+    pub oracle: Pubkey,
+    pub removed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub migrated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferProposed {
+    pub current_admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminTransferred {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminRenounced {
+    pub previous_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolConfig {
+    pub stake_required: u64,
+    pub voting_period: i64,
+    pub quorum_percentage: u8,
+    pub reward_per_vote: u64,
+    pub early_voter_bonus: EarlyVoterBonus,
+}
+
+impl ProtocolConfig {
+    pub const SIZE: usize = 8 + // stake_required
+        8 + // voting_period
+        1 + // quorum_percentage
+        8 + // reward_per_vote
+        EarlyVoterBonus::SIZE; // early_voter_bonus
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.quorum_percentage >= constants::MIN_QUORUM_PERCENTAGE
+                && self.quorum_percentage <= constants::MAX_QUORUM_PERCENTAGE,
+            ErrorCode::InvalidQuorumPercentage
+        );
+        require!(
+            self.voting_period >= constants::MIN_VOTING_PERIOD
+                && self.voting_period <= constants::MAX_VOTING_PERIOD,
+            ErrorCode::InvalidVotingPeriod
+        );
+        require!(
+            self.reward_per_vote > 0,
+            ErrorCode::InvalidRewardPerVote
+        );
+        self.early_voter_bonus.validate()
+    }
+}
+
+#[account]
+pub struct PendingConfigUpdate {
+    pub config: ProtocolConfig,
+    pub treasury: Pubkey,
+    pub proposed_by: Pubkey,
+    pub proposed_at: i64,
+    pub eta: i64,
+    pub bump: u8,
+}
+
+impl PendingConfigUpdate {
+    pub const SIZE: usize = 8 + // discriminator
+        ProtocolConfig::SIZE + // config
+        32 + // treasury
+        32 + // proposed_by
+        8 + // proposed_at
+        8 + // eta
+        1; // bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum EarlyBonusCurve {
+    None,
+    /// Decays linearly to zero over the first `window_percentage` of the voting period.
+    TimeDecay { window_percentage: u8 },
+    /// Decays linearly to zero over the first `window_votes` votes on the content.
+    VoteCountDecay { window_votes: u32 },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct EarlyVoterBonus {
+    pub max_bonus_percentage: u8,
+    pub curve: EarlyBonusCurve,
+}
+
+impl EarlyVoterBonus {
+    pub const SIZE: usize = 1 + // max_bonus_percentage
+        (1 + 4); // curve
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.max_bonus_percentage <= constants::EARLY_VOTER_BONUS,
+            ErrorCode::InvalidEarlyVoterBonus
+        );
+        match self.curve {
+            EarlyBonusCurve::None => {}
+            EarlyBonusCurve::TimeDecay { window_percentage } => require!(
+                window_percentage > 0 && window_percentage <= 100,
+                ErrorCode::InvalidEarlyVoterBonus
+            ),
+            EarlyBonusCurve::VoteCountDecay { window_votes } => require!(
+                window_votes > 0,
+                ErrorCode::InvalidEarlyVoterBonus
+            ),
+        }
+        Ok(())
+    }
+
+    /// Bonus for a vote cast `elapsed` seconds into the voting period as the
+    /// `vote_index`-th vote (zero based) on the content.
+    pub fn bonus_percentage(&self, elapsed: i64, voting_period: i64, vote_index: u32) -> u8 {
+        let (position, window) = match self.curve {
+            EarlyBonusCurve::None => return 0,
+            EarlyBonusCurve::TimeDecay { window_percentage } => (
+                elapsed.max(0) as u128,
+                (voting_period.max(0) as u128 * window_percentage as u128) / 100,
+            ),
+            EarlyBonusCurve::VoteCountDecay { window_votes } => {
+                (vote_index as u128, window_votes as u128)
+            }
+        };
+        if position >= window {
+            return 0;
+        }
+        ((self.max_bonus_percentage as u128 * (window - position)) / window) as u8
+    }
+}
+
+/// Implements the account traits `#[account]` would, except that
+/// deserialization also requires the layout version at `VERSION_OFFSET` to be
+/// current. `#[account]` decodes the whole layout first, so a stale account
+/// would fail as garbage before any version field could be compared; reading
+/// the byte from raw data lets it fail with `AccountNotMigrated` instead.
+/// Unchecked deserialization, used by `init`, skips the version.
+macro_rules! versioned_account {
+    ($name:ident, $discriminator:expr) => {
+        impl anchor_lang::Discriminator for $name {
+            const DISCRIMINATOR: [u8; 8] = $discriminator;
+        }
+
+        impl anchor_lang::Owner for $name {
+            fn owner() -> Pubkey {
+                crate::ID
+            }
+        }
+
+        impl anchor_lang::AccountSerialize for $name {
+            fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<()> {
+                writer
+                    .write_all(&<$name as anchor_lang::Discriminator>::DISCRIMINATOR)
+                    .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize)?;
+                AnchorSerialize::serialize(self, writer)
+                    .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize)?;
+                Ok(())
+            }
+        }
+
+        impl anchor_lang::AccountDeserialize for $name {
+            fn try_deserialize(buf: &mut &[u8]) -> Result<Self> {
+                require!(
+                    buf.len() >= 8,
+                    anchor_lang::error::ErrorCode::AccountDiscriminatorNotFound
+                );
+                require!(
+                    buf[..8] == <$name as anchor_lang::Discriminator>::DISCRIMINATOR,
+                    anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+                );
+                require!(
+                    buf.get(Self::VERSION_OFFSET) == Some(&constants::PROGRAM_VERSION),
+                    ErrorCode::AccountNotMigrated
+                );
+                Self::try_deserialize_unchecked(buf)
+            }
+
+            fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+                let mut data: &[u8] = &buf[8..];
+                AnchorDeserialize::deserialize(&mut data)
+                    .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into())
+            }
+        }
+    };
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ProtocolState {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub stake_required: u64,
+    pub voting_period: i64,
+    pub quorum_percentage: u8,
+    pub reward_per_vote: u64,
+    pub is_paused: bool,
+    pub daily_submission_count: u32,
+    pub daily_vote_count: u32,
+    pub last_reset_timestamp: i64,
+    pub last_reward_distribution_timestamp: i64,
+    pub version: u8,
+    pub bump: u8,
+    pub emergency_admins: Vec<Pubkey>,
+    pub current_epoch: u64,
+    pub reserved_rewards: u64,
+    pub total_rewards_distributed: u64,
+    pub emission_schedule_configured: bool,
+    pub early_voter_bonus: EarlyVoterBonus,
+    pub reward_vesting: VestingConfig,
+    pub vesting_balance: u64,
+    pub oracles: Vec<Pubkey>,
+    pub proposal_count: u64,
+    pub pending_admin: Option<Pubkey>,
+    pub admin_threshold: u8,
+    pub admin_action_count: u64,
+    pub pause_flags: u8,
+    pub paused_content_types: u8,
+    pub circuit_breaker: CircuitBreakerConfig,
+    pub circuit_breaker_tripped: bool,
+    pub avg_daily_submissions: u32,
+    pub avg_daily_votes: u32,
+    pub window_vote_stake: u64,
+    pub dominant_voter: Pubkey,
+    pub dominant_voter_weight: u64,
+    pub dominant_voter_stake: u64,
+    pub treasury_config: TreasuryConfig,
+    pub treasury_period_start: i64,
+    pub treasury_period_spent: u64,
+    pub content_tree_count: u64,
+}
+
+versioned_account!(ProtocolState, [33, 51, 173, 134, 35, 140, 195, 248]);
+
+impl ProtocolState {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // admin
+        32 + // treasury
+        8 + // stake_required
+        8 + // voting_period
+        1 + // quorum_percentage
+        8 + // reward_per_vote
+        1 + // is_paused
+        4 + // daily_submission_count
+        4 + // daily_vote_count
+        8 + // last_reset_timestamp
+        8 + // last_reward_distribution_timestamp
+        1 + // version
+        1 + // bump
+        (4 + (32 * constants::MAX_EMERGENCY_ADMINS)) + // emergency_admins vector
+        8 + // current_epoch
+        8 + // reserved_rewards
+        8 + // total_rewards_distributed
+        1 + // emission_schedule_configured
+        EarlyVoterBonus::SIZE + // early_voter_bonus
+        VestingConfig::SIZE + // reward_vesting
+        8 + // vesting_balance
+        (4 + (32 * constants::MAX_ORACLES)) + // oracles vector
+        8 + // proposal_count
+        (1 + 32) + // pending_admin
+        1 + // admin_threshold
+        8 + // admin_action_count
+        1 + // pause_flags
+        1 + // paused_content_types
+        CircuitBreakerConfig::SIZE + // circuit_breaker
+        1 + // circuit_breaker_tripped
+        4 + // avg_daily_submissions
+        4 + // avg_daily_votes
+        8 + // window_vote_stake
+        32 + // dominant_voter
+        8 + // dominant_voter_weight
+        8 + // dominant_voter_stake
+        TreasuryConfig::SIZE + // treasury_config
+        8 + // treasury_period_start
+        8 + // treasury_period_spent
+        8; // content_tree_count
+
+    /// Byte offset of `version`, which sits before the first vector and so
+    /// is readable in every layout since v3.
+    pub const VERSION_OFFSET: usize = 8 + // discriminator
+        32 + // admin
+        32 + // treasury
+        8 + // stake_required
+        8 + // voting_period
+        1 + // quorum_percentage
+        8 + // reward_per_vote
+        1 + // is_paused
+        4 + // daily_submission_count
+        4 + // daily_vote_count
+        8 + // last_reset_timestamp
+        8; // last_reward_distribution_timestamp
+
+    pub fn apply_config(&mut self, config: &ProtocolConfig) {
+        self.stake_required = config.stake_required;
+        self.voting_period = config.voting_period;
+        self.quorum_percentage = config.quorum_percentage;
+        self.reward_per_vote = config.reward_per_vote;
+        self.early_voter_bonus = config.early_voter_bonus;
+    }
+
+    pub fn validate_proposal_action(&self, action: &ProposalAction) -> Result<()> {
+        match action {
+            ProposalAction::UpdateConfig { config } => config.validate()?,
+            ProposalAction::TreasurySpend { amount, .. } => {
+                require!(*amount > 0, ErrorCode::InvalidProposal)
+            }
+            ProposalAction::AddOracle { oracle } => {
+                require!(
+                    self.oracles.len() < constants::MAX_ORACLES,
+                    ErrorCode::MaxOraclesReached
+                );
+                require!(!self.oracles.contains(oracle), ErrorCode::InvalidProposal);
+            }
+            ProposalAction::RemoveOracle { oracle } => {
+                require!(self.oracles.contains(oracle), ErrorCode::InvalidProposal)
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the emergency admin approvals a sensitive instruction needs.
+    /// Callers check the signer's own admin status or role first. Above a
+    /// threshold of one, an unexpired `AdminAction` for exactly this change
+    /// must carry enough approvals from current admins; it is consumed.
+    pub fn authorize_admin_action(
+        &self,
+        signer: &Pubkey,
+        admin_action: Option<&mut Account<AdminAction>>,
+        expected: &AdminActionKind,
+        current_timestamp: i64,
+    ) -> Result<()> {
+        if self.admin_threshold <= 1 {
+            return Ok(());
+        }
+
+        let action = admin_action.ok_or(ErrorCode::AdminActionRequired)?;
+        require!(!action.executed, ErrorCode::AdminActionAlreadyExecuted);
+        require!(action.kind == *expected, ErrorCode::AdminActionMismatch);
+        require!(
+            current_timestamp <= action.created_at + constants::ADMIN_ACTION_TTL,
+            ErrorCode::AdminActionExpired
+        );
+
+        // Approvals from admins removed since they signed no longer count
+        let approvals = action
+            .approvals
+            .iter()
+            .filter(|approver| self.emergency_admins.contains(approver))
+            .count();
+        require!(
+            approvals >= self.admin_threshold as usize,
+            ErrorCode::InsufficientApprovals
+        );
+
+        action.executed = true;
+
+        emit!(AdminActionExecuted {
+            action_id: action.id,
+            executed_by: *signer,
+            timestamp: current_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn record_treasury_spend(&mut self, amount: u64, current_timestamp: i64) -> Result<()> {
+        if current_timestamp
+            >= self.treasury_period_start + self.treasury_config.spend_period
+        {
+            self.treasury_period_start = current_timestamp;
+            self.treasury_period_spent = 0;
+        }
+
+        let spent = self.treasury_period_spent
+            .checked_add(amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        require!(
+            spent <= self.treasury_config.spend_limit,
+            ErrorCode::TreasurySpendLimitExceeded
+        );
+        self.treasury_period_spent = spent;
+        Ok(())
+    }
+
+    pub fn check_active_status(&self) -> Result<()> {
+        require!(!self.is_paused, ErrorCode::ProtocolPaused);
+        Ok(())
+    }
+
+    /// `is_paused` still stops everything; `flag` is one of the
+    /// `constants::PAUSE_*` subsystem bits.
+    pub fn check_not_paused(&self, flag: u8) -> Result<()> {
+        self.check_active_status()?;
+        require!(self.pause_flags & flag == 0, ErrorCode::SubsystemPaused);
+        require!(
+            !self.circuit_breaker_tripped || flag & constants::CIRCUIT_BREAKER_RESTRICTED == 0,
+            ErrorCode::CircuitBreakerActive
+        );
+        Ok(())
+    }
+
+    pub fn check_content_type_active(&self, content_type: ContentType) -> Result<()> {
+        require!(
+            self.paused_content_types & (1 << content_type as u8) == 0,
+            ErrorCode::ContentTypePaused
+        );
+        Ok(())
+    }
+
+    pub fn check_and_update_daily_limits(&mut self, current_timestamp: i64) -> Result<()> {
+        let elapsed_days = (current_timestamp - self.last_reset_timestamp) / 86400;
+        if elapsed_days >= 1 {
+            self.avg_daily_submissions =
+                trailing_average(self.avg_daily_submissions, self.daily_submission_count);
+            self.avg_daily_votes = trailing_average(self.avg_daily_votes, self.daily_vote_count);
+            // Every further day that passed without a submission or vote is an
+            // empty day. The loop ends early once both averages reach zero.
+            for _ in 1..elapsed_days {
+                if self.avg_daily_submissions == 0 && self.avg_daily_votes == 0 {
+                    break;
+                }
+                self.avg_daily_submissions = trailing_average(self.avg_daily_submissions, 0);
+                self.avg_daily_votes = trailing_average(self.avg_daily_votes, 0);
+            }
+            self.daily_submission_count = 0;
+            self.daily_vote_count = 0;
+            self.window_vote_stake = 0;
+            self.dominant_voter = Pubkey::default();
+            self.dominant_voter_weight = 0;
+            self.dominant_voter_stake = 0;
+            self.last_reset_timestamp += elapsed_days * 86400;
+        }
+        Ok(())
+    }
+
+    /// Tracks the voter most likely to dominate the current window with a
+    /// stake-weighted majority vote: a voter above half of the window's stake
+    /// is always the candidate, and `dominant_voter_stake` is their exact stake
+    /// since becoming it.
+    pub fn record_vote_activity(&mut self, voter: Pubkey, stake_amount: u64) -> Result<()> {
+        self.window_vote_stake = self
+            .window_vote_stake
+            .checked_add(stake_amount)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        if voter == self.dominant_voter {
+            self.dominant_voter_weight = self.dominant_voter_weight.saturating_add(stake_amount);
+            self.dominant_voter_stake = self.dominant_voter_stake.saturating_add(stake_amount);
+        } else if self.dominant_voter_weight >= stake_amount {
+            self.dominant_voter_weight -= stake_amount;
+        } else {
+            self.dominant_voter = voter;
+            self.dominant_voter_weight = stake_amount - self.dominant_voter_weight;
+            self.dominant_voter_stake = stake_amount;
+        }
+        Ok(())
+    }
+
+    /// Trips the breaker instead of failing, so the triggering transaction
+    /// still lands and every later submission or vote is refused.
+    pub fn check_circuit_breaker(&mut self, current_timestamp: i64) {
+        let config = self.circuit_breaker;
+        if !config.enabled || self.circuit_breaker_tripped {
+            return;
+        }
+
+        let trip = if let Some(threshold) =
+            config.surge_threshold(self.daily_submission_count, self.avg_daily_submissions)
+        {
+            Some((CircuitBreakerReason::SubmissionSurge, self.daily_submission_count as u64, threshold, None))
+        } else if let Some(threshold) =
+            config.surge_threshold(self.daily_vote_count, self.avg_daily_votes)
+        {
+            Some((CircuitBreakerReason::VoteSurge, self.daily_vote_count as u64, threshold, None))
+        } else if let Some(threshold) =
+            config.dominance_threshold(self.dominant_voter_stake, self.window_vote_stake)
+        {
+            Some((
+                CircuitBreakerReason::VoterDominance,
+                self.dominant_voter_stake,
+                threshold,
+                Some(self.dominant_voter),
+            ))
+        } else {
+            None
+        };
+
+        if let Some((reason, observed, threshold, voter)) = trip {
+            self.circuit_breaker_tripped = true;
+            emit!(CircuitBreakerTripped {
+                reason,
+                observed,
+                threshold,
+                voter,
+                timestamp: current_timestamp,
+            });
+        }
+    }
+
+    pub fn validate_submission(&self, content_data: &ContentData) -> Result<()> {
+        self.check_content_type_active(content_data.content_type)?;
+        require!(
+            content_data.content_type != ContentType::Video
+                && content_data.content_type != ContentType::DeFi,
+            ErrorCode::UnsupportedContentType
+        );
+        require!(
+            content_data.content_hash.len() <= constants::MAX_CONTENT_HASH_LENGTH,
+            ErrorCode::ContentHashTooLong
+        );
+        require!(
+            content_data.ai_score >= constants::MIN_AI_CONFIDENCE,
+            ErrorCode::LowAIConfidence
+        );
+        Ok(())
+    }
+
+    pub fn validate_vote_transaction(
+        &self,
+        content: &Content,
+        stake_amount: u64,
+        current_timestamp: i64,
+    ) -> Result<()> {
+        self.check_not_paused(constants::PAUSE_VOTING)?;
+        self.check_content_type_active(content.content_type)?;
+        require!(
+            stake_amount >= self.stake_required && stake_amount <= constants::MAX_STAKE_PER_USER,
+            ErrorCode::InvalidStakeAmount
+        );
+        require!(
+            current_timestamp <= content.submission_time + content.voting_period,
+            ErrorCode::VotingPeriodEnded
+        );
+        require!(
+            current_timestamp >= content.last_vote_timestamp + constants::VOTE_COOLDOWN_PERIOD,
+            ErrorCode::VotingTooFrequent
+        );
+        Ok(())
+    }
+
+    /// Checks a batch's shape and counts all of its items against the daily
+    /// limit. Each item needs a `[content, verdict]` account pair.
+    pub fn reserve_batch(&mut self, item_count: usize, account_count: usize) -> Result<u32> {
+        require!(
+            item_count > 0 && item_count <= constants::MAX_BATCH_SUBMISSIONS,
+            ErrorCode::InvalidBatchSize
+        );
+        require!(
+            account_count == item_count * 2,
+            ErrorCode::InvalidBatchAccounts
+        );
+        let count = item_count as u32;
+        self.record_submissions(count)?;
+        Ok(count)
+    }
+
+    /// Compressed submissions only take the low-risk end of the flagged range.
+    pub fn validate_compressed_submission(&self, content_data: &ContentData) -> Result<()> {
+        self.validate_submission(content_data)?;
+        require!(
+            content_data.ai_score <= constants::LOW_RISK_MAX_AI_SCORE,
+            ErrorCode::NotLowRisk
+        );
+        Ok(())
+    }
+
+    pub fn record_submissions(&mut self, count: u32) -> Result<()> {
+        let daily_submission_count = self
+            .daily_submission_count
+            .checked_add(count)
+            .ok_or(ErrorCode::CalculationError)?;
+        require!(
+            daily_submission_count <= constants::MAX_DAILY_SUBMISSIONS,
+            ErrorCode::DailyLimitExceeded
+        );
+        self.daily_submission_count = daily_submission_count;
+        Ok(())
+    }
+
+    pub fn increment_vote_count(&mut self) -> Result<()> {
+        self.daily_vote_count = self
+            .daily_vote_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Content {
+    pub submitter: Pubkey,
+    pub content_hash: [u8; 32],
+    pub content_type: ContentType,
+    pub ai_score: u8,
+    pub submission_time: i64,
+    pub status: ContentStatus,
+    pub approve_votes: u64,
+    pub reject_votes: u64,
+    pub total_stake: u64,
+    pub voting_period: i64,
+    pub quorum_percentage: u8,
+    pub vote_count: u32,
+    pub last_vote_timestamp: i64,
+    pub version: u8,
+    pub bump: u8,
+    pub moderation_flags: u8,
+    pub finalized_at: i64,
+    pub closed_vote_count: u32,
+    pub app: Option<Pubkey>,
+    pub callback_status: CallbackStatus,
+    pub rent_payer: Pubkey,
+}
+
+versioned_account!(Content, [3, 76, 253, 21, 4, 198, 52, 206]);
+
+impl Content {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // submitter
+        32 + // content_hash
+        1 + // content_type
+        1 + // ai_score
+        8 + // submission_time
+        1 + // status
+        8 + // approve_votes
+        8 + // reject_votes
+        8 + // total_stake
+        8 + // voting_period
+        1 + // quorum_percentage
+        4 + // vote_count
+        8 + // last_vote_timestamp
+        1 + // version
+        1 + // bump
+        1 + // moderation_flags
+        8 + // finalized_at
+        4 + // closed_vote_count
+        (1 + 32) + // app
+        1 + // callback_status
+        32; // rent_payer
+
+    pub const VERSION_OFFSET: usize = 8 + // discriminator
+        32 + // submitter
+        32 + // content_hash
+        1 + // content_type
+        1 + // ai_score
+        8 + // submission_time
+        1 + // status
+        8 + // approve_votes
+        8 + // reject_votes
+        8 + // total_stake
+        8 + // voting_period
+        1 + // quorum_percentage
+        4 + // vote_count
+        8; // last_vote_timestamp
+
+    pub fn initialize(
+        &mut self,
+        submitter: Pubkey,
+        content_data: ContentData,
+        protocol: &ProtocolState,
+        current_timestamp: i64,
+        bump: u8,
+    ) -> Result<()> {
+        self.submitter = submitter;
+        self.content_hash = content_data.content_hash;
+        self.content_type = content_data.content_type;
+        self.ai_score = content_data.ai_score;
+        self.submission_time = current_timestamp;
+        self.status = ContentStatus::Pending;
+        self.voting_period = protocol.voting_period;
+        self.quorum_percentage = protocol.quorum_percentage;
+        self.version = constants::PROGRAM_VERSION;
+        self.bump = bump;
+        self.finalized_at = 0;
+        self.closed_vote_count = 0;
+        self.app = None;
+        self.callback_status = CallbackStatus::None;
+        self.rent_payer = submitter;
+        Ok(())
+    }
+
+    pub fn verdict_info(&self, content_id: Pubkey) -> VerdictInfo {
+        VerdictInfo {
+            content_id,
+            content_hash: self.content_hash,
+            status: self.status,
+            ai_score: self.ai_score,
+            approve_votes: self.approve_votes,
+            reject_votes: self.reject_votes,
+            finalized_at: self.finalized_at,
+            closed: false,
+        }
+    }
+
+    /// Decides pending content once its voting period is over and quorum is
+    /// met. Returns the verdict and the stake that voted.
+    pub fn finalize(&mut self, current_timestamp: i64) -> Result<(ContentStatus, u64)> {
+        require!(
+            self.status == ContentStatus::Pending,
+            ErrorCode::ContentAlreadyFinalized
+        );
+        require!(
+            current_timestamp > self.submission_time + self.voting_period,
+            ErrorCode::VotingPeriodActive
+        );
+
+        let total_stake = self
+            .approve_votes
+            .checked_add(self.reject_votes)
+            .ok_or(ErrorCode::CalculationError)?;
+        let quorum = self
+            .total_stake
+            .checked_mul(self.quorum_percentage as u64)
+            .ok_or(ErrorCode::CalculationError)?
+            / 100;
+        require!(total_stake >= quorum, ErrorCode::QuorumNotReached);
+
+        let final_status = if self.approve_votes > self.reject_votes {
+            ContentStatus::Approved
+        } else {
+            ContentStatus::Rejected
+        };
+
+        self.status = final_status;
+        self.finalized_at = current_timestamp;
+        // Delivered separately by `dispatch_callback`, so a failing callback
+        // can never block finalization
+        if self.app.is_some() {
+            self.callback_status = CallbackStatus::Pending;
+        }
+
+        Ok((final_status, total_stake))
+    }
+
+    /// Marks the callback queued by `finalize` as delivered. Fails unless one
+    /// is pending, so each verdict reaches the app at most once.
+    pub fn take_pending_callback(&mut self) -> Result<()> {
+        require!(
+            self.callback_status == CallbackStatus::Pending,
+            ErrorCode::NoPendingCallback
+        );
+        self.callback_status = CallbackStatus::Delivered;
+        Ok(())
+    }
+
+    pub fn check_closable(&self, current_timestamp: i64) -> Result<()> {
+        require!(
+            self.status != ContentStatus::Pending,
+            ErrorCode::ContentNotFinalized
+        );
+        require!(
+            current_timestamp >= self.finalized_at + constants::ACCOUNT_CLOSE_GRACE_PERIOD,
+            ErrorCode::CloseGracePeriodActive
+        );
+        Ok(())
+    }
+
+    pub fn process_vote(
+        &mut self,
+        vote_type: VoteType,
+        stake_amount: u64,
+    ) -> Result<()> {
+        match vote_type {
+            VoteType::Approve => {
+                self.approve_votes = self
+                    .approve_votes
+                    .checked_add(stake_amount)
+                    .ok_or(ErrorCode::CalculationError)?;
+            }
+            VoteType::Reject => {
+                self.reject_votes = self
+                    .reject_votes
+                    .checked_add(stake_amount)
+                    .ok_or(ErrorCode::CalculationError)?;
+            }
+        }
+
+        self.total_stake = self
+            .total_stake
+            .checked_add(stake_amount)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        self.vote_count = self
+            .vote_count
+            .checked_add(1)
+            .ok_or(ErrorCode::CalculationError)?;
+
+        self.last_vote_timestamp = Clock::get()?.unix_timestamp;
+
+        Ok(())
+    }
+}
+
+#[account]
+pub struct Vote {
+    pub voter: Pubkey,
+    pub content_id: Pubkey,
+    pub vote_type: VoteType,
+    pub stake_amount: u64,
+    pub vote_timestamp: i64,
+    pub status: VoteStatus,
+    pub reward_epoch: u64,
+    pub reward_weight: u64,
+    pub epoch_reward_claimed: bool,
+    pub early_bonus_percentage: u8,
+}
+
+impl Vote {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // voter
+        32 + // content_id
+        1 + // vote_type
+        8 + // stake_amount
+        8 + // vote_timestamp
+        1 + // status
+        8 + // reward_epoch
+        8 + // reward_weight
+        1 + // epoch_reward_claimed
+        1; // early_bonus_percentage
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(
+        &mut self,
+        voter: Pubkey,
+        content_id: Pubkey,
+        vote_type: VoteType,
+        stake_amount: u64,
+        current_timestamp: i64,
+        reward_epoch: u64,
+        early_bonus_percentage: u8,
+    ) -> Result<()> {
+        self.voter = voter;
+        self.content_id = content_id;
+        self.vote_type = vote_type;
+        self.stake_amount = stake_amount;
+        self.vote_timestamp = current_timestamp;
+        self.status = VoteStatus::Active;
+        self.reward_epoch = reward_epoch;
+        self.reward_weight = apply_early_bonus(stake_amount, early_bonus_percentage)?;
+        self.epoch_reward_claimed = false;
+        self.early_bonus_percentage = early_bonus_percentage;
+        Ok(())
+    }
+
+    pub fn is_aligned_with(&self, final_status: ContentStatus) -> bool {
+        matches!(
+            (self.vote_type, final_status),
+            (VoteType::Approve, ContentStatus::Approved) | (VoteType::Reject, ContentStatus::Rejected)
+        )
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ContentData {
+    pub content_hash: [u8; 32],
+    pub content_type: ContentType,
+    pub ai_score: u8,
+}
+
+/// Discriminants double as bit positions in `paused_content_types`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    Text,
+    Image,
+    Link,
+    Video,
+    DeFi,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ContentStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum VoteType {
+    Approve,
+    Reject,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum VoteStatus {
+    Active,
+    Rewarded,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CallbackStatus {
+    /// The content was not submitted through a registered app.
+    None,
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// A dApp that submits content and wants its verdicts delivered on-chain.
+/// The callback receives the `[b"callback"]` PDA as a signer, the content
+/// account, then any pass-through accounts; its data is the discriminator
+/// followed by the Borsh-encoded `VerdictInfo`.
+#[account]
+pub struct AppRegistration {
+    pub authority: Pubkey,
+    pub callback_program: Pubkey,
+    pub callback_discriminator: [u8; 8],
+    pub created_at: i64,
+    pub bump: u8,
+    pub paused: bool,
+}
+
+impl AppRegistration {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // authority
+        32 + // callback_program
+        8 + // callback_discriminator
+        8 + // created_at
+        1 + // bump
+        1; // paused
+
+    /// Checked for every submission that attaches this registration.
+    pub fn check_active(&self) -> Result<()> {
+        require!(!self.paused, ErrorCode::AppPaused);
+        Ok(())
+    }
+}
+
+/// Leaf of a compressed content tree. Leaves are append-only, so a proof
+/// against any root still in the tree's history remains valid.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct CompressedContent {
+    pub tree_id: u64,
+    pub leaf_index: u32,
+    pub submitter: Pubkey,
+    pub submission_time: i64,
+    pub content_data: ContentData,
+}
+
+impl CompressedContent {
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        Ok(merkle::content_leaf_hash(&self.try_to_vec()?))
+    }
+}
+
+/// Append-only Merkle tree of compressed submissions. Only the right edge
+/// (`filled_subtrees`) is stored, and a ring of recent roots lets proofs built
+/// against a slightly stale root still verify while other appends land.
+#[account]
+pub struct ContentTree {
+    pub id: u64,
+    pub depth: u8,
+    pub next_index: u32,
+    pub filled_subtrees: [[u8; 32]; constants::MAX_CONTENT_TREE_DEPTH as usize],
+    pub root_history: [[u8; 32]; constants::CONTENT_TREE_ROOT_HISTORY],
+    pub root_index: u8,
+    pub bump: u8,
+}
+
+impl ContentTree {
+    pub const SIZE: usize = 8 + // discriminator
+        8 + // id
+        1 + // depth
+        4 + // next_index
+        (32 * constants::MAX_CONTENT_TREE_DEPTH as usize) + // filled_subtrees
+        (32 * constants::CONTENT_TREE_ROOT_HISTORY) + // root_history
+        1 + // root_index
+        1; // bump
+
+    pub fn initialize(&mut self, id: u64, depth: u8, bump: u8) {
+        self.id = id;
+        self.depth = depth;
+        self.next_index = 0;
+        self.root_history[0] = (0..depth).fold([0u8; 32], |zero, _| merkle::node_hash(&zero, &zero));
+        self.root_index = 0;
+        self.bump = bump;
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.root_history[self.root_index as usize]
+    }
+
+    pub fn append(&mut self, leaf: [u8; 32]) -> Result<[u8; 32]> {
+        require!(
+            (self.next_index as u64) < (1u64 << self.depth),
+            ErrorCode::ContentTreeFull
+        );
+
+        let mut node = leaf;
+        let mut index = self.next_index;
+        let mut zero = [0u8; 32];
+        for level in 0..self.depth as usize {
+            node = if index % 2 == 0 {
+                self.filled_subtrees[level] = node;
+                merkle::node_hash(&node, &zero)
+            } else {
+                merkle::node_hash(&self.filled_subtrees[level], &node)
+            };
+            zero = merkle::node_hash(&zero, &zero);
+            index /= 2;
+        }
+
+        self.next_index += 1;
+        self.root_index = ((self.root_index as usize + 1) % constants::CONTENT_TREE_ROOT_HISTORY) as u8;
+        self.root_history[self.root_index as usize] = node;
+        Ok(node)
+    }
+
+    pub fn verify(&self, leaf: &CompressedContent, root: [u8; 32], proof: &[[u8; 32]]) -> Result<()> {
+        require!(leaf.tree_id == self.id, ErrorCode::InvalidMerkleProof);
+        require!(
+            root != [0u8; 32] && self.root_history.contains(&root),
+            ErrorCode::UnknownTreeRoot
+        );
+        require!(
+            proof.len() == self.depth as usize
+                && merkle::verify(proof, root, leaf.hash()?),
+            ErrorCode::InvalidMerkleProof
+        );
+        Ok(())
+    }
+}
+
+/// What remains of a `Content` account after `close_content`.
+#[account]
+pub struct Verdict {
+    pub content_id: Pubkey,
+    pub content_hash: [u8; 32],
+    pub content_type: ContentType,
+    pub status: ContentStatus,
+    pub ai_score: u8,
+    pub approve_votes: u64,
+    pub reject_votes: u64,
+    pub finalized_at: i64,
+    pub bump: u8,
+}
+
+impl Verdict {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // content_id
+        32 + // content_hash
+        1 + // content_type
+        1 + // status
+        1 + // ai_score
+        8 + // approve_votes
+        8 + // reject_votes
+        8 + // finalized_at
+        1; // bump
+
+    pub fn info(&self) -> VerdictInfo {
+        VerdictInfo {
+            content_id: self.content_id,
+            content_hash: self.content_hash,
+            status: self.status,
+            ai_score: self.ai_score,
+            approve_votes: self.approve_votes,
+            reject_votes: self.reject_votes,
+            finalized_at: self.finalized_at,
+            closed: true,
+        }
+    }
+}
+
+/// Returned by `get_verdict`. `status` stays `Pending` and `finalized_at` zero
+/// until the decision is finalized.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct VerdictInfo {
+    pub content_id: Pubkey,
+    pub content_hash: [u8; 32],
+    pub status: ContentStatus,
+    pub ai_score: u8,
+    pub approve_votes: u64,
+    pub reject_votes: u64,
+    pub finalized_at: i64,
+    pub closed: bool,
+}
+
+#[account]
+pub struct RewardEpoch {
+    pub epoch: u64,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    pub total_weight: u64,
+    pub reward_pool: u64,
+    pub reward_per_weight: u128,
+    pub is_closed: bool,
+    pub bump: u8,
+    pub claimed_weight: u64,
+    pub merkle_root_posted: bool,
+}
+
+impl RewardEpoch {
+    pub const SIZE: usize = 8 + // discriminator
+        8 + // epoch
+        8 + // start_timestamp
+        8 + // end_timestamp
+        8 + // total_weight
+        8 + // reward_pool
+        16 + // reward_per_weight
+        1 + // is_closed
+        1 + // bump
+        8 + // claimed_weight
+        1; // merkle_root_posted
+
+    pub fn initialize(&mut self, epoch: u64, start_timestamp: i64, bump: u8) {
+        self.epoch = epoch;
+        self.start_timestamp = start_timestamp;
+        self.end_timestamp = 0;
+        self.total_weight = 0;
+        self.reward_pool = 0;
+        self.reward_per_weight = 0;
+        self.is_closed = false;
+        self.bump = bump;
+        self.claimed_weight = 0;
+        self.merkle_root_posted = false;
+    }
+
+    pub fn accrue_weight(&mut self, weight: u64) -> Result<()> {
+        require!(!self.is_closed, ErrorCode::RewardEpochClosed);
+        self.total_weight = self
+            .total_weight
+            .checked_add(weight)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+
+    /// Fixes the reward-per-weight rate for the epoch and returns the amount
+    /// actually allocated to voters. Rounding dust stays in the pool.
+    pub fn close(&mut self, reward_pool: u64, current_timestamp: i64) -> Result<u64> {
+        require!(!self.is_closed, ErrorCode::RewardEpochClosed);
+
+        if self.total_weight > 0 {
+            self.reward_per_weight = (reward_pool as u128)
+                .checked_mul(constants::REWARD_PRECISION)
+                .ok_or(ErrorCode::CalculationError)?
+                .checked_div(self.total_weight as u128)
+                .ok_or(ErrorCode::CalculationError)?;
+            self.reward_pool = self.reward_for(self.total_weight)?;
+        }
+
+        self.end_timestamp = current_timestamp;
+        self.is_closed = true;
+        Ok(self.reward_pool)
+    }
+
+    pub fn reward_for(&self, weight: u64) -> Result<u64> {
+        let reward = (weight as u128)
+            .checked_mul(self.reward_per_weight)
+            .ok_or(ErrorCode::CalculationError)?
+            .checked_div(constants::REWARD_PRECISION)
+            .ok_or(ErrorCode::CalculationError)?;
+        u64::try_from(reward).map_err(|_| error!(ErrorCode::CalculationError))
+    }
+}
+
+#[account]
+pub struct MerkleDistribution {
+    pub epoch: u64,
+    pub root: [u8; 32],
+    pub total_amount: u64,
+    pub claimed_amount: u64,
+    pub num_leaves: u32,
+    pub posted_by: Pubkey,
+    pub posted_at: i64,
+    pub bump: u8,
+    pub claimed_bitmap: Vec<u8>,
+}
+
+impl MerkleDistribution {
+    pub fn space(num_leaves: u32) -> usize {
+        8 + // discriminator
+        8 + // epoch
+        32 + // root
+        8 + // total_amount
+        8 + // claimed_amount
+        4 + // num_leaves
+        32 + // posted_by
+        8 + // posted_at
+        1 + // bump
+        (4 + Self::bitmap_len(num_leaves)) // claimed_bitmap vector
+    }
+
+    pub fn bitmap_len(num_leaves: u32) -> usize {
+        (num_leaves as usize + 7) / 8
+    }
+
+    pub fn is_claimed(&self, index: u32) -> bool {
+        self.claimed_bitmap[index as usize / 8] & (1 << (index % 8)) != 0
+    }
 
-    Ok(u64::try_from(final_reward).unwrap_or(0))
+    pub fn set_claimed(&mut self, index: u32) {
+        self.claimed_bitmap[index as usize / 8] |= 1 << (index % 8);
+    }
 }
 
-fn calculate_time_weight(vote_timestamp: i64, current_timestamp: i64) -> Result<u64> {
-    let time_diff = current_timestamp
-        .checked_sub(vote_timestamp)
-        .ok_or(ErrorCode::CalculationError)?;
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum EmissionDecay {
+    Halving { interval_epochs: u32 },
+    Linear { decrement_per_epoch: u64 },
+}
 
-    // Implement exponential decay for rewards based on time
-    if time_diff < 86400 { // Within 24 hours
-        Ok(100)
-    } else if time_diff < 259200 { // Within 3 days
-        Ok(75)
-    } else if time_diff < 604800 { // Within 7 days
-        Ok(50)
-    } else {
-        Ok(25)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct EmissionScheduleParams {
+    pub initial_emission: u64,
+    pub decay: EmissionDecay,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+}
+
+impl EmissionScheduleParams {
+    pub const SIZE: usize = 8 + // initial_emission
+        (1 + 8) + // decay
+        8 + // start_timestamp
+        8; // end_timestamp
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.initial_emission > 0 && self.end_timestamp > self.start_timestamp,
+            ErrorCode::InvalidEmissionSchedule
+        );
+        if let EmissionDecay::Halving { interval_epochs } = self.decay {
+            require!(interval_epochs > 0, ErrorCode::InvalidEmissionSchedule);
+        }
+        Ok(())
     }
 }
 
-#[derive(Accounts)]
-pub struct DistributeRewards<'info> {
-    #[account(mut)]
-    pub protocol_state: Account<'info, ProtocolState>,
-    
-    #[account(mut)]
-    pub reward_vault: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub stake_vault: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub voter_token_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
+#[account]
+pub struct EmissionSchedule {
+    pub initial_emission: u64,
+    pub decay: EmissionDecay,
+    pub start_timestamp: i64,
+    pub end_timestamp: i64,
+    pub epochs_elapsed: u32,
+    pub total_emitted: u64,
+    pub bump: u8,
 }
 
-#[event]
-pub struct RewardDistributed {
-    pub voter: Pubkey,
+impl EmissionSchedule {
+    pub const SIZE: usize = 8 + // discriminator
+        8 + // initial_emission
+        (1 + 8) + // decay
+        8 + // start_timestamp
+        8 + // end_timestamp
+        4 + // epochs_elapsed
+        8 + // total_emitted
+        1; // bump
+
+    pub fn apply(&mut self, params: &EmissionScheduleParams) {
+        self.initial_emission = params.initial_emission;
+        self.decay = params.decay;
+        self.start_timestamp = params.start_timestamp;
+        self.end_timestamp = params.end_timestamp;
+    }
+
+    pub fn is_active_at(&self, timestamp: i64) -> bool {
+        timestamp >= self.start_timestamp && timestamp < self.end_timestamp
+    }
+
+    /// Emission owed to the `epoch_index`-th epoch inside the schedule window.
+    pub fn scheduled_emission(&self, epoch_index: u32) -> u64 {
+        match self.decay {
+            EmissionDecay::Halving { interval_epochs } => self
+                .initial_emission
+                .checked_shr(epoch_index / interval_epochs)
+                .unwrap_or(0),
+            EmissionDecay::Linear { decrement_per_epoch } => self
+                .initial_emission
+                .saturating_sub(decrement_per_epoch.saturating_mul(epoch_index as u64)),
+        }
+    }
+
+    pub fn emission_for(&self, epoch_start_timestamp: i64) -> u64 {
+        if self.is_active_at(epoch_start_timestamp) {
+            self.scheduled_emission(self.epochs_elapsed)
+        } else {
+            0
+        }
+    }
+
+    pub fn record_emission(&mut self, epoch_start_timestamp: i64, funded_amount: u64) -> Result<()> {
+        if self.is_active_at(epoch_start_timestamp) {
+            self.epochs_elapsed = self
+                .epochs_elapsed
+                .checked_add(1)
+                .ok_or(ErrorCode::CalculationError)?;
+        }
+        self.total_emitted = self
+            .total_emitted
+            .checked_add(funded_amount)
+            .ok_or(ErrorCode::CalculationError)?;
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VestingConfig {
+    pub enabled: bool,
+    pub cliff_seconds: i64,
+    pub duration_seconds: i64,
+}
+
+impl VestingConfig {
+    pub const SIZE: usize = 1 + // enabled
+        8 + // cliff_seconds
+        8; // duration_seconds
+
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled {
+            require!(
+                self.duration_seconds > 0
+                    && self.cliff_seconds >= 0
+                    && self.cliff_seconds <= self.duration_seconds,
+                ErrorCode::InvalidVestingConfig
+            );
+        }
+        Ok(())
+    }
+}
+
+/// One reward deposit and the schedule it vests on.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct VestingTranche {
     pub amount: u64,
-    pub timestamp: i64,
+    pub released_amount: u64,
+    pub start_timestamp: i64,
+    pub cliff_seconds: i64,
+    pub duration_seconds: i64,
 }
 
-#[event]
-pub struct RewardsDistributed {
-    pub total_amount: u64,
-    pub timestamp: i64,
+impl VestingTranche {
+    pub const SIZE: usize = 8 + // amount
+        8 + // released_amount
+        8 + // start_timestamp
+        8 + // cliff_seconds
+        8; // duration_seconds
+
+    pub fn vested_amount(&self, timestamp: i64) -> u64 {
+        let elapsed = timestamp - self.start_timestamp;
+        if elapsed < self.cliff_seconds {
+            0
+        } else if elapsed >= self.duration_seconds {
+            self.amount
+        } else {
+            ((self.amount as u128 * elapsed as u128) / self.duration_seconds as u128) as u64
+        }
+    }
+
+    pub fn cliff_timestamp(&self) -> i64 {
+        self.start_timestamp + self.cliff_seconds
+    }
+
+    pub fn end_timestamp(&self) -> i64 {
+        self.start_timestamp + self.duration_seconds
+    }
 }
 
-#[derive(Accounts)]
-pub struct PauseProtocol<'info> {
-    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
-    pub protocol_state: Account<'info, ProtocolState>,
-    #[account(signer)]
-    pub admin: Signer<'info>,
+/// Each deposit vests on its own schedule, so adding rewards never re-locks
+/// earlier ones. Deposits made within `duration / MAX_VESTING_TRANCHES` of the
+/// newest tranche under the same schedule join it instead of opening another,
+/// and so vest at most that much early. Fully vested tranches are folded into
+/// `unlocked_carry` to free their slot.
+#[account]
+pub struct VestingAccount {
+    pub owner: Pubkey,
+    pub unlocked_carry: u64,
+    pub bump: u8,
+    pub tranches: Vec<VestingTranche>,
 }
 
-#[derive(Accounts)]
-pub struct UnpauseProtocol<'info> {
-    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
-    pub protocol_state: Account<'info, ProtocolState>,
-    #[account(signer)]
-    pub admin: Signer<'info>,
+impl VestingAccount {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // owner
+        8 + // unlocked_carry
+        1 + // bump
+        (4 + (VestingTranche::SIZE * constants::MAX_VESTING_TRANCHES)); // tranches vector
+
+    pub fn vested_amount(&self, timestamp: i64) -> u64 {
+        self.tranches.iter().map(|tranche| tranche.vested_amount(timestamp)).sum()
+    }
+
+    pub fn locked_amount(&self, timestamp: i64) -> u64 {
+        self.tranches
+            .iter()
+            .map(|tranche| tranche.amount - tranche.vested_amount(timestamp))
+            .sum()
+    }
+
+    /// Adds rewards under `config` and returns the tranche they landed in.
+    /// Should every slot still be vesting, they join the newest tranche.
+    pub fn deposit(
+        &mut self,
+        amount: u64,
+        timestamp: i64,
+        config: &VestingConfig,
+    ) -> Result<VestingTranche> {
+        self.settle(timestamp)?;
+
+        let join_window = config.duration_seconds / constants::MAX_VESTING_TRANCHES as i64;
+        let joins_newest = match self.tranches.last() {
+            Some(newest) => {
+                self.tranches.len() >= constants::MAX_VESTING_TRANCHES
+                    || (newest.cliff_seconds == config.cliff_seconds
+                        && newest.duration_seconds == config.duration_seconds
+                        && timestamp - newest.start_timestamp < join_window)
+            }
+            None => false,
+        };
+
+        if joins_newest {
+            let newest = self.tranches.last_mut().unwrap();
+            newest.amount = newest
+                .amount
+                .checked_add(amount)
+                .ok_or(ErrorCode::CalculationError)?;
+        } else {
+            self.tranches.push(VestingTranche {
+                amount,
+                released_amount: 0,
+                start_timestamp: timestamp,
+                cliff_seconds: config.cliff_seconds,
+                duration_seconds: config.duration_seconds,
+            });
+        }
+        Ok(*self.tranches.last().unwrap())
+    }
+
+    pub fn release(&mut self, timestamp: i64) -> Result<u64> {
+        let mut amount = self.unlocked_carry;
+        for tranche in self.tranches.iter_mut() {
+            let vested = tranche.vested_amount(timestamp);
+            amount = amount
+                .checked_add(vested - tranche.released_amount)
+                .ok_or(ErrorCode::CalculationError)?;
+            tranche.released_amount = vested;
+        }
+        self.unlocked_carry = 0;
+        self.tranches.retain(|tranche| tranche.released_amount < tranche.amount);
+        Ok(amount)
+    }
+
+    /// Drops every still-locked amount and returns the total.
+    pub fn forfeit(&mut self, timestamp: i64) -> Result<u64> {
+        self.settle(timestamp)?;
+        let mut forfeited: u64 = 0;
+        for tranche in &self.tranches {
+            let vested = tranche.vested_amount(timestamp);
+            forfeited = forfeited
+                .checked_add(tranche.amount - vested)
+                .ok_or(ErrorCode::CalculationError)?;
+            self.unlocked_carry = self
+                .unlocked_carry
+                .checked_add(vested - tranche.released_amount)
+                .ok_or(ErrorCode::CalculationError)?;
+        }
+        self.tranches.clear();
+        Ok(forfeited)
+    }
+
+    /// Moves whatever fully vested tranches have not released yet into
+    /// `unlocked_carry` and drops them.
+    fn settle(&mut self, timestamp: i64) -> Result<()> {
+        for tranche in &self.tranches {
+            if tranche.vested_amount(timestamp) == tranche.amount {
+                self.unlocked_carry = self
+                    .unlocked_carry
+                    .checked_add(tranche.amount - tranche.released_amount)
+                    .ok_or(ErrorCode::CalculationError)?;
+            }
+        }
+        self.tranches
+            .retain(|tranche| tranche.vested_amount(timestamp) < tranche.amount);
+        Ok(())
+    }
 }
 
-#[derive(Accounts)]
-pub struct AddEmergencyAdmin<'info> {
-    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
-    pub protocol_state: Account<'info, ProtocolState>,
-    #[account(signer)]
-    pub admin: Signer<'info>,
-    pub new_admin: Signer<'info>,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub enum ProposalAction {
+    UpdateConfig { config: ProtocolConfig },
+    TreasurySpend { recipient: Pubkey, amount: u64 },
+    AddOracle { oracle: Pubkey },
+    RemoveOracle { oracle: Pubkey },
 }
 
-#[derive(Accounts)]
-pub struct RemoveEmergencyAdmin<'info> {
-    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
-    pub protocol_state: Account<'info, ProtocolState>,
-    #[account(signer)]
-    pub admin: Signer<'info>,
-    pub admin_to_remove: Signer<'info>,
+impl ProposalAction {
+    pub const SIZE: usize = 1 + // variant
+        ProtocolConfig::SIZE + 32 + 8; // upper bound across variants
 }
 
-#[derive(Accounts)]
-pub struct DistributeRewards<'info> {
-    #[account(mut, seeds = [b"protocol"], bump = protocol_state.bump)]
-    pub protocol_state: Account<'info, ProtocolState>,
-    #[account(signer)]
-    pub admin: Signer<'info>,
-    #[account(mut)]
-    pub reward_vault: Account<'info, TokenAccount>,
+#[account]
+pub struct Proposal {
+    pub id: u64,
+    pub proposer: Pubkey,
+    pub action: ProposalAction,
+    pub description_hash: [u8; 32],
+    pub for_votes: u64,
+    pub against_votes: u64,
+    pub created_at: i64,
+    pub voting_ends_at: i64,
+    pub executable_at: i64,
+    pub executed: bool,
+    pub bump: u8,
 }
 
-#[error_code]
-pub enum ErrorCode {
-    // Previous errors
-    #[msg("Unsupported content type")]
-    UnsupportedContentType,
-    #[msg("Content hash too long")]
-    ContentHashTooLong,
-    #[msg("Voting period is still active")]
-    VotingPeriodActive,
-    #[msg("Rewards already claimed")]
-    RewardsAlreadyClaimed,
-    #[msg("Invalid quorum percentage")]
-    InvalidQuorumPercentage,
-    #[msg("Invalid voting period")]
-    InvalidVotingPeriod,
-    #[msg("Invalid reward per vote")]
-    InvalidRewardPerVote,
-    #[msg("Maximum emergency admins reached")]
-    MaxEmergencyAdminsReached,
-    #[msg("Cannot remove the last emergency admin")]
-    CannotRemoveLastAdmin,
-    #[msg("Reward distribution not due yet")]
-    RewardDistributionNotDue,
+impl Proposal {
+    pub const SIZE: usize = 8 + // discriminator
+        8 + // id
+        32 + // proposer
+        ProposalAction::SIZE + // action
+        32 + // description_hash
+        8 + // for_votes
+        8 + // against_votes
+        8 + // created_at
+        8 + // voting_ends_at
+        8 + // executable_at
+        1 + // executed
+        1; // bump
 }
 
-#[event]
-pub struct ProtocolInitialized {
-    pub admin: Pubkey,
-    pub treasury: Pubkey,
-    pub stake_required: u64,
-    pub voting_period: i64,
-    pub quorum_percentage: u8,
-    pub reward_per_vote: u64,
-    pub version: u8,
-    pub timestamp: i64,
+#[account]
+pub struct GovernanceVote {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub support: bool,
+    pub stake_amount: u64,
+    pub bump: u8,
 }
 
-#[event]
-pub struct ProtocolPaused {
-    pub paused_by: Pubkey,
-    pub timestamp: i64,
+impl GovernanceVote {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // proposal
+        32 + // voter
+        1 + // support
+        8 + // stake_amount
+        1; // bump
 }
 
-#[event]
-pub struct ProtocolUnpaused {
-    pub unpaused_by: Pubkey,
-    pub timestamp: i64,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum AdminActionKind {
+    Unpause,
+    AddEmergencyAdmin { admin: Pubkey },
+    RemoveEmergencyAdmin { admin: Pubkey },
+    SetThreshold { threshold: u8 },
+    SetRewardVesting { config: VestingConfig },
+    InitializeEmissionSchedule { params: EmissionScheduleParams },
+    UpdateEmissionSchedule { params: EmissionScheduleParams },
+    GrantRole { account: Pubkey, role: Role },
+    RevokeRole { account: Pubkey, role: Role },
+    SetPauseFlags { pause_flags: u8, paused_content_types: u8 },
+    SetCircuitBreakerConfig { config: CircuitBreakerConfig },
+    ResetCircuitBreaker,
+    SetTreasuryConfig { config: TreasuryConfig },
+    ProposeConfigUpdate { config: ProtocolConfig, treasury: Pubkey },
+    CancelConfigUpdate { proposed_at: i64 },
+    ForfeitVesting { owner: Pubkey },
+    UnpauseApp { app: Pubkey },
 }
 
-#[event]
-pub struct EmergencyAdminAdded {
-    pub new_admin: Pubkey,
-    pub added_by: Pubkey,
-    pub timestamp: i64,
+impl AdminActionKind {
+    pub const SIZE: usize = 1 + // variant
+        ProtocolConfig::SIZE + 32; // largest variant: ProposeConfigUpdate
 }
 
-#[event]
-pub struct EmergencyAdminRemoved {
-    pub removed_admin: Pubkey,
-    pub removed_by: Pubkey,
-    pub timestamp: i64,
+#[account]
+pub struct AdminAction {
+    pub id: u64,
+    pub kind: AdminActionKind,
+    pub proposer: Pubkey,
+    pub approvals: Vec<Pubkey>,
+    pub created_at: i64,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+impl AdminAction {
+    pub const SIZE: usize = 8 + // discriminator
+        8 + // id
+        AdminActionKind::SIZE + // kind
+        32 + // proposer
+        (4 + (32 * constants::MAX_EMERGENCY_ADMINS)) + // approvals vector
+        8 + // created_at
+        1 + // executed
+        1; // bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Pauser,
+    /// Retired: oracles are only added and removed by governance proposals.
+    /// The variant stays so the bits of the roles after it do not move.
+    OracleManager,
+    TreasuryManager,
+    ConfigManager,
+    Keeper,
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [
+        Role::Pauser,
+        Role::TreasuryManager,
+        Role::ConfigManager,
+        Role::Keeper,
+    ];
+
+    pub fn mask(self) -> u8 {
+        1 << (self as u8)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct RoleMember {
+    pub account: Pubkey,
+    pub roles: u8,
+}
+
+#[account]
+pub struct Roles {
+    pub members: Vec<RoleMember>,
+    pub bump: u8,
+}
+
+impl Roles {
+    pub const SIZE: usize = 8 + // discriminator
+        (4 + ((32 + 1) * constants::MAX_ROLE_MEMBERS)) + // members vector
+        1; // bump
+
+    pub fn has_role(&self, account: &Pubkey, role: Role) -> bool {
+        self.members
+            .iter()
+            .any(|member| member.account == *account && member.roles & role.mask() != 0)
+    }
+
+    pub fn require_role(&self, account: &Pubkey, role: Role) -> Result<()> {
+        require!(self.has_role(account, role), ErrorCode::MissingRole);
+        Ok(())
+    }
+
+    pub fn grant(&mut self, account: Pubkey, role: Role) -> Result<()> {
+        require!(role != Role::OracleManager, ErrorCode::RoleRetired);
+        if let Some(member) = self.members.iter_mut().find(|member| member.account == account) {
+            require!(member.roles & role.mask() == 0, ErrorCode::RoleAlreadyGranted);
+            member.roles |= role.mask();
+            return Ok(());
+        }
+
+        require!(
+            self.members.len() < constants::MAX_ROLE_MEMBERS,
+            ErrorCode::MaxRoleMembersReached
+        );
+        self.members.push(RoleMember {
+            account,
+            roles: role.mask(),
+        });
+        Ok(())
+    }
+
+    pub fn revoke(&mut self, account: &Pubkey, role: Role) -> Result<()> {
+        require!(self.has_role(account, role), ErrorCode::MissingRole);
+        for member in self.members.iter_mut().filter(|member| member.account == *account) {
+            member.roles &= !role.mask();
+        }
+        self.members.retain(|member| member.roles != 0);
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CircuitBreakerConfig {
+    pub enabled: bool,
+    /// Daily activity above this percentage of the trailing average trips, e.g. 500 = 5x.
+    pub activity_multiplier_percentage: u16,
+    /// Surges are ignored until the day has at least this many submissions or votes.
+    pub min_window_activity: u32,
+    /// One voter's share of the day's voted stake that trips; at least 50.
+    pub max_voter_share_percentage: u8,
+    /// Dominance is ignored until the day has at least this much stake voted.
+    pub min_window_stake: u64,
+}
+
+impl CircuitBreakerConfig {
+    pub const SIZE: usize = 1 + // enabled
+        2 + // activity_multiplier_percentage
+        4 + // min_window_activity
+        1 + // max_voter_share_percentage
+        8; // min_window_stake
+
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled {
+            // Only a voter above half of the window's stake is reliably the
+            // tracked candidate (see `record_vote_activity`)
+            require!(
+                self.activity_multiplier_percentage > 100
+                    && self.max_voter_share_percentage >= 50
+                    && self.max_voter_share_percentage <= 100,
+                ErrorCode::InvalidCircuitBreakerConfig
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the crossed threshold when `count` is a surge over `average`.
+    pub fn surge_threshold(&self, count: u32, average: u32) -> Option<u64> {
+        // Without history there is no baseline to compare against
+        if average == 0 || count < self.min_window_activity {
+            return None;
+        }
+        let threshold = (average as u64 * self.activity_multiplier_percentage as u64) / 100;
+        (count as u64 > threshold).then_some(threshold)
+    }
+
+    pub fn dominance_threshold(&self, voter_stake: u64, window_stake: u64) -> Option<u64> {
+        if window_stake == 0 || window_stake < self.min_window_stake {
+            return None;
+        }
+        let threshold =
+            ((window_stake as u128 * self.max_voter_share_percentage as u128) / 100) as u64;
+        (voter_stake > threshold).then_some(threshold)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TreasuryConfig {
+    /// Charged to the submitter for every item submitted, whether through
+    /// `submit_content`, `submit_content_batch` or `submit_content_compressed`.
+    pub submission_fee: u64,
+    /// Most `withdraw_treasury` may move per period; governance spends are not counted.
+    pub spend_limit: u64,
+    pub spend_period: i64,
+    /// Paid to whoever runs `finalize_decision` or `close_reward_epoch`. Never
+    /// more than `submission_fee`, so a finalization costs the treasury no more
+    /// than its submission paid in. Epoch closes have no submission behind
+    /// them and are paid from the treasury's other income.
+    pub keeper_fee: u64,
+}
+
+impl TreasuryConfig {
+    pub const SIZE: usize = 8 + // submission_fee
+        8 + // spend_limit
+        8 + // spend_period
+        8; // keeper_fee
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.spend_period >= constants::MIN_TREASURY_SPEND_PERIOD,
+            ErrorCode::InvalidTreasuryConfig
+        );
+        require!(
+            self.keeper_fee <= self.submission_fee,
+            ErrorCode::InvalidTreasuryConfig
+        );
+        Ok(())
+    }
 }
 
-#[event]
-pub struct RewardsDistributed {
-    pub timestamp: i64,
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TreasuryMovementKind {
+    SubmissionFee,
+    ForfeitedVesting,
+    EmissionFunding,
+    GovernanceSpend,
+    Withdrawal,
+    KeeperFee,
+    /// A misaligned voter's epoch reward share.
+    ForfeitedReward,
 }
 
-#[account]
-pub struct ProtocolState {
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitBreakerReason {
+    SubmissionSurge,
+    VoteSurge,
+    VoterDominance,
+}
+
+/// `ProtocolState` as laid out by program version 3.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ProtocolStateV3 {
     pub admin: Pubkey,
     pub treasury: Pubkey,
     pub stake_required: u64,
@@ -638,78 +6418,59 @@ pub struct ProtocolState {
     pub emergency_admins: Vec<Pubkey>,
 }
 
-impl ProtocolState {
-    pub const SIZE: usize = 8 + // discriminator
-        32 + // admin
-        32 + // treasury
-        8 + // stake_required
-        8 + // voting_period
-        1 + // quorum_percentage
-        8 + // reward_per_vote
-        1 + // is_paused
-        4 + // daily_submission_count
-        4 + // daily_vote_count
-        8 + // last_reset_timestamp
-        8 + // last_reward_distribution_timestamp
-        1 + // version
-        1 + // bump
-        (4 + (32 * constants::MAX_EMERGENCY_ADMINS)); // emergency_admins vector
-
-    pub fn check_active_status(&self) -> Result<()> {
-        require!(!self.is_paused, ErrorCode::ProtocolPaused);
-        Ok(())
-    }
-
-    pub fn check_and_update_daily_limits(&mut self, current_timestamp: i64) -> Result<()> {
-        if current_timestamp - self.last_reset_timestamp >= 86400 {
-            self.daily_submission_count = 0;
-            self.daily_vote_count = 0;
-            self.last_reset_timestamp = current_timestamp;
+impl ProtocolStateV3 {
+    pub fn into_current(self, current_timestamp: i64) -> ProtocolState {
+        ProtocolState {
+            admin: self.admin,
+            treasury: self.treasury,
+            stake_required: self.stake_required,
+            voting_period: self.voting_period,
+            quorum_percentage: self.quorum_percentage,
+            reward_per_vote: self.reward_per_vote,
+            is_paused: self.is_paused,
+            daily_submission_count: self.daily_submission_count,
+            daily_vote_count: self.daily_vote_count,
+            last_reset_timestamp: self.last_reset_timestamp,
+            last_reward_distribution_timestamp: current_timestamp,
+            version: constants::PROGRAM_VERSION,
+            bump: self.bump,
+            emergency_admins: self.emergency_admins,
+            current_epoch: 0,
+            reserved_rewards: 0,
+            total_rewards_distributed: 0,
+            emission_schedule_configured: false,
+            early_voter_bonus: EarlyVoterBonus {
+                max_bonus_percentage: 0,
+                curve: EarlyBonusCurve::None,
+            },
+            reward_vesting: VestingConfig::default(),
+            vesting_balance: 0,
+            oracles: Vec::new(),
+            proposal_count: 0,
+            pending_admin: None,
+            admin_threshold: 1,
+            admin_action_count: 0,
+            pause_flags: 0,
+            paused_content_types: 0,
+            circuit_breaker: CircuitBreakerConfig::default(),
+            circuit_breaker_tripped: false,
+            avg_daily_submissions: 0,
+            avg_daily_votes: 0,
+            window_vote_stake: 0,
+            dominant_voter: Pubkey::default(),
+            dominant_voter_weight: 0,
+            dominant_voter_stake: 0,
+            treasury_config: TreasuryConfig::default(),
+            treasury_period_start: current_timestamp,
+            treasury_period_spent: 0,
+            content_tree_count: 0,
         }
-        Ok(())
-    }
-
-    pub fn validate_vote_transaction(
-        &self,
-        content: &Content,
-        stake_amount: u64,
-        current_timestamp: i64,
-    ) -> Result<()> {
-        self.check_active_status()?;
-        require!(
-            stake_amount >= self.stake_required && stake_amount <= constants::MAX_STAKE_PER_USER,
-            ErrorCode::InvalidStakeAmount
-        );
-        require!(
-            current_timestamp <= content.submission_time + content.voting_period,
-            ErrorCode::VotingPeriodEnded
-        );
-        require!(
-            current_timestamp >= content.last_vote_timestamp + constants::VOTE_COOLDOWN_PERIOD,
-            ErrorCode::VotingTooFrequent
-        );
-        Ok(())
-    }
-
-    pub fn increment_submission_count(&mut self) -> Result<()> {
-        self.daily_submission_count = self
-            .daily_submission_count
-            .checked_add(1)
-            .ok_or(ErrorCode::CalculationError)?;
-        Ok(())
-    }
-
-    pub fn increment_vote_count(&mut self) -> Result<()> {
-        self.daily_vote_count = self
-            .daily_vote_count
-            .checked_add(1)
-            .ok_or(ErrorCode::CalculationError)?;
-        Ok(())
     }
 }
 
-#[account]
-pub struct Content {
+/// `Content` as laid out by program version 3.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ContentV3 {
     pub submitter: Pubkey,
     pub content_hash: [u8; 32],
     pub content_type: ContentType,
@@ -728,84 +6489,45 @@ pub struct Content {
     pub moderation_flags: u8,
 }
 
-impl Content {
-    pub const SIZE: usize = 8 + // discriminator
-        32 + // submitter
-        32 + // content_hash
-        1 + // content_type
-        1 + // ai_score
-        8 + // submission_time
-        1 + // status
-        8 + // approve_votes
-        8 + // reject_votes
-        8 + // total_stake
-        8 + // voting_period
-        1 + // quorum_percentage
-        4 + // vote_count
-        8 + // last_vote_timestamp
-        1 + // version
-        1 + // bump
-        1; // moderation_flags
-
-    pub fn initialize(
-        &mut self,
-        submitter: Pubkey,
-        content_data: ContentData,
-        protocol: &ProtocolState,
-        current_timestamp: i64,
-        bump: u8,
-    ) -> Result<()> {
-        self.submitter = submitter;
-        self.content_hash = content_data.content_hash;
-        self.content_type = content_data.content_type;
-        self.ai_score = content_data.ai_score;
-        self.submission_time = current_timestamp;
-        self.status = ContentStatus::Pending;
-        self.voting_period = protocol.voting_period;
-        self.quorum_percentage = protocol.quorum_percentage;
-        self.version = constants::PROGRAM_VERSION;
-        self.bump = bump;
-        Ok(())
-    }
+impl ContentV3 {
+    pub fn into_current(self) -> Content {
+        // v3 never recorded when a decision was made; the end of the voting
+        // period is the earliest it could have been
+        let finalized_at = if self.status == ContentStatus::Pending {
+            0
+        } else {
+            self.submission_time + self.voting_period
+        };
 
-    pub fn process_vote(
-        &mut self,
-        vote_type: VoteType,
-        stake_amount: u64,
-    ) -> Result<()> {
-        match vote_type {
-            VoteType::Approve => {
-                self.approve_votes = self
-                    .approve_votes
-                    .checked_add(stake_amount)
-                    .ok_or(ErrorCode::CalculationError)?;
-            }
-            VoteType::Reject => {
-                self.reject_votes = self
-                    .reject_votes
-                    .checked_add(stake_amount)
-                    .ok_or(ErrorCode::CalculationError)?;
-            }
+        Content {
+            submitter: self.submitter,
+            content_hash: self.content_hash,
+            content_type: self.content_type,
+            ai_score: self.ai_score,
+            submission_time: self.submission_time,
+            status: self.status,
+            approve_votes: self.approve_votes,
+            reject_votes: self.reject_votes,
+            total_stake: self.total_stake,
+            voting_period: self.voting_period,
+            quorum_percentage: self.quorum_percentage,
+            vote_count: self.vote_count,
+            last_vote_timestamp: self.last_vote_timestamp,
+            version: constants::PROGRAM_VERSION,
+            bump: self.bump,
+            moderation_flags: self.moderation_flags,
+            finalized_at,
+            closed_vote_count: 0,
+            app: None,
+            callback_status: CallbackStatus::None,
+            rent_payer: self.submitter,
         }
-
-        self.total_stake = self
-            .total_stake
-            .checked_add(stake_amount)
-            .ok_or(ErrorCode::CalculationError)?;
-
-        self.vote_count = self
-            .vote_count
-            .checked_add(1)
-            .ok_or(ErrorCode::CalculationError)?;
-
-        self.last_vote_timestamp = Clock::get()?.unix_timestamp;
-
-        Ok(())
     }
 }
 
-#[account]
-pub struct Vote {
+/// `Vote` as laid out by program version 3, before epoch accounting.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct VoteV3 {
     pub voter: Pubkey,
     pub content_id: Pubkey,
     pub vote_type: VoteType,
@@ -814,7 +6536,7 @@ pub struct Vote {
     pub status: VoteStatus,
 }
 
-impl Vote {
+impl VoteV3 {
     pub const SIZE: usize = 8 + // discriminator
         32 + // voter
         32 + // content_id
@@ -822,10 +6544,471 @@ impl Vote {
         8 + // stake_amount
         8 + // vote_timestamp
         1; // status
+
+    pub fn into_current(self) -> Vote {
+        // v3 votes predate epoch accounting, so there is no epoch share to
+        // settle when they are closed
+        Vote {
+            voter: self.voter,
+            content_id: self.content_id,
+            vote_type: self.vote_type,
+            stake_amount: self.stake_amount,
+            vote_timestamp: self.vote_timestamp,
+            status: self.status,
+            reward_epoch: 0,
+            reward_weight: 0,
+            epoch_reward_claimed: true,
+            early_bonus_percentage: 0,
+        }
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub enum VoteStatus {
-    Active,
-    Rewarded,
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vesting_account() -> VestingAccount {
+        VestingAccount {
+            owner: Pubkey::new_unique(),
+            unlocked_carry: 0,
+            bump: 255,
+            tranches: Vec::new(),
+        }
+    }
+
+    const VESTING: VestingConfig = VestingConfig {
+        enabled: true,
+        cliff_seconds: 100,
+        duration_seconds: 1000,
+    };
+
+    #[test]
+    fn repeated_vesting_deposits_keep_earlier_schedules() {
+        let mut vesting = vesting_account();
+        vesting.deposit(1000, 0, &VESTING).unwrap();
+        assert_eq!(vesting.vested_amount(99), 0);
+        assert_eq!(vesting.vested_amount(200), 200);
+
+        // Past the first cliff, a second deposit must not re-lock the first
+        vesting.deposit(1000, 200, &VESTING).unwrap();
+        assert_eq!(vesting.tranches.len(), 2);
+        assert_eq!(vesting.vested_amount(250), 250);
+        assert_eq!(vesting.locked_amount(250), 1750);
+        assert_eq!(vesting.release(250).unwrap(), 250);
+
+        // A third deposit after the first fully vested settles it into the carry
+        vesting.deposit(500, 1100, &VESTING).unwrap();
+        assert_eq!(vesting.tranches.len(), 2);
+        assert_eq!(vesting.unlocked_carry, 750);
+        assert_eq!(vesting.release(1100).unwrap(), 750 + 900);
+
+        assert_eq!(vesting.release(2100).unwrap(), 100 + 500);
+        assert!(vesting.tranches.is_empty());
+    }
+
+    #[test]
+    fn vesting_deposits_close_together_share_a_tranche() {
+        let mut vesting = vesting_account();
+        vesting.deposit(1000, 0, &VESTING).unwrap();
+        let tranche = vesting.deposit(1000, 50, &VESTING).unwrap();
+        assert_eq!(vesting.tranches.len(), 1);
+        assert_eq!(tranche.amount, 2000);
+        assert_eq!(tranche.cliff_timestamp(), 100);
+
+        for step in 1..constants::MAX_VESTING_TRANCHES as i64 {
+            vesting.deposit(10, step * 200, &VESTING).unwrap();
+        }
+        assert_eq!(vesting.tranches.len(), 5);
+    }
+
+    #[test]
+    fn forfeiting_vesting_keeps_vested_rewards() {
+        let mut vesting = vesting_account();
+        vesting.deposit(1000, 0, &VESTING).unwrap();
+        vesting.deposit(1000, 500, &VESTING).unwrap();
+
+        assert_eq!(vesting.forfeit(600).unwrap(), 400 + 900);
+        assert!(vesting.tranches.is_empty());
+        assert_eq!(vesting.release(600).unwrap(), 700);
+    }
+
+    #[test]
+    fn callbacks_cannot_sign_for_the_vault_authority() {
+        let program_id = crate::ID;
+        let (protocol, _) = Pubkey::find_program_address(&[b"protocol"], &program_id);
+        let (callback_authority, _) = Pubkey::find_program_address(&[b"callback"], &program_id);
+        let dispatcher = Pubkey::new_unique();
+        let (mut protocol_lamports, mut dispatcher_lamports) = (0, 0);
+        let (mut protocol_data, mut dispatcher_data) = (vec![], vec![]);
+
+        // Even a dispatcher that passes the vault authority and its own signing
+        // key through must not hand either signature to the callee
+        let remaining = [
+            AccountInfo::new(
+                &protocol,
+                true,
+                true,
+                &mut protocol_lamports,
+                &mut protocol_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &dispatcher,
+                true,
+                true,
+                &mut dispatcher_lamports,
+                &mut dispatcher_data,
+                &system_program::ID,
+                false,
+                0,
+            ),
+        ];
+        let metas = callback_account_metas(callback_authority, Pubkey::new_unique(), &remaining);
+
+        let signers: Vec<_> = metas
+            .iter()
+            .filter(|meta| meta.is_signer)
+            .map(|meta| meta.pubkey)
+            .collect();
+        assert_eq!(signers, vec![callback_authority]);
+        assert_ne!(callback_authority, protocol);
+        assert!(metas[2].is_writable && metas[3].is_writable);
+    }
+
+    /// A freshly initialized protocol; the v3 migration fills in the same
+    /// defaults `initialize` would.
+    fn protocol_state() -> ProtocolState {
+        ProtocolStateV3 {
+            admin: Pubkey::new_unique(),
+            treasury: Pubkey::new_unique(),
+            stake_required: 1000,
+            voting_period: constants::MIN_VOTING_PERIOD,
+            quorum_percentage: constants::MIN_QUORUM_PERCENTAGE,
+            reward_per_vote: 10,
+            is_paused: false,
+            daily_submission_count: 0,
+            daily_vote_count: 0,
+            last_reset_timestamp: 0,
+            last_reward_distribution_timestamp: 0,
+            version: 3,
+            bump: 255,
+            emergency_admins: Vec::new(),
+        }
+        .into_current(0)
+    }
+
+    #[test]
+    fn batches_count_every_item_against_the_daily_limit() {
+        let mut protocol = protocol_state();
+        assert_eq!(protocol.reserve_batch(3, 6).unwrap(), 3);
+        protocol.record_submissions(1).unwrap();
+        assert_eq!(protocol.daily_submission_count, 4);
+
+        protocol.daily_submission_count = constants::MAX_DAILY_SUBMISSIONS - 2;
+        assert_eq!(
+            protocol.reserve_batch(3, 6).unwrap_err(),
+            error!(ErrorCode::DailyLimitExceeded)
+        );
+        assert_eq!(protocol.reserve_batch(2, 4).unwrap(), 2);
+
+        // The next day starts from zero again
+        protocol.check_and_update_daily_limits(86400).unwrap();
+        assert_eq!(protocol.reserve_batch(1, 2).unwrap(), 1);
+        assert_eq!(protocol.daily_submission_count, 1);
+    }
+
+    #[test]
+    fn batches_need_a_content_and_verdict_account_per_item() {
+        let mut protocol = protocol_state();
+        for account_count in [0, 5, 7, 8] {
+            assert_eq!(
+                protocol.reserve_batch(3, account_count).unwrap_err(),
+                error!(ErrorCode::InvalidBatchAccounts)
+            );
+        }
+        assert_eq!(
+            protocol.reserve_batch(0, 0).unwrap_err(),
+            error!(ErrorCode::InvalidBatchSize)
+        );
+        assert_eq!(
+            protocol.reserve_batch(constants::MAX_BATCH_SUBMISSIONS + 1, 0).unwrap_err(),
+            error!(ErrorCode::InvalidBatchSize)
+        );
+        assert_eq!(protocol.daily_submission_count, 0);
+    }
+
+    fn content_data(ai_score: u8) -> ContentData {
+        ContentData {
+            content_hash: Pubkey::new_unique().to_bytes(),
+            content_type: ContentType::Text,
+            ai_score,
+        }
+    }
+
+    #[test]
+    fn compressed_submissions_take_the_low_end_of_the_flagged_range() {
+        let protocol = protocol_state();
+        let check = |ai_score| protocol.validate_compressed_submission(&content_data(ai_score));
+
+        assert_eq!(
+            check(constants::MIN_AI_CONFIDENCE - 1).unwrap_err(),
+            error!(ErrorCode::LowAIConfidence)
+        );
+        check(constants::MIN_AI_CONFIDENCE).unwrap();
+        check(constants::LOW_RISK_MAX_AI_SCORE).unwrap();
+        assert_eq!(
+            check(constants::LOW_RISK_MAX_AI_SCORE + 1).unwrap_err(),
+            error!(ErrorCode::NotLowRisk)
+        );
+        protocol
+            .validate_submission(&content_data(constants::LOW_RISK_MAX_AI_SCORE + 1))
+            .unwrap();
+    }
+
+    fn content_tree(depth: u8) -> ContentTree {
+        let mut tree = ContentTree {
+            id: 0,
+            depth: 0,
+            next_index: 0,
+            filled_subtrees: [[0u8; 32]; constants::MAX_CONTENT_TREE_DEPTH as usize],
+            root_history: [[0u8; 32]; constants::CONTENT_TREE_ROOT_HISTORY],
+            root_index: 0,
+            bump: 0,
+        };
+        tree.initialize(7, depth, 255);
+        tree
+    }
+
+    fn leaf(tree: &ContentTree) -> CompressedContent {
+        CompressedContent {
+            tree_id: tree.id,
+            leaf_index: tree.next_index,
+            submitter: Pubkey::new_unique(),
+            submission_time: 1_700_000_000,
+            content_data: content_data(60),
+        }
+    }
+
+    /// Proof for `leaves[index]` in a tree of `depth` holding exactly `leaves`.
+    fn proof(leaves: &[CompressedContent], mut index: usize, depth: u8) -> Vec<[u8; 32]> {
+        let mut level: Vec<_> = leaves.iter().map(|leaf| leaf.hash().unwrap()).collect();
+        let mut zero = [0u8; 32];
+        let mut proof = Vec::new();
+        for _ in 0..depth {
+            if level.len() % 2 == 1 {
+                level.push(zero);
+            }
+            proof.push(level[index ^ 1]);
+            level = level
+                .chunks(2)
+                .map(|pair| merkle::node_hash(&pair[0], &pair[1]))
+                .collect();
+            zero = merkle::node_hash(&zero, &zero);
+            index /= 2;
+        }
+        proof
+    }
+
+    #[test]
+    fn appended_leaves_verify_against_the_latest_root() {
+        let mut tree = content_tree(3);
+        let mut leaves = Vec::new();
+        for _ in 0..5 {
+            let next = leaf(&tree);
+            let root = tree.append(next.hash().unwrap()).unwrap();
+            assert_eq!(tree.root(), root);
+            leaves.push(next);
+        }
+        let root = tree.root();
+
+        for index in 0..leaves.len() {
+            tree.verify(&leaves[index], root, &proof(&leaves, index, 3)).unwrap();
+        }
+
+        let mut tampered = leaves[2].clone();
+        tampered.content_data.ai_score += 1;
+        assert_eq!(
+            tree.verify(&tampered, root, &proof(&leaves, 2, 3)).unwrap_err(),
+            error!(ErrorCode::InvalidMerkleProof)
+        );
+        assert_eq!(
+            tree.verify(&leaves[2], root, &proof(&leaves, 2, 3)[..2]).unwrap_err(),
+            error!(ErrorCode::InvalidMerkleProof)
+        );
+    }
+
+    #[test]
+    fn proofs_against_rotated_out_roots_are_rejected() {
+        let mut tree = content_tree(6);
+        let first = leaf(&tree);
+        let first_root = tree.append(first.hash().unwrap()).unwrap();
+        let first_proof = proof(&[first.clone()], 0, 6);
+
+        // The root stays verifiable while it is in the history ring
+        for _ in 1..constants::CONTENT_TREE_ROOT_HISTORY {
+            let next = leaf(&tree);
+            tree.append(next.hash().unwrap()).unwrap();
+        }
+        tree.verify(&first, first_root, &first_proof).unwrap();
+
+        let next = leaf(&tree);
+        tree.append(next.hash().unwrap()).unwrap();
+        assert_eq!(
+            tree.verify(&first, first_root, &first_proof).unwrap_err(),
+            error!(ErrorCode::UnknownTreeRoot)
+        );
+    }
+
+    #[test]
+    fn full_trees_refuse_appends() {
+        let mut tree = content_tree(2);
+        for _ in 0..4 {
+            let next = leaf(&tree);
+            tree.append(next.hash().unwrap()).unwrap();
+        }
+        let overflow = leaf(&tree);
+        assert_eq!(
+            tree.append(overflow.hash().unwrap()).unwrap_err(),
+            error!(ErrorCode::ContentTreeFull)
+        );
+        assert_eq!(tree.next_index, 4);
+    }
+
+    /// Content submitted at 0 with one approving vote that meets quorum.
+    fn voted_content(app: Option<Pubkey>) -> Content {
+        let mut content = Content {
+            submitter: Pubkey::default(),
+            content_hash: [0; 32],
+            content_type: ContentType::Text,
+            ai_score: 0,
+            submission_time: 0,
+            status: ContentStatus::Pending,
+            approve_votes: 0,
+            reject_votes: 0,
+            total_stake: 0,
+            voting_period: 0,
+            quorum_percentage: 0,
+            vote_count: 0,
+            last_vote_timestamp: 0,
+            version: 0,
+            bump: 0,
+            moderation_flags: 0,
+            finalized_at: 0,
+            closed_vote_count: 0,
+            app: None,
+            callback_status: CallbackStatus::None,
+            rent_payer: Pubkey::default(),
+        };
+        content
+            .initialize(Pubkey::new_unique(), content_data(80), &protocol_state(), 0, 255)
+            .unwrap();
+        content.app = app;
+        content.process_vote(VoteType::Approve, 1000).unwrap();
+        content
+    }
+
+    #[test]
+    fn content_is_finalized_only_once() {
+        let mut content = voted_content(None);
+        let deadline = content.submission_time + content.voting_period;
+        assert_eq!(
+            content.finalize(deadline).unwrap_err(),
+            error!(ErrorCode::VotingPeriodActive)
+        );
+
+        let (status, total_stake) = content.finalize(deadline + 1).unwrap();
+        assert!(status == ContentStatus::Approved);
+        assert_eq!(total_stake, 1000);
+        assert_eq!(content.finalized_at, deadline + 1);
+
+        // `finalize_decision` returns before `pay_keeper_fee` on this error,
+        // so a repeat call moves nothing out of the treasury
+        assert_eq!(
+            content.finalize(deadline + 100).unwrap_err(),
+            error!(ErrorCode::ContentAlreadyFinalized)
+        );
+        assert_eq!(content.finalized_at, deadline + 1);
+    }
+
+    #[test]
+    fn a_finalization_queues_exactly_one_callback() {
+        let mut content = voted_content(Some(Pubkey::new_unique()));
+        let after_deadline = content.submission_time + content.voting_period + 1;
+        assert_eq!(
+            content.take_pending_callback().unwrap_err(),
+            error!(ErrorCode::NoPendingCallback)
+        );
+
+        content.finalize(after_deadline).unwrap();
+        content.take_pending_callback().unwrap();
+        assert!(content.callback_status == CallbackStatus::Delivered);
+        assert_eq!(
+            content.take_pending_callback().unwrap_err(),
+            error!(ErrorCode::NoPendingCallback)
+        );
+
+        // A repeat finalization cannot queue the verdict again
+        content.finalize(after_deadline + 1).unwrap_err();
+        assert!(content.callback_status == CallbackStatus::Delivered);
+        assert_eq!(
+            content.take_pending_callback().unwrap_err(),
+            error!(ErrorCode::NoPendingCallback)
+        );
+    }
+
+    #[test]
+    fn content_without_an_app_queues_no_callback() {
+        let mut content = voted_content(None);
+        content
+            .finalize(content.submission_time + content.voting_period + 1)
+            .unwrap();
+        assert!(content.callback_status == CallbackStatus::None);
+        assert_eq!(
+            content.take_pending_callback().unwrap_err(),
+            error!(ErrorCode::NoPendingCallback)
+        );
+    }
+
+    #[test]
+    fn v3_votes_migrate_with_no_epoch_share_to_settle() {
+        let legacy = VoteV3 {
+            voter: Pubkey::new_unique(),
+            content_id: Pubkey::new_unique(),
+            vote_type: VoteType::Reject,
+            stake_amount: 1000,
+            vote_timestamp: 1_700_000_000,
+            status: VoteStatus::Active,
+        };
+        assert_eq!(8 + legacy.try_to_vec().unwrap().len(), VoteV3::SIZE);
+
+        let vote = legacy.into_current();
+        assert_eq!(8 + vote.try_to_vec().unwrap().len(), Vote::SIZE);
+        assert_eq!(vote.stake_amount, 1000);
+        assert_eq!(vote.reward_weight, 0);
+        assert!(vote.epoch_reward_claimed);
+    }
+
+    #[test]
+    fn only_v3_accounts_can_be_migrated() {
+        let discriminator = Content::discriminator();
+        let mut data = vec![0u8; Content::VERSION_OFFSET + 1];
+        data[..8].copy_from_slice(&discriminator);
+
+        data[Content::VERSION_OFFSET] = 3;
+        assert_eq!(check_legacy_account(&data, &discriminator, Content::VERSION_OFFSET).unwrap(), 3);
+
+        data[Content::VERSION_OFFSET] = constants::PROGRAM_VERSION;
+        assert_eq!(
+            check_legacy_account(&data, &discriminator, Content::VERSION_OFFSET).unwrap_err(),
+            error!(ErrorCode::AccountAlreadyMigrated)
+        );
+
+        data[Content::VERSION_OFFSET] = 2;
+        assert_eq!(
+            check_legacy_account(&data, &discriminator, Content::VERSION_OFFSET).unwrap_err(),
+            error!(ErrorCode::UnsupportedAccountVersion)
+        );
+    }
 }
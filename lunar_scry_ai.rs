@@ -0,0 +1,751 @@
+//! `lunar-scry-ai`: the off-chain analysis step that produces `ai_score`.
+//!
+//! Usage: `lunar-scry-ai [--model groq|rules|mock] [--listen <addr>] [--url <rpc>] [--keypair <file>]`
+//!
+//! Content is scored by a [`ModerationModel`], turned into the `ContentData`
+//! the program expects and signed by the service's oracle key. Routes:
+//!
+//! - `POST /analyze`: score and attest, nothing is sent on-chain
+//! - `POST /submit`: as above, then `submit_content` if the score reaches
+//!   `MIN_AI_CONFIDENCE` (lower scores are not flagged for review)
+//!
+//! Both take `{"type": "text", "content": "..."}`, `{"type": "link", "url": "..."}`
+//! or `{"type": "image", "url": "..."}` / `{"type": "image", "data": "<base64>", "mime_type": "image/png"}`.
+//!
+//! The attestation is an ed25519 signature over
+//! `lunar_scry_client::attestation_message`. Submissions carry it in an
+//! Ed25519 program instruction, so the runtime rejects a bad signature. The
+//! program itself ignores it; whoever relies on the score checks the message
+//! and that the signer is one of `ProtocolState::oracles`.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use clap::{Parser, ValueEnum};
+use lunar_scry::{constants, ContentData, ContentType};
+use lunar_scry_client::{attestation_message, hash_content, ix, names, pda, LunarScryClient};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use solana_sdk::ed25519_instruction::new_ed25519_instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Signer};
+use spl_associated_token_account::get_associated_token_address;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const GROQ_CHAT_URL: &str = "https://api.groq.com/openai/v1/chat/completions";
+const GROQ_DEFAULT_MODEL: &str = "llama-3.2-90b-vision-preview";
+const GROQ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Largest image accepted inline; matches Groq's limit for base64 images.
+const MAX_IMAGE_BYTES: usize = 4 * 1024 * 1024;
+
+const SYSTEM_PROMPT: &str = "You are a content moderation classifier. Rate how likely the \
+    submitted content is harmful (harassment, hate, spam, scams, phishing, malware, or \
+    sexual/violent imagery) from 0 (clearly safe) to 100 (clearly harmful). Reply with a \
+    JSON object only: {\"score\": <0-100>, \"categories\": [<short labels>], \
+    \"rationale\": \"<one sentence>\"}.";
+
+// --- Inputs and results ---------------------------------------------------
+
+pub enum ImageSource {
+    Url(String),
+    Bytes { data: Vec<u8>, mime_type: String },
+}
+
+pub enum ModerationInput {
+    Text(String),
+    Image(ImageSource),
+    Link(String),
+}
+
+impl ModerationInput {
+    pub fn content_type(&self) -> ContentType {
+        match self {
+            ModerationInput::Text(_) => ContentType::Text,
+            ModerationInput::Image(_) => ContentType::Image,
+            ModerationInput::Link(_) => ContentType::Link,
+        }
+    }
+
+    /// The bytes `content_hash` commits to: the text, the URL, or the image
+    /// itself when it was uploaded rather than linked.
+    pub fn canonical_bytes(&self) -> &[u8] {
+        match self {
+            ModerationInput::Text(text) => text.as_bytes(),
+            ModerationInput::Link(url) | ModerationInput::Image(ImageSource::Url(url)) => url.as_bytes(),
+            ModerationInput::Image(ImageSource::Bytes { data, .. }) => data,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Analysis {
+    /// 0 (safe) to 100 (harmful); becomes `ContentData::ai_score`.
+    pub ai_score: u8,
+    pub categories: Vec<String>,
+    pub rationale: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ModelError {
+    #[error("model request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("model returned an unusable response: {0}")]
+    Response(String),
+}
+
+/// A backend that scores content. Implementations are blocking; the service
+/// runs them on the blocking pool.
+pub trait ModerationModel: Send + Sync {
+    /// Recorded alongside every attestation, e.g. `groq:llama-3.2-90b-vision-preview`.
+    fn name(&self) -> String;
+
+    fn analyze(&self, input: &ModerationInput) -> Result<Analysis, ModelError>;
+}
+
+// --- Groq-compatible LLM --------------------------------------------------
+
+/// Any OpenAI-style chat completions endpoint; Groq by default.
+pub struct GroqModel {
+    http: reqwest::blocking::Client,
+    endpoint: String,
+    api_key: String,
+    model: String,
+}
+
+impl GroqModel {
+    pub fn new(endpoint: String, api_key: String, model: String) -> Result<Self, ModelError> {
+        Ok(Self {
+            http: reqwest::blocking::Client::builder().timeout(GROQ_TIMEOUT).build()?,
+            endpoint,
+            api_key,
+            model,
+        })
+    }
+
+    fn user_message(input: &ModerationInput) -> serde_json::Value {
+        match input {
+            ModerationInput::Text(text) => json!(format!("Text post:\n{}", text)),
+            // The page is not fetched: the URL alone says a lot, and fetching
+            // attacker-chosen links from the oracle host is its own risk.
+            ModerationInput::Link(url) => json!(format!("Link (judge the URL itself):\n{}", url)),
+            ModerationInput::Image(source) => {
+                let url = match source {
+                    ImageSource::Url(url) => url.clone(),
+                    ImageSource::Bytes { data, mime_type } => {
+                        format!("data:{};base64,{}", mime_type, STANDARD.encode(data))
+                    }
+                };
+                json!([
+                    { "type": "text", "text": "Image:" },
+                    { "type": "image_url", "image_url": { "url": url } },
+                ])
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ModelVerdict {
+    score: f64,
+    #[serde(default)]
+    categories: Vec<String>,
+    #[serde(default)]
+    rationale: String,
+}
+
+impl ModerationModel for GroqModel {
+    fn name(&self) -> String {
+        format!("groq:{}", self.model)
+    }
+
+    fn analyze(&self, input: &ModerationInput) -> Result<Analysis, ModelError> {
+        let response: serde_json::Value = self
+            .http
+            .post(&self.endpoint)
+            .bearer_auth(&self.api_key)
+            .json(&json!({
+                "model": self.model,
+                "temperature": 0,
+                "response_format": { "type": "json_object" },
+                "messages": [
+                    { "role": "system", "content": SYSTEM_PROMPT },
+                    { "role": "user", "content": Self::user_message(input) },
+                ],
+            }))
+            .send()?
+            .error_for_status()?
+            .json()?;
+
+        let content = response["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| ModelError::Response("no message content".into()))?;
+        let verdict: ModelVerdict =
+            serde_json::from_str(content).map_err(|err| ModelError::Response(err.to_string()))?;
+        if !verdict.score.is_finite() {
+            return Err(ModelError::Response(format!("score {} is not a number", verdict.score)));
+        }
+
+        Ok(Analysis {
+            ai_score: verdict.score.round().clamp(0.0, 100.0) as u8,
+            categories: verdict.categories,
+            rationale: verdict.rationale,
+        })
+    }
+}
+
+// --- Local rules ----------------------------------------------------------
+
+/// Keyword and URL heuristics that need no network access. Coarse, but cheap
+/// enough to pre-screen everything and a fallback when the LLM is down.
+pub struct RulesModel;
+
+const TEXT_RULES: &[(&str, &str, u8)] = &[
+    ("seed phrase", "scam", 45),
+    ("private key", "scam", 45),
+    ("recovery phrase", "scam", 45),
+    ("double your", "scam", 35),
+    ("guaranteed return", "scam", 35),
+    ("send sol", "scam", 25),
+    ("claim your airdrop", "scam", 30),
+    ("connect your wallet", "phishing", 25),
+    ("dm me", "spam", 10),
+    ("click here", "spam", 15),
+    ("limited time", "spam", 10),
+    ("kill yourself", "harassment", 60),
+];
+
+const SHORTENER_HOSTS: &[&str] = &["bit.ly", "tinyurl.com", "t.co", "goo.gl", "is.gd", "cutt.ly"];
+const RISKY_TLDS: &[&str] = &["zip", "mov", "xyz", "top", "click", "country", "gq", "tk"];
+const LURE_WORDS: &[&str] = &["airdrop", "claim", "wallet", "connect", "verify", "giveaway", "mint"];
+
+impl RulesModel {
+    fn score_text(text: &str) -> Analysis {
+        let lower = text.to_lowercase();
+        let mut score: u32 = 0;
+        let mut categories: Vec<String> = Vec::new();
+        let mut reasons = Vec::new();
+        let mut flag = |category: &str, weight: u8, reason: String| {
+            score += weight as u32;
+            reasons.push(reason);
+            if !categories.iter().any(|existing| existing == category) {
+                categories.push(category.to_string());
+            }
+        };
+
+        for (phrase, category, weight) in TEXT_RULES {
+            if lower.contains(phrase) {
+                flag(category, *weight, format!("\"{}\"", phrase));
+            }
+        }
+
+        let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+        if letters.len() >= 20
+            && letters.iter().filter(|c| c.is_uppercase()).count() * 10 >= letters.len() * 8
+        {
+            flag("spam", 20, "mostly capitals".into());
+        }
+        let links = lower.matches("http://").count() + lower.matches("https://").count();
+        if links >= 3 {
+            flag("spam", 20, format!("{} links", links));
+        }
+
+        Analysis {
+            ai_score: score.min(100) as u8,
+            categories,
+            rationale: if reasons.is_empty() {
+                "no rule matched".into()
+            } else {
+                format!("matched: {}", reasons.join(", "))
+            },
+        }
+    }
+
+    fn score_link(link: &str) -> Analysis {
+        let Ok(url) = url::Url::parse(link.trim()) else {
+            return Analysis {
+                ai_score: 60,
+                categories: vec!["malformed".into()],
+                rationale: "not a valid URL".into(),
+            };
+        };
+        let host = url.host_str().unwrap_or_default().to_lowercase();
+        let mut score: u32 = 0;
+        let mut reasons = Vec::new();
+
+        if url.scheme() != "https" {
+            score += 15;
+            reasons.push("not https");
+        }
+        if matches!(url.host(), Some(url::Host::Ipv4(_)) | Some(url::Host::Ipv6(_))) {
+            score += 30;
+            reasons.push("raw IP host");
+        }
+        if host.split('.').any(|label| label.starts_with("xn--")) {
+            score += 30;
+            reasons.push("punycode host");
+        }
+        if SHORTENER_HOSTS.contains(&host.as_str()) {
+            score += 20;
+            reasons.push("URL shortener");
+        }
+        if host.rsplit('.').next().is_some_and(|tld| RISKY_TLDS.contains(&tld)) {
+            score += 20;
+            reasons.push("high-risk TLD");
+        }
+        let path = format!("{}{}", host, url.path()).to_lowercase();
+        let lures = LURE_WORDS.iter().filter(|word| path.contains(*word)).count() as u32;
+        if lures > 0 {
+            score += 15 * lures;
+            reasons.push("wallet/airdrop lure wording");
+        }
+
+        Analysis {
+            ai_score: score.min(100) as u8,
+            categories: if score > 0 { vec!["phishing".into()] } else { Vec::new() },
+            rationale: if reasons.is_empty() {
+                "no rule matched".into()
+            } else {
+                reasons.join(", ")
+            },
+        }
+    }
+}
+
+impl ModerationModel for RulesModel {
+    fn name(&self) -> String {
+        "rules".into()
+    }
+
+    fn analyze(&self, input: &ModerationInput) -> Result<Analysis, ModelError> {
+        Ok(match input {
+            ModerationInput::Text(text) => Self::score_text(text),
+            ModerationInput::Link(url) => Self::score_link(url),
+            // Pixels are out of reach for keyword rules: send images to the
+            // community rather than guess either way
+            ModerationInput::Image(_) => Analysis {
+                ai_score: constants::MIN_AI_CONFIDENCE,
+                categories: Vec::new(),
+                rationale: "images are not covered by rules; deferring to review".into(),
+            },
+        })
+    }
+}
+
+// --- Deterministic mock ---------------------------------------------------
+
+/// Same input, same score, no network. A fixed score pins every result;
+/// otherwise it is derived from the content hash.
+#[derive(Default)]
+pub struct MockModel {
+    fixed_score: Option<u8>,
+}
+
+impl MockModel {
+    pub fn new() -> Self {
+        Self { fixed_score: None }
+    }
+
+    pub fn with_score(score: u8) -> Self {
+        Self {
+            fixed_score: Some(score.min(100)),
+        }
+    }
+}
+
+impl ModerationModel for MockModel {
+    fn name(&self) -> String {
+        "mock".into()
+    }
+
+    fn analyze(&self, input: &ModerationInput) -> Result<Analysis, ModelError> {
+        let ai_score = self
+            .fixed_score
+            .unwrap_or_else(|| hash_content(input.canonical_bytes())[0] % 101);
+        Ok(Analysis {
+            ai_score,
+            categories: Vec::new(),
+            rationale: "mock model".into(),
+        })
+    }
+}
+
+// --- Service --------------------------------------------------------------
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ModelKind {
+    Groq,
+    Rules,
+    Mock,
+}
+
+#[derive(Parser)]
+#[command(name = "lunar-scry-ai", about = "Score content and submit it to LunarScry")]
+struct Args {
+    #[arg(long, value_enum, default_value = "groq")]
+    model: ModelKind,
+    #[arg(long, default_value = "127.0.0.1:8090")]
+    listen: String,
+    /// Solana RPC used by /submit
+    #[arg(long, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Oracle key: signs attestations and pays for submissions
+    #[arg(long)]
+    keypair: Option<PathBuf>,
+    /// Chat completions endpoint for --model groq
+    #[arg(long, default_value = GROQ_CHAT_URL)]
+    groq_url: String,
+    #[arg(long, default_value = GROQ_DEFAULT_MODEL)]
+    groq_model: String,
+    /// Fixed score for --model mock
+    #[arg(long)]
+    mock_score: Option<u8>,
+    /// Submit through the oracle's app registration
+    #[arg(long)]
+    via_app: bool,
+}
+
+struct Service {
+    model: Box<dyn ModerationModel>,
+    client: LunarScryClient,
+    token_account: Pubkey,
+    via_app: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum AnalyzeRequest {
+    Text {
+        content: String,
+    },
+    Link {
+        url: String,
+    },
+    Image {
+        url: Option<String>,
+        data: Option<String>,
+        mime_type: Option<String>,
+    },
+}
+
+impl TryFrom<AnalyzeRequest> for ModerationInput {
+    type Error = ApiError;
+
+    fn try_from(request: AnalyzeRequest) -> Result<Self, ApiError> {
+        Ok(match request {
+            AnalyzeRequest::Text { content } if content.is_empty() => {
+                return Err(ApiError::BadRequest("content is empty".into()))
+            }
+            AnalyzeRequest::Text { content } => ModerationInput::Text(content),
+            AnalyzeRequest::Link { url } => ModerationInput::Link(url.trim().to_string()),
+            AnalyzeRequest::Image { url: Some(url), data: None, .. } => {
+                ModerationInput::Image(ImageSource::Url(url))
+            }
+            AnalyzeRequest::Image { url: None, data: Some(data), mime_type } => {
+                let data = STANDARD
+                    .decode(data)
+                    .map_err(|err| ApiError::BadRequest(format!("data is not base64: {}", err)))?;
+                if data.len() > MAX_IMAGE_BYTES {
+                    return Err(ApiError::BadRequest(format!(
+                        "image is larger than {} bytes",
+                        MAX_IMAGE_BYTES
+                    )));
+                }
+                ModerationInput::Image(ImageSource::Bytes {
+                    data,
+                    mime_type: mime_type.unwrap_or_else(|| "image/png".into()),
+                })
+            }
+            AnalyzeRequest::Image { .. } => {
+                return Err(ApiError::BadRequest("image needs exactly one of url or data".into()))
+            }
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Attestation {
+    oracle: String,
+    model: String,
+    analyzed_at: i64,
+    content_hash: String,
+    content_type: &'static str,
+    ai_score: u8,
+    signature: String,
+}
+
+#[derive(Serialize)]
+struct Submission {
+    signature: String,
+    content: String,
+}
+
+#[derive(Serialize)]
+struct AnalyzeResponse {
+    analysis: Analysis,
+    /// Whether the score reaches `MIN_AI_CONFIDENCE` and needs community review.
+    flagged: bool,
+    attestation: Attestation,
+    submission: Option<Submission>,
+}
+
+enum ApiError {
+    BadRequest(String),
+    Upstream(String),
+    Internal(String),
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::Upstream(message) => (StatusCode::BAD_GATEWAY, message),
+            ApiError::Internal(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+impl Service {
+    /// Scores `input` and signs the resulting `ContentData`; with `submit`,
+    /// also sends it on-chain when it is flagged.
+    fn moderate(&self, input: ModerationInput, submit: bool) -> Result<AnalyzeResponse, ApiError> {
+        let analysis = self
+            .model
+            .analyze(&input)
+            .map_err(|err| ApiError::Upstream(err.to_string()))?;
+        let content_data = ContentData {
+            content_hash: hash_content(input.canonical_bytes()),
+            content_type: input.content_type(),
+            ai_score: analysis.ai_score,
+        };
+        let analyzed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| ApiError::Internal(err.to_string()))?
+            .as_secs() as i64;
+
+        let oracle = self.client.payer();
+        let message = attestation_message(&content_data, analyzed_at);
+        let attestation = Attestation {
+            oracle: oracle.pubkey().to_string(),
+            model: self.model.name(),
+            analyzed_at,
+            content_hash: to_hex(&content_data.content_hash),
+            content_type: names::content_type(content_data.content_type),
+            ai_score: content_data.ai_score,
+            signature: oracle.sign_message(&message).to_string(),
+        };
+
+        let flagged = analysis.ai_score >= constants::MIN_AI_CONFIDENCE;
+        let submission = if submit && flagged {
+            let content = pda::content(&content_data.content_hash);
+            let treasury = self
+                .client
+                .protocol_state()
+                .map_err(|err| ApiError::Upstream(err.to_string()))?
+                .treasury;
+            let signature = self
+                .client
+                .send(
+                    &[
+                        Self::ed25519_instruction(oracle, &message)?,
+                        ix::submit_content(
+                            &oracle.pubkey(),
+                            &self.token_account,
+                            &treasury,
+                            content_data,
+                            self.via_app,
+                        ),
+                    ],
+                    &[],
+                )
+                .map_err(|err| ApiError::Upstream(err.to_string()))?;
+            Some(Submission {
+                signature: signature.to_string(),
+                content: content.to_string(),
+            })
+        } else {
+            None
+        };
+
+        Ok(AnalyzeResponse {
+            analysis,
+            flagged,
+            attestation,
+            submission,
+        })
+    }
+
+    fn ed25519_instruction(
+        oracle: &solana_sdk::signature::Keypair,
+        message: &[u8],
+    ) -> Result<solana_sdk::instruction::Instruction, ApiError> {
+        let keypair = ed25519_dalek::Keypair::from_bytes(&oracle.to_bytes())
+            .map_err(|err| ApiError::Internal(err.to_string()))?;
+        Ok(new_ed25519_instruction(&keypair, message))
+    }
+}
+
+async fn handle(
+    service: Arc<Service>,
+    request: AnalyzeRequest,
+    submit: bool,
+) -> Result<Json<AnalyzeResponse>, ApiError> {
+    let input = ModerationInput::try_from(request)?;
+    tokio::task::spawn_blocking(move || service.moderate(input, submit))
+        .await
+        .map_err(|err| ApiError::Internal(err.to_string()))?
+        .map(Json)
+}
+
+async fn analyze(
+    State(service): State<Arc<Service>>,
+    Json(request): Json<AnalyzeRequest>,
+) -> Result<Json<AnalyzeResponse>, ApiError> {
+    handle(service, request, false).await
+}
+
+async fn submit(
+    State(service): State<Arc<Service>>,
+    Json(request): Json<AnalyzeRequest>,
+) -> Result<Json<AnalyzeResponse>, ApiError> {
+    handle(service, request, true).await
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn build_model(args: &Args) -> Result<Box<dyn ModerationModel>, Box<dyn Error>> {
+    Ok(match args.model {
+        ModelKind::Groq => {
+            let api_key = std::env::var("GROQ_API_KEY").map_err(|_| "GROQ_API_KEY is not set")?;
+            Box::new(GroqModel::new(args.groq_url.clone(), api_key, args.groq_model.clone())?)
+        }
+        ModelKind::Rules => Box::new(RulesModel),
+        ModelKind::Mock => Box::new(match args.mock_score {
+            Some(score) => MockModel::with_score(score),
+            None => MockModel::new(),
+        }),
+    })
+}
+
+// Blocking RPC and HTTP clients are built before the runtime starts; the
+// handlers reach them through the blocking pool.
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let keypair_path = match args.keypair.clone() {
+        Some(path) => path,
+        None => dirs::home_dir()
+            .ok_or("cannot locate home directory; pass --keypair")?
+            .join(".config/solana/id.json"),
+    };
+    let oracle = read_keypair_file(&keypair_path)
+        .map_err(|err| format!("reading {}: {}", keypair_path.display(), err))?;
+    let client = LunarScryClient::new(&args.url, oracle);
+
+    let protocol = client.protocol_state()?;
+    if !protocol.oracles.contains(&client.payer().pubkey()) {
+        eprintln!(
+            "warning: {} is not a registered oracle; its attestations will fail oracle checks",
+            client.payer().pubkey()
+        );
+    }
+    let token_account = get_associated_token_address(&client.payer().pubkey(), &client.guard_mint()?);
+
+    let service = Arc::new(Service {
+        model: build_model(&args)?,
+        client,
+        token_account,
+        via_app: args.via_app,
+    });
+    let router = Router::new()
+        .route("/analyze", post(analyze))
+        .route("/submit", post(submit))
+        .with_state(service.clone());
+
+    eprintln!("scoring with {}", service.model.name());
+    tokio::runtime::Runtime::new()?.block_on(async {
+        let listener = tokio::net::TcpListener::bind(&args.listen).await?;
+        eprintln!("listening on {}", args.listen);
+        axum::serve(listener, router).await
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_score_text_by_matched_phrases() {
+        let clean = RulesModel::score_text("Lovely weather for a walk today.");
+        assert_eq!(clean.ai_score, 0);
+        assert!(clean.categories.is_empty());
+        assert_eq!(clean.rationale, "no rule matched");
+
+        // Several scam phrases, one category
+        let scam = RulesModel::score_text("Send SOL and your Seed Phrase to claim your airdrop");
+        assert_eq!(scam.ai_score, 25 + 45 + 30);
+        assert_eq!(scam.categories, ["scam"]);
+
+        let shouting = RulesModel::score_text("THIS IS A VERY LOUD ANNOUNCEMENT FOR EVERYONE");
+        assert_eq!(shouting.ai_score, 20);
+        assert_eq!(shouting.categories, ["spam"]);
+
+        let links = RulesModel::score_text("https://a.io https://b.io http://c.io");
+        assert_eq!(links.ai_score, 20);
+        assert_eq!(links.rationale, "matched: 3 links");
+
+        let capped = RulesModel::score_text("kill yourself, then send sol for your private key");
+        assert_eq!(capped.ai_score, 100);
+        assert_eq!(capped.categories, ["scam", "harassment"]);
+    }
+
+    #[test]
+    fn rules_score_link_by_url_shape() {
+        let clean = RulesModel::score_link("https://example.com/docs");
+        assert_eq!(clean.ai_score, 0);
+        assert!(clean.categories.is_empty());
+
+        // Plain http, a risky TLD and three lure words
+        let lure = RulesModel::score_link("http://claim-airdrop.xyz/wallet");
+        assert_eq!(lure.ai_score, 15 + 20 + 3 * 15);
+        assert_eq!(lure.categories, ["phishing"]);
+
+        assert_eq!(RulesModel::score_link("https://192.168.0.1/").ai_score, 30);
+        assert_eq!(RulesModel::score_link("https://bit.ly/abc").ai_score, 20);
+        assert_eq!(RulesModel::score_link("https://xn--80ak6aa92e.com/").ai_score, 30);
+
+        let malformed = RulesModel::score_link("not a url");
+        assert_eq!(malformed.ai_score, 60);
+        assert_eq!(malformed.categories, ["malformed"]);
+    }
+
+    #[test]
+    fn mock_scores_are_deterministic() {
+        let model = MockModel::new();
+        let text = ModerationInput::Text("hello".into());
+        let score = model.analyze(&text).unwrap().ai_score;
+        assert_eq!(model.analyze(&text).unwrap().ai_score, score);
+        assert_eq!(score, hash_content(b"hello")[0] % 101);
+
+        // Scores follow the canonical bytes, not the input kind
+        let link = ModerationInput::Link("https://example.com".into());
+        let image = ModerationInput::Image(ImageSource::Url("https://example.com".into()));
+        assert_eq!(
+            model.analyze(&link).unwrap().ai_score,
+            model.analyze(&image).unwrap().ai_score
+        );
+
+        let pinned = MockModel::with_score(150);
+        assert_eq!(pinned.analyze(&text).unwrap().ai_score, 100);
+        assert_eq!(pinned.analyze(&link).unwrap().ai_score, 100);
+    }
+}
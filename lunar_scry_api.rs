@@ -0,0 +1,381 @@
+//! `lunar-scry-api`: read-only HTTP API over the indexer database.
+//!
+//! Usage: `lunar-scry-api [--listen <addr>] [--database <file> | --postgres <conn>]`
+//!
+//! Every route answers with JSON and is a thin wrapper around
+//! `lunar_scry_indexer::query`:
+//!
+//! - `GET /content?status=&type=&app=&limit=&offset=`
+//! - `GET /content/<hash>` (hex `content_hash`)
+//! - `GET /pending?within=<seconds>&limit=`
+//! - `GET /voters/<voter>/votes?limit=&offset=`
+//! - `GET /leaderboard?min_votes=&limit=`
+//! - `GET /apps` and `GET /apps/<app>`
+//!
+//! Errors are `{"error": "..."}` with a 400, 404 or 500 status.
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use clap::Parser;
+use lunar_scry_indexer::query::{self, ContentFilter};
+use lunar_scry_indexer::{Database, IndexerError, SqliteDatabase};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default look-ahead for `/pending`: one hour.
+const DEFAULT_PENDING_WINDOW: i64 = 3_600;
+
+#[derive(Parser)]
+#[command(name = "lunar-scry-api", about = "Serve indexed LunarScry data over HTTP")]
+struct Args {
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// SQLite database written by lunar-scry-indexerd
+    #[arg(long, default_value = "lunar_scry_index.db")]
+    database: String,
+    /// Postgres connection string; replaces --database
+    #[cfg(feature = "postgres")]
+    #[arg(long)]
+    postgres: Option<String>,
+}
+
+type Db = Arc<Mutex<dyn Database + Send>>;
+
+enum ApiError {
+    BadRequest(String),
+    NotFound(String),
+    Internal(String),
+}
+
+impl From<IndexerError> for ApiError {
+    fn from(err: IndexerError) -> Self {
+        ApiError::Internal(err.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            ApiError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            ApiError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            ApiError::Internal(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// Runs a query on the blocking pool so a slow scan never stalls the runtime.
+async fn with_db<T, F>(db: Db, query: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut (dyn Database + Send)) -> lunar_scry_indexer::Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        let mut db = db.lock().map_err(|_| ApiError::Internal("database lock poisoned".into()))?;
+        query(&mut *db).map_err(ApiError::from)
+    })
+    .await
+    .map_err(|err| ApiError::Internal(err.to_string()))?
+}
+
+#[derive(Deserialize)]
+struct ContentParams {
+    status: Option<String>,
+    #[serde(rename = "type")]
+    content_type: Option<String>,
+    app: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct PendingParams {
+    within: Option<i64>,
+    limit: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct PageParams {
+    limit: Option<u32>,
+    offset: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct LeaderboardParams {
+    min_votes: Option<u32>,
+    limit: Option<u32>,
+}
+
+fn check_one_of(field: &str, value: &Option<String>, allowed: &[&str]) -> Result<(), ApiError> {
+    match value {
+        Some(value) if !allowed.contains(&value.as_str()) => Err(ApiError::BadRequest(format!(
+            "{} must be one of {}",
+            field,
+            allowed.join(", ")
+        ))),
+        _ => Ok(()),
+    }
+}
+
+async fn list_content(
+    State(db): State<Db>,
+    Query(params): Query<ContentParams>,
+) -> ApiResult<Vec<query::ContentRecord>> {
+    check_one_of("status", &params.status, &["pending", "approved", "rejected"])?;
+    check_one_of("type", &params.content_type, &["text", "image", "link", "video", "defi"])?;
+    let filter = ContentFilter {
+        status: params.status,
+        content_type: params.content_type,
+        app: params.app,
+        limit: params.limit,
+        offset: params.offset,
+    };
+    with_db(db, move |db| query::contents(db, &filter)).await.map(Json)
+}
+
+async fn content_by_hash(
+    State(db): State<Db>,
+    Path(hash): Path<String>,
+) -> ApiResult<query::ContentRecord> {
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ApiError::BadRequest("content hash must be 32 bytes of hex".into()));
+    }
+    let lookup = hash.clone();
+    with_db(db, move |db| query::content_by_hash(db, &lookup))
+        .await?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("no content with hash {}", hash)))
+}
+
+async fn pending(
+    State(db): State<Db>,
+    Query(params): Query<PendingParams>,
+) -> ApiResult<Vec<query::ContentRecord>> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| ApiError::Internal(err.to_string()))?
+        .as_secs() as i64;
+    let window = params.within.unwrap_or(DEFAULT_PENDING_WINDOW).max(0);
+    with_db(db, move |db| query::pending_near_deadline(db, now, window, params.limit))
+        .await
+        .map(Json)
+}
+
+async fn voter_votes(
+    State(db): State<Db>,
+    Path(voter): Path<String>,
+    Query(params): Query<PageParams>,
+) -> ApiResult<Vec<query::VoteRecord>> {
+    with_db(db, move |db| query::votes_by_voter(db, &voter, params.limit, params.offset))
+        .await
+        .map(Json)
+}
+
+async fn leaderboard(
+    State(db): State<Db>,
+    Query(params): Query<LeaderboardParams>,
+) -> ApiResult<Vec<query::LeaderboardEntry>> {
+    let min_votes = params.min_votes.unwrap_or(1);
+    with_db(db, move |db| query::leaderboard(db, min_votes, params.limit))
+        .await
+        .map(Json)
+}
+
+async fn all_apps(State(db): State<Db>) -> ApiResult<Vec<query::AppStats>> {
+    with_db(db, |db| query::app_stats(db, None)).await.map(Json)
+}
+
+async fn one_app(State(db): State<Db>, Path(app): Path<String>) -> ApiResult<query::AppStats> {
+    let lookup = app.clone();
+    with_db(db, move |db| query::app_stats(db, Some(&lookup)))
+        .await?
+        .into_iter()
+        .next()
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("no submissions from app {}", app)))
+}
+
+#[derive(Serialize)]
+struct Health {
+    status: &'static str,
+}
+
+async fn health() -> Json<Health> {
+    Json(Health { status: "ok" })
+}
+
+/// The full route table over `db`. Kept separate from `main` so it can be
+/// served from an in-memory database (`SqliteDatabase::open_in_memory`).
+fn router(db: Db) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/content", get(list_content))
+        .route("/content/:hash", get(content_by_hash))
+        .route("/pending", get(pending))
+        .route("/voters/:voter/votes", get(voter_votes))
+        .route("/leaderboard", get(leaderboard))
+        .route("/apps", get(all_apps))
+        .route("/apps/:app", get(one_app))
+        .with_state(db)
+}
+
+// The database is opened before the runtime starts: the synchronous Postgres
+// client spins up its own runtime and refuses to run inside another one.
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    #[cfg(feature = "postgres")]
+    let db: Db = match &args.postgres {
+        Some(params) => Arc::new(Mutex::new(lunar_scry_indexer::PostgresDatabase::connect(params)?)),
+        None => Arc::new(Mutex::new(SqliteDatabase::open(&args.database)?)),
+    };
+    #[cfg(not(feature = "postgres"))]
+    let db: Db = Arc::new(Mutex::new(SqliteDatabase::open(&args.database)?));
+
+    tokio::runtime::Runtime::new()?.block_on(async {
+        let listener = tokio::net::TcpListener::bind(&args.listen).await?;
+        eprintln!("listening on {}", args.listen);
+        axum::serve(listener, router(db)).await
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::Request;
+    use http_body_util::BodyExt;
+    use lunar_scry_indexer::Statement;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    const APPROVED_HASH: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const PENDING_HASH: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+    /// Approved text content from `app`, plus link content long past its
+    /// deadline and still undecided. `voter` voted with the verdict.
+    fn seeded() -> Router {
+        let mut db = SqliteDatabase::open_in_memory().unwrap();
+        let rows = [
+            (
+                "INSERT INTO content_submissions (content_id, submitter, content_hash, content_type, \
+                 ai_score, voting_period, app, submitted_at, signature, event_index, slot) \
+                 VALUES ('approved', 'submitter', ?, 'text', 20, 86400, 'app', 200, 'a', 0, 1)",
+                APPROVED_HASH,
+            ),
+            (
+                "INSERT INTO content_submissions (content_id, submitter, content_hash, content_type, \
+                 ai_score, voting_period, app, submitted_at, signature, event_index, slot) \
+                 VALUES ('pending', 'submitter', ?, 'link', 60, 86400, NULL, 100, 'b', 0, 2)",
+                PENDING_HASH,
+            ),
+        ];
+        let mut statements: Vec<Statement> = rows
+            .iter()
+            .map(|(sql, hash)| Statement::new(*sql, vec![(*hash).into()]))
+            .collect();
+        statements.push(Statement::new(
+            "INSERT INTO decisions (content_id, status, approve_votes, reject_votes, total_stake, \
+             finalized_at, signature, event_index, slot) \
+             VALUES ('approved', 'approved', 1, 0, 500, 90000, 'c', 0, 3)",
+            Vec::new(),
+        ));
+        statements.push(Statement::new(
+            "INSERT INTO votes (content_id, voter, vote_type, stake_amount, reward_epoch, \
+             reward_weight, early_bonus_percentage, voted_at, signature, event_index, slot) \
+             VALUES ('approved', 'voter', 'approve', 500, 0, 500, 0, 300, 'd', 0, 4)",
+            Vec::new(),
+        ));
+        db.execute(&statements).unwrap();
+        router(Arc::new(Mutex::new(db)))
+    }
+
+    async fn get(router: &Router, uri: &str) -> (StatusCode, Value) {
+        let response = router
+            .clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn content_ids(body: &Value) -> Vec<&str> {
+        body.as_array()
+            .unwrap()
+            .iter()
+            .map(|record| record["content_id"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn content_routes_filter_and_validate() {
+        let router = seeded();
+
+        let (status, body) = get(&router, "/content").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_ids(&body), ["approved", "pending"]);
+
+        let (_, body) = get(&router, "/content?status=pending").await;
+        assert_eq!(content_ids(&body), ["pending"]);
+        let (_, body) = get(&router, "/content?type=text&app=app").await;
+        assert_eq!(content_ids(&body), ["approved"]);
+        let (_, body) = get(&router, "/content?limit=1&offset=1").await;
+        assert_eq!(content_ids(&body), ["pending"]);
+
+        let (status, body) = get(&router, "/content?status=flagged").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().starts_with("status must be one of"));
+
+        let uri = format!("/content/{}", APPROVED_HASH.to_uppercase());
+        let (status, body) = get(&router, &uri).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "approved");
+        assert_eq!(body["voting_deadline"], 200 + 86_400);
+        assert_eq!(body["approve_stake"], 500);
+
+        let (status, _) = get(&router, &format!("/content/{}", "0".repeat(64))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = get(&router, "/content/abc").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, body) = get(&router, "/pending").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_ids(&body), ["pending"]);
+    }
+
+    #[tokio::test]
+    async fn voter_leaderboard_and_app_routes() {
+        let router = seeded();
+
+        let (_, body) = get(&router, "/voters/voter/votes").await;
+        assert_eq!(body[0]["content_id"], "approved");
+        assert_eq!(body[0]["aligned"], true);
+
+        let (_, body) = get(&router, "/leaderboard").await;
+        assert_eq!(body[0]["voter"], "voter");
+        assert_eq!(body[0]["accuracy"], 1.0);
+        let (_, body) = get(&router, "/leaderboard?min_votes=2").await;
+        assert_eq!(body, serde_json::json!([]));
+
+        let (status, body) = get(&router, "/apps/app").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["submissions"], 1);
+        assert_eq!(body["approved"], 1);
+        let (status, body) = get(&router, "/apps").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.as_array().unwrap().len(), 1);
+        let (status, _) = get(&router, "/apps/unknown").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,553 @@
+//! `lunarscry`: operator CLI for the LunarScry program.
+//!
+//! Usage: `lunarscry [--url <rpc>] [--keypair <file>] [--json] <command>`
+//!
+//! Defaults target a local validator (`solana-test-validator`) with the
+//! Solana CLI's default keypair. `--json` prints one JSON object per command
+//! for scripting; otherwise the same fields are printed as `key: value` lines.
+
+use anchor_lang::{AccountDeserialize, Discriminator};
+use clap::{Parser, Subcommand, ValueEnum};
+use lunar_scry::{
+    AdminAction, AdminActionKind, Content, ContentData, EarlyBonusCurve, EarlyVoterBonus,
+    ProtocolConfig, ProtocolState, RewardEpoch, Verdict, Vote, VoteType,
+};
+use lunar_scry_client::{hash_content, ix, names, pda, LunarScryClient};
+use serde_json::{json, Map, Value};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use spl_associated_token_account::get_associated_token_address;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "lunarscry", about = "Administer and interact with the LunarScry program")]
+struct Cli {
+    #[arg(long, global = true, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Signing keypair; defaults to ~/.config/solana/id.json
+    #[arg(long, global = true)]
+    keypair: Option<PathBuf>,
+    /// Print machine-readable JSON
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the protocol state, vaults and roles
+    Init {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long, default_value_t = 1_000_000)]
+        stake_required: u64,
+        #[arg(long, default_value_t = 86_400)]
+        voting_period: i64,
+        #[arg(long, default_value_t = 51)]
+        quorum: u8,
+        #[arg(long, default_value_t = 1_000_000)]
+        reward_per_vote: u64,
+    },
+    /// Print the protocol configuration and counters
+    ShowConfig,
+    /// Submit content for moderation
+    Submit {
+        /// Content to hash; use --file for binary content
+        #[arg(long, conflicts_with = "file")]
+        content: Option<String>,
+        #[arg(long)]
+        file: Option<PathBuf>,
+        #[arg(long = "type", default_value = "text")]
+        content_type: String,
+        #[arg(long)]
+        ai_score: u8,
+        /// Attach the signer's app registration for callbacks
+        #[arg(long)]
+        via_app: bool,
+    },
+    /// Vote on content, staking GUARD
+    Vote {
+        content: Pubkey,
+        #[arg(value_enum)]
+        choice: VoteChoice,
+        #[arg(long)]
+        stake: u64,
+    },
+    /// Finalize content whose voting period has ended
+    Finalize { content: Pubkey },
+    /// Claim epoch rewards for a vote once its epoch is closed
+    Claim { content: Pubkey },
+    /// Pause the whole protocol (requires the Pauser role)
+    Pause,
+    /// Lift a full pause
+    Unpause {
+        #[arg(long)]
+        admin_action: Option<u64>,
+    },
+    /// Manage emergency admins
+    EmergencyAdmin {
+        #[command(subcommand)]
+        command: EmergencyAdminCommand,
+    },
+    /// Propose, approve and inspect multisig admin actions
+    AdminAction {
+        #[command(subcommand)]
+        command: AdminActionCommand,
+    },
+    /// Decode and print any LunarScry account
+    Dump { address: Pubkey },
+}
+
+#[derive(Subcommand)]
+enum EmergencyAdminCommand {
+    /// Add an emergency admin; the new admin co-signs the transaction
+    Add {
+        #[arg(long)]
+        new_admin_keypair: PathBuf,
+        #[arg(long)]
+        admin_action: Option<u64>,
+    },
+    Remove {
+        admin: Pubkey,
+        #[arg(long)]
+        admin_action: Option<u64>,
+    },
+    List,
+}
+
+#[derive(Subcommand)]
+enum AdminActionCommand {
+    /// Propose an action; the proposal counts as the signer's approval
+    Propose {
+        #[command(subcommand)]
+        kind: ProposedAction,
+    },
+    Approve { action_id: u64 },
+    Show { action_id: u64 },
+}
+
+/// Actions that take no structured configuration. The rest are proposed
+/// through the client library.
+#[derive(Subcommand)]
+enum ProposedAction {
+    Unpause,
+    AddEmergencyAdmin { admin: Pubkey },
+    RemoveEmergencyAdmin { admin: Pubkey },
+    SetThreshold { threshold: u8 },
+    ResetCircuitBreaker,
+    ForfeitVesting { owner: Pubkey },
+    UnpauseApp { app: Pubkey },
+}
+
+impl From<ProposedAction> for AdminActionKind {
+    fn from(action: ProposedAction) -> Self {
+        match action {
+            ProposedAction::Unpause => AdminActionKind::Unpause,
+            ProposedAction::AddEmergencyAdmin { admin } => AdminActionKind::AddEmergencyAdmin { admin },
+            ProposedAction::RemoveEmergencyAdmin { admin } => {
+                AdminActionKind::RemoveEmergencyAdmin { admin }
+            }
+            ProposedAction::SetThreshold { threshold } => AdminActionKind::SetThreshold { threshold },
+            ProposedAction::ResetCircuitBreaker => AdminActionKind::ResetCircuitBreaker,
+            ProposedAction::ForfeitVesting { owner } => AdminActionKind::ForfeitVesting { owner },
+            ProposedAction::UnpauseApp { app } => AdminActionKind::UnpauseApp { app },
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum VoteChoice {
+    Approve,
+    Reject,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let json = cli.json;
+    if let Err(err) = run(cli) {
+        if json {
+            println!("{}", json!({ "error": err.to_string() }));
+        } else {
+            eprintln!("error: {}", err);
+        }
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let keypair_path = match cli.keypair {
+        Some(path) => path,
+        None => dirs::home_dir()
+            .ok_or("cannot locate home directory; pass --keypair")?
+            .join(".config/solana/id.json"),
+    };
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| format!("reading {}: {}", keypair_path.display(), err))?;
+    let client = LunarScryClient::new(&cli.url, payer);
+    let signer = client.payer().pubkey();
+
+    let output = match cli.command {
+        Command::Init {
+            mint,
+            stake_required,
+            voting_period,
+            quorum,
+            reward_per_vote,
+        } => {
+            let config = ProtocolConfig {
+                stake_required,
+                voting_period,
+                quorum_percentage: quorum,
+                reward_per_vote,
+                early_voter_bonus: EarlyVoterBonus {
+                    max_bonus_percentage: 0,
+                    curve: EarlyBonusCurve::None,
+                },
+            };
+            let signature = client.send(
+                &[ix::initialize(&signer, &mint, config), ix::initialize_roles(&signer)],
+                &[],
+            )?;
+            transaction(signature, [
+                ("protocol_state", json!(pda::protocol().to_string())),
+                ("reward_vault", json!(pda::reward_vault().to_string())),
+                ("treasury", json!(pda::treasury_vault().to_string())),
+            ])
+        }
+        Command::ShowConfig => protocol_json(&client.protocol_state()?),
+        Command::Submit {
+            content,
+            file,
+            content_type,
+            ai_score,
+            via_app,
+        } => {
+            let bytes = match (content, file) {
+                (Some(content), _) => content.into_bytes(),
+                (None, Some(file)) => fs::read(file)?,
+                (None, None) => return Err("pass --content or --file".into()),
+            };
+            let content_type = names::parse_content_type(&content_type)
+                .ok_or_else(|| format!("unknown content type {:?}", content_type))?;
+            let content_hash = hash_content(&bytes);
+            let token_account = get_associated_token_address(&signer, &client.guard_mint()?);
+            let treasury = client.protocol_state()?.treasury;
+
+            let signature = client.send(
+                &[ix::submit_content(
+                    &signer,
+                    &token_account,
+                    &treasury,
+                    ContentData {
+                        content_hash,
+                        content_type,
+                        ai_score,
+                    },
+                    via_app,
+                )],
+                &[],
+            )?;
+            transaction(signature, [
+                ("content", json!(pda::content(&content_hash).to_string())),
+                ("content_hash", json!(hex(&content_hash))),
+            ])
+        }
+        Command::Vote {
+            content,
+            choice,
+            stake,
+        } => {
+            let vote_type = match choice {
+                VoteChoice::Approve => VoteType::Approve,
+                VoteChoice::Reject => VoteType::Reject,
+            };
+            let protocol = client.protocol_state()?;
+            let mint = client.guard_mint()?;
+            let token_account = get_associated_token_address(&signer, &mint);
+
+            let signature = client.send(
+                &[ix::cast_vote(
+                    &signer,
+                    &content,
+                    &token_account,
+                    &mint,
+                    protocol.current_epoch,
+                    vote_type,
+                    stake,
+                )],
+                &[],
+            )?;
+            transaction(signature, [("vote", json!(pda::vote(&content, &signer).to_string()))])
+        }
+        Command::Finalize { content } => {
+            let treasury = client.protocol_state()?.treasury;
+            let signature = client.send(&[ix::finalize_decision(&content, &treasury, None)], &[])?;
+            let content = client.content(&content)?;
+            transaction(signature, [(
+                "status",
+                json!(names::content_status(content.status)),
+            )])
+        }
+        Command::Claim { content } => {
+            let protocol = client.protocol_state()?;
+            let vote = client.vote(&content, &signer)?;
+            let token_account = get_associated_token_address(&signer, &client.guard_mint()?);
+
+            let signature = client.send(
+                &[ix::claim_epoch_rewards(
+                    &signer,
+                    &content,
+                    vote.reward_epoch,
+                    &protocol.treasury,
+                    &token_account,
+                    protocol.reward_vesting.enabled,
+                )],
+                &[],
+            )?;
+            transaction(signature, [("reward_epoch", json!(vote.reward_epoch))])
+        }
+        Command::Pause => {
+            let with_roles = !client.protocol_state()?.emergency_admins.contains(&signer);
+            transaction(client.send(&[ix::pause_protocol(&signer, with_roles)], &[])?, [])
+        }
+        Command::Unpause { admin_action } => transaction(
+            client.send(&[ix::unpause_protocol(&signer, admin_action)], &[])?,
+            [],
+        ),
+        Command::EmergencyAdmin { command } => match command {
+            EmergencyAdminCommand::Add {
+                new_admin_keypair,
+                admin_action,
+            } => {
+                let new_admin: Keypair = read_keypair_file(&new_admin_keypair)
+                    .map_err(|err| format!("reading {}: {}", new_admin_keypair.display(), err))?;
+                let signature = client.send(
+                    &[ix::add_emergency_admin(&signer, &new_admin.pubkey(), admin_action)],
+                    &[&new_admin],
+                )?;
+                transaction(signature, [("added", json!(new_admin.pubkey().to_string()))])
+            }
+            EmergencyAdminCommand::Remove { admin, admin_action } => {
+                let signature = client.send(
+                    &[ix::remove_emergency_admin(&signer, &admin, admin_action)],
+                    &[],
+                )?;
+                transaction(signature, [("removed", json!(admin.to_string()))])
+            }
+            EmergencyAdminCommand::List => {
+                let protocol = client.protocol_state()?;
+                json!({
+                    "emergency_admins": keys(&protocol.emergency_admins),
+                    "admin_threshold": protocol.admin_threshold,
+                })
+            }
+        },
+        Command::AdminAction { command } => match command {
+            AdminActionCommand::Propose { kind } => {
+                let action_id = client.protocol_state()?.admin_action_count;
+                let signature = client.send(
+                    &[ix::propose_admin_action(&signer, action_id, kind.into())],
+                    &[],
+                )?;
+                transaction(signature, [
+                    ("action_id", json!(action_id)),
+                    ("admin_action", json!(pda::admin_action(action_id).to_string())),
+                ])
+            }
+            AdminActionCommand::Approve { action_id } => {
+                let signature = client.send(&[ix::approve_admin_action(&signer, action_id)], &[])?;
+                let action: AdminAction = client.fetch(&pda::admin_action(action_id))?;
+                transaction(signature, [("approvals", json!(action.approvals.len()))])
+            }
+            AdminActionCommand::Show { action_id } => {
+                let address = pda::admin_action(action_id);
+                let mut output = admin_action_json(&client.fetch(&address)?);
+                if let Value::Object(fields) = &mut output {
+                    fields.insert("address".into(), json!(address.to_string()));
+                    fields.insert("threshold".into(), json!(client.protocol_state()?.admin_threshold));
+                }
+                output
+            }
+        },
+        Command::Dump { address } => dump(&client, &address)?,
+    };
+
+    print(&output, cli.json);
+    Ok(())
+}
+
+fn dump(client: &LunarScryClient, address: &Pubkey) -> Result<Value, Box<dyn Error>> {
+    let data = client.rpc().get_account_data(address)?;
+    let discriminator = data.get(..8).ok_or("account is too small to be a LunarScry account")?;
+
+    fn decode<T: AccountDeserialize>(data: &[u8]) -> Result<T, Box<dyn Error>> {
+        Ok(T::try_deserialize(&mut &data[..])?)
+    }
+
+    let (kind, value) = if discriminator == ProtocolState::discriminator() {
+        ("ProtocolState", protocol_json(&decode(&data)?))
+    } else if discriminator == Content::discriminator() {
+        ("Content", content_json(&decode(&data)?))
+    } else if discriminator == Vote::discriminator() {
+        ("Vote", vote_json(&decode(&data)?))
+    } else if discriminator == Verdict::discriminator() {
+        ("Verdict", verdict_json(&decode(&data)?))
+    } else if discriminator == RewardEpoch::discriminator() {
+        ("RewardEpoch", epoch_json(&decode(&data)?))
+    } else if discriminator == AdminAction::discriminator() {
+        ("AdminAction", admin_action_json(&decode(&data)?))
+    } else {
+        return Err(format!("{} is not an account type this CLI can decode", address).into());
+    };
+
+    let mut object = Map::new();
+    object.insert("address".into(), json!(address.to_string()));
+    object.insert("kind".into(), json!(kind));
+    if let Value::Object(fields) = value {
+        object.extend(fields);
+    }
+    Ok(Value::Object(object))
+}
+
+fn protocol_json(protocol: &ProtocolState) -> Value {
+    json!({
+        "admin": protocol.admin.to_string(),
+        "pending_admin": protocol.pending_admin.map(|admin| admin.to_string()),
+        "treasury": protocol.treasury.to_string(),
+        "version": protocol.version,
+        "is_paused": protocol.is_paused,
+        "pause_flags": protocol.pause_flags,
+        "paused_content_types": protocol.paused_content_types,
+        "circuit_breaker_tripped": protocol.circuit_breaker_tripped,
+        "stake_required": protocol.stake_required,
+        "voting_period": protocol.voting_period,
+        "quorum_percentage": protocol.quorum_percentage,
+        "reward_per_vote": protocol.reward_per_vote,
+        "early_voter_max_bonus_percentage": protocol.early_voter_bonus.max_bonus_percentage,
+        "reward_vesting_enabled": protocol.reward_vesting.enabled,
+        "submission_fee": protocol.treasury_config.submission_fee,
+        "treasury_spend_limit": protocol.treasury_config.spend_limit,
+        "treasury_spend_period": protocol.treasury_config.spend_period,
+        "keeper_fee": protocol.treasury_config.keeper_fee,
+        "current_epoch": protocol.current_epoch,
+        "reserved_rewards": protocol.reserved_rewards,
+        "total_rewards_distributed": protocol.total_rewards_distributed,
+        "daily_submission_count": protocol.daily_submission_count,
+        "daily_vote_count": protocol.daily_vote_count,
+        "emergency_admins": keys(&protocol.emergency_admins),
+        "admin_threshold": protocol.admin_threshold,
+        "oracles": keys(&protocol.oracles),
+        "proposal_count": protocol.proposal_count,
+        "content_tree_count": protocol.content_tree_count,
+    })
+}
+
+fn content_json(content: &Content) -> Value {
+    json!({
+        "submitter": content.submitter.to_string(),
+        "content_hash": hex(&content.content_hash),
+        "content_type": names::content_type(content.content_type),
+        "ai_score": content.ai_score,
+        "status": names::content_status(content.status),
+        "submission_time": content.submission_time,
+        "voting_period": content.voting_period,
+        "finalized_at": content.finalized_at,
+        "approve_votes": content.approve_votes,
+        "reject_votes": content.reject_votes,
+        "total_stake": content.total_stake,
+        "vote_count": content.vote_count,
+        "closed_vote_count": content.closed_vote_count,
+        "app": content.app.map(|app| app.to_string()),
+        "version": content.version,
+    })
+}
+
+fn vote_json(vote: &Vote) -> Value {
+    json!({
+        "voter": vote.voter.to_string(),
+        "content_id": vote.content_id.to_string(),
+        "vote_type": names::vote_type(vote.vote_type),
+        "stake_amount": vote.stake_amount,
+        "vote_timestamp": vote.vote_timestamp,
+        "reward_epoch": vote.reward_epoch,
+        "reward_weight": vote.reward_weight,
+        "epoch_reward_claimed": vote.epoch_reward_claimed,
+        "early_bonus_percentage": vote.early_bonus_percentage,
+    })
+}
+
+fn verdict_json(verdict: &Verdict) -> Value {
+    json!({
+        "content_id": verdict.content_id.to_string(),
+        "content_hash": hex(&verdict.content_hash),
+        "content_type": names::content_type(verdict.content_type),
+        "status": names::content_status(verdict.status),
+        "ai_score": verdict.ai_score,
+        "approve_votes": verdict.approve_votes,
+        "reject_votes": verdict.reject_votes,
+        "finalized_at": verdict.finalized_at,
+    })
+}
+
+fn epoch_json(epoch: &RewardEpoch) -> Value {
+    json!({
+        "epoch": epoch.epoch,
+        "start_timestamp": epoch.start_timestamp,
+        "end_timestamp": epoch.end_timestamp,
+        "is_closed": epoch.is_closed,
+        "total_weight": epoch.total_weight,
+        "claimed_weight": epoch.claimed_weight,
+        "reward_pool": epoch.reward_pool,
+        "reward_per_weight": epoch.reward_per_weight.to_string(),
+        "merkle_root_posted": epoch.merkle_root_posted,
+    })
+}
+
+fn admin_action_json(action: &AdminAction) -> Value {
+    json!({
+        "id": action.id,
+        "kind": names::admin_action(&action.kind),
+        "proposer": action.proposer.to_string(),
+        "approvals": keys(&action.approvals),
+        "created_at": action.created_at,
+        "executed": action.executed,
+    })
+}
+
+fn transaction<const N: usize>(signature: Signature, fields: [(&str, Value); N]) -> Value {
+    let mut object = Map::new();
+    object.insert("signature".into(), json!(signature.to_string()));
+    for (key, value) in fields {
+        object.insert(key.into(), value);
+    }
+    Value::Object(object)
+}
+
+fn print(output: &Value, as_json: bool) {
+    if as_json {
+        println!("{}", output);
+        return;
+    }
+    match output {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                match value {
+                    Value::String(text) => println!("{}: {}", key, text),
+                    other => println!("{}: {}", key, other),
+                }
+            }
+        }
+        other => println!("{}", other),
+    }
+}
+
+fn keys(keys: &[Pubkey]) -> Vec<String> {
+    keys.iter().map(Pubkey::to_string).collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
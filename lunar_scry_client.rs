@@ -0,0 +1,1126 @@
+//! `lunar-scry-client`: native Rust client for the LunarScry program.
+//!
+//! Instruction builders return plain [`Instruction`]s so they can be batched
+//! with anything else in a transaction; [`LunarScryClient`] adds the RPC side
+//! (sending, fetching accounts and decoding the events a transaction emitted).
+//! Account and instruction layouts come straight from the `lunar_scry` crate,
+//! so the client never drifts from the program it was built against.
+//!
+//! This file is the whole crate, but the repository has no Cargo manifest for
+//! it. Build it as the library of your own `lunar-scry-client` package that
+//! depends on `lunar_scry` (with `no-entrypoint`), `anchor-lang`, `anchor-spl`,
+//! `solana-client`, `solana-sdk`, `solana-transaction-status`, `base64`, `log`
+//! and `thiserror`. Skipped data is reported through `log`; binaries pick
+//! the logger.
+
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, Discriminator};
+use anchor_spl::token::TokenAccount;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use lunar_scry::{constants, Content, ContentData, ContentStatus, ContentType, ProtocolState, Vote};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+
+pub use lunar_scry::ID as PROGRAM_ID;
+
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+/// Byte offset of `Content::status`: discriminator, submitter, content_hash,
+/// content_type, ai_score and submission_time come first.
+const CONTENT_STATUS_OFFSET: usize = 8 + 32 + 32 + 1 + 1 + 8;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("rpc error: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+    #[error("account {0} could not be deserialized: {1}")]
+    Deserialize(Pubkey, anchor_lang::error::Error),
+    #[error("transaction {0} has no log messages")]
+    MissingLogs(Signature),
+    #[error("event data could not be decoded: {0}")]
+    Event(String),
+    #[error("sysvar {0} could not be decoded")]
+    Sysvar(Pubkey),
+}
+
+pub type Result<T> = std::result::Result<T, ClientError>;
+
+/// Digest used for `ContentData::content_hash`. The program accepts any
+/// 32-byte hash, but all of our off-chain tools agree on this one.
+pub fn hash_content(content: &[u8]) -> [u8; 32] {
+    solana_sdk::keccak::hash(content).to_bytes()
+}
+
+/// Domain separator for [`attestation_message`].
+const ATTESTATION_PREFIX: &[u8] = b"lunarscry-attestation-v1";
+
+/// Bytes an AI oracle signs to vouch for the `ContentData` it produced at
+/// `analyzed_at`. Verifiers rebuild this from the submitted content and check
+/// the signer against `ProtocolState::oracles`.
+pub fn attestation_message(content_data: &ContentData, analyzed_at: i64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(ATTESTATION_PREFIX.len() + 32 + 2 + 8);
+    message.extend_from_slice(ATTESTATION_PREFIX);
+    message.extend_from_slice(&content_data.content_hash);
+    message.push(content_data.content_type as u8);
+    message.push(content_data.ai_score);
+    message.extend_from_slice(&analyzed_at.to_le_bytes());
+    message
+}
+
+/// Stable lowercase names for program enums, shared by every tool that
+/// prints or stores them.
+pub mod names {
+    use lunar_scry::{
+        AdminActionKind, ContentStatus, ContentType, Role, TreasuryMovementKind, VoteType,
+    };
+
+    pub fn content_status(status: ContentStatus) -> &'static str {
+        match status {
+            ContentStatus::Pending => "pending",
+            ContentStatus::Approved => "approved",
+            ContentStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn content_type(content_type: ContentType) -> &'static str {
+        match content_type {
+            ContentType::Text => "text",
+            ContentType::Image => "image",
+            ContentType::Link => "link",
+            ContentType::Video => "video",
+            ContentType::DeFi => "defi",
+        }
+    }
+
+    pub fn parse_content_type(name: &str) -> Option<ContentType> {
+        match name {
+            "text" => Some(ContentType::Text),
+            "image" => Some(ContentType::Image),
+            "link" => Some(ContentType::Link),
+            "video" => Some(ContentType::Video),
+            "defi" => Some(ContentType::DeFi),
+            _ => None,
+        }
+    }
+
+    pub fn vote_type(vote_type: VoteType) -> &'static str {
+        match vote_type {
+            VoteType::Approve => "approve",
+            VoteType::Reject => "reject",
+        }
+    }
+
+    pub fn role(role: Role) -> &'static str {
+        match role {
+            Role::Pauser => "pauser",
+            Role::OracleManager => "oracle_manager",
+            Role::TreasuryManager => "treasury_manager",
+            Role::ConfigManager => "config_manager",
+            Role::Keeper => "keeper",
+        }
+    }
+
+    pub fn treasury_movement(kind: TreasuryMovementKind) -> &'static str {
+        match kind {
+            TreasuryMovementKind::SubmissionFee => "submission_fee",
+            TreasuryMovementKind::ForfeitedVesting => "forfeited_vesting",
+            TreasuryMovementKind::EmissionFunding => "emission_funding",
+            TreasuryMovementKind::GovernanceSpend => "governance_spend",
+            TreasuryMovementKind::Withdrawal => "withdrawal",
+            TreasuryMovementKind::KeeperFee => "keeper_fee",
+            TreasuryMovementKind::ForfeitedReward => "forfeited_reward",
+        }
+    }
+
+    pub fn admin_action(kind: &AdminActionKind) -> &'static str {
+        match kind {
+            AdminActionKind::Unpause => "unpause",
+            AdminActionKind::AddEmergencyAdmin { .. } => "add_emergency_admin",
+            AdminActionKind::RemoveEmergencyAdmin { .. } => "remove_emergency_admin",
+            AdminActionKind::SetThreshold { .. } => "set_threshold",
+            AdminActionKind::SetRewardVesting { .. } => "set_reward_vesting",
+            AdminActionKind::InitializeEmissionSchedule { .. } => "initialize_emission_schedule",
+            AdminActionKind::UpdateEmissionSchedule { .. } => "update_emission_schedule",
+            AdminActionKind::GrantRole { .. } => "grant_role",
+            AdminActionKind::RevokeRole { .. } => "revoke_role",
+            AdminActionKind::SetPauseFlags { .. } => "set_pause_flags",
+            AdminActionKind::SetCircuitBreakerConfig { .. } => "set_circuit_breaker_config",
+            AdminActionKind::ResetCircuitBreaker => "reset_circuit_breaker",
+            AdminActionKind::SetTreasuryConfig { .. } => "set_treasury_config",
+            AdminActionKind::ProposeConfigUpdate { .. } => "propose_config_update",
+            AdminActionKind::CancelConfigUpdate { .. } => "cancel_config_update",
+            AdminActionKind::ForfeitVesting { .. } => "forfeit_vesting",
+            AdminActionKind::UnpauseApp { .. } => "unpause_app",
+        }
+    }
+}
+
+/// Program-derived addresses, mirroring the seeds in the program's contexts.
+pub mod pda {
+    use super::PROGRAM_ID;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn find(seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &PROGRAM_ID).0
+    }
+
+    pub fn protocol() -> Pubkey {
+        find(&[b"protocol"])
+    }
+
+    pub fn content(content_hash: &[u8; 32]) -> Pubkey {
+        find(&[b"content", content_hash])
+    }
+
+    pub fn vote(content: &Pubkey, voter: &Pubkey) -> Pubkey {
+        find(&[b"vote", content.as_ref(), voter.as_ref()])
+    }
+
+    pub fn vote_escrow(vote: &Pubkey) -> Pubkey {
+        find(&[b"vote_escrow", vote.as_ref()])
+    }
+
+    pub fn verdict(content: &Pubkey) -> Pubkey {
+        find(&[b"verdict", content.as_ref()])
+    }
+
+    pub fn reward_epoch(epoch: u64) -> Pubkey {
+        find(&[b"reward_epoch", &epoch.to_le_bytes()])
+    }
+
+    pub fn reward_vault() -> Pubkey {
+        find(&[b"reward_vault"])
+    }
+
+    pub fn treasury_vault() -> Pubkey {
+        find(&[b"treasury_vault"])
+    }
+
+    pub fn emission_schedule() -> Pubkey {
+        find(&[b"emission_schedule"])
+    }
+
+    pub fn vesting(owner: &Pubkey) -> Pubkey {
+        find(&[b"vesting", owner.as_ref()])
+    }
+
+    pub fn roles() -> Pubkey {
+        find(&[b"roles"])
+    }
+
+    pub fn admin_action(id: u64) -> Pubkey {
+        find(&[b"admin_action", &id.to_le_bytes()])
+    }
+
+    pub fn app(authority: &Pubkey) -> Pubkey {
+        find(&[b"app", authority.as_ref()])
+    }
+
+    pub fn content_tree(id: u64) -> Pubkey {
+        find(&[b"content_tree", &id.to_le_bytes()])
+    }
+}
+
+/// Instruction builders. Arguments are the accounts the caller has to choose;
+/// everything derivable is derived. `admin_action` is the id of an approved
+/// `AdminAction` when the protocol's admin threshold is above one.
+pub mod ix {
+    use super::{pda, PROGRAM_ID};
+    use anchor_lang::{InstructionData, ToAccountMetas};
+    use lunar_scry::{
+        accounts, instruction, AdminActionKind, ContentData, ProtocolConfig, Role, VoteType,
+    };
+    use solana_sdk::instruction::Instruction;
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::{system_program, sysvar};
+
+    fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: accounts.to_account_metas(None),
+            data: data.data(),
+        }
+    }
+
+    pub fn initialize(admin: &Pubkey, guard_mint: &Pubkey, config: ProtocolConfig) -> Instruction {
+        build(
+            accounts::Initialize {
+                protocol_state: pda::protocol(),
+                reward_epoch: pda::reward_epoch(0),
+                reward_vault: pda::reward_vault(),
+                guard_mint: *guard_mint,
+                treasury: pda::treasury_vault(),
+                admin: *admin,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::Initialize { config },
+        )
+    }
+
+    /// `via_app` attaches the submitter's `AppRegistration` for callbacks.
+    /// `treasury` is `ProtocolState::treasury`, here and below.
+    pub fn submit_content(
+        submitter: &Pubkey,
+        submitter_token_account: &Pubkey,
+        treasury: &Pubkey,
+        content_data: ContentData,
+        via_app: bool,
+    ) -> Instruction {
+        let content = pda::content(&content_data.content_hash);
+        build(
+            accounts::SubmitContent {
+                protocol_state: pda::protocol(),
+                content,
+                verdict: pda::verdict(&content),
+                treasury: *treasury,
+                submitter_token_account: *submitter_token_account,
+                app_registration: via_app.then(|| pda::app(submitter)),
+                submitter: *submitter,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::SubmitContent { content_data },
+        )
+    }
+
+    /// `current_epoch` is `ProtocolState::current_epoch`; votes accrue to the open epoch.
+    pub fn cast_vote(
+        voter: &Pubkey,
+        content: &Pubkey,
+        voter_token_account: &Pubkey,
+        guard_mint: &Pubkey,
+        current_epoch: u64,
+        vote_type: VoteType,
+        stake_amount: u64,
+    ) -> Instruction {
+        build(
+            accounts::CastVote {
+                protocol_state: pda::protocol(),
+                content: *content,
+                vote_account: pda::vote(content, voter),
+                reward_epoch: pda::reward_epoch(current_epoch),
+                vote_escrow: pda::vote_escrow(&pda::vote(content, voter)),
+                reward_vault: pda::reward_vault(),
+                guard_mint: *guard_mint,
+                voter_token_account: *voter_token_account,
+                voter: *voter,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+                rent: sysvar::rent::ID,
+            },
+            instruction::CastVote { vote_type, stake_amount },
+        )
+    }
+
+    /// Pass `keeper_token_account` to collect the keeper fee, if one is set.
+    pub fn finalize_decision(
+        content: &Pubkey,
+        treasury: &Pubkey,
+        keeper_token_account: Option<&Pubkey>,
+    ) -> Instruction {
+        build(
+            accounts::FinalizeDecision {
+                protocol_state: pda::protocol(),
+                content: *content,
+                treasury: keeper_token_account.map(|_| *treasury),
+                keeper_token_account: keeper_token_account.copied(),
+                token_program: anchor_spl::token::ID,
+            },
+            instruction::FinalizeDecision {},
+        )
+    }
+
+    /// `reward_epoch` is `Vote::reward_epoch`; `vesting` must match whether
+    /// reward vesting is enabled on the protocol.
+    pub fn claim_epoch_rewards(
+        voter: &Pubkey,
+        content: &Pubkey,
+        reward_epoch: u64,
+        treasury: &Pubkey,
+        voter_token_account: &Pubkey,
+        vesting: bool,
+    ) -> Instruction {
+        build(
+            accounts::ClaimEpochRewards {
+                protocol_state: pda::protocol(),
+                content: *content,
+                vote_account: pda::vote(content, voter),
+                reward_epoch: pda::reward_epoch(reward_epoch),
+                reward_vault: pda::reward_vault(),
+                treasury: *treasury,
+                voter_token_account: *voter_token_account,
+                vesting_account: vesting.then(|| pda::vesting(voter)),
+                voter: *voter,
+                token_program: anchor_spl::token::ID,
+            },
+            instruction::ClaimEpochRewards {},
+        )
+    }
+
+    /// `with_emission` must be set once an emission schedule is configured.
+    pub fn close_reward_epoch(
+        payer: &Pubkey,
+        current_epoch: u64,
+        with_emission: bool,
+        treasury: &Pubkey,
+        keeper_token_account: Option<&Pubkey>,
+    ) -> Instruction {
+        build(
+            accounts::CloseRewardEpoch {
+                protocol_state: pda::protocol(),
+                reward_epoch: pda::reward_epoch(current_epoch),
+                next_reward_epoch: pda::reward_epoch(current_epoch + 1),
+                reward_vault: pda::reward_vault(),
+                emission_schedule: with_emission.then(pda::emission_schedule),
+                treasury: (with_emission || keeper_token_account.is_some()).then(|| *treasury),
+                keeper_token_account: keeper_token_account.copied(),
+                payer: *payer,
+                token_program: anchor_spl::token::ID,
+                system_program: system_program::ID,
+            },
+            instruction::CloseRewardEpoch {},
+        )
+    }
+
+    pub fn get_verdict(content: &Pubkey) -> Instruction {
+        build(
+            accounts::GetVerdict {
+                content: *content,
+                verdict: pda::verdict(content),
+            },
+            instruction::GetVerdict {},
+        )
+    }
+
+    /// Pausers who are not emergency admins need `with_roles` to pass the
+    /// roles account.
+    pub fn pause_protocol(admin: &Pubkey, with_roles: bool) -> Instruction {
+        build(
+            accounts::PauseProtocol {
+                protocol_state: pda::protocol(),
+                roles: with_roles.then(pda::roles),
+                admin: *admin,
+            },
+            instruction::PauseProtocol {},
+        )
+    }
+
+    pub fn unpause_protocol(admin: &Pubkey, admin_action: Option<u64>) -> Instruction {
+        build(
+            accounts::UnpauseProtocol {
+                protocol_state: pda::protocol(),
+                admin_action: admin_action.map(pda::admin_action),
+                admin: *admin,
+            },
+            instruction::UnpauseProtocol {},
+        )
+    }
+
+    /// The new admin must also sign.
+    pub fn add_emergency_admin(
+        admin: &Pubkey,
+        new_admin: &Pubkey,
+        admin_action: Option<u64>,
+    ) -> Instruction {
+        build(
+            accounts::AddEmergencyAdmin {
+                protocol_state: pda::protocol(),
+                admin_action: admin_action.map(pda::admin_action),
+                admin: *admin,
+                new_admin: *new_admin,
+            },
+            instruction::AddEmergencyAdmin {},
+        )
+    }
+
+    pub fn remove_emergency_admin(
+        admin: &Pubkey,
+        admin_to_remove: &Pubkey,
+        admin_action: Option<u64>,
+    ) -> Instruction {
+        build(
+            accounts::RemoveEmergencyAdmin {
+                protocol_state: pda::protocol(),
+                admin_action: admin_action.map(pda::admin_action),
+                admin: *admin,
+                admin_to_remove: *admin_to_remove,
+            },
+            instruction::RemoveEmergencyAdmin {},
+        )
+    }
+
+    /// `action_id` is `ProtocolState::admin_action_count` at proposal time.
+    pub fn propose_admin_action(admin: &Pubkey, action_id: u64, kind: AdminActionKind) -> Instruction {
+        build(
+            accounts::ProposeAdminAction {
+                protocol_state: pda::protocol(),
+                admin_action: pda::admin_action(action_id),
+                admin: *admin,
+                system_program: system_program::ID,
+            },
+            instruction::ProposeAdminAction { kind },
+        )
+    }
+
+    pub fn approve_admin_action(admin: &Pubkey, action_id: u64) -> Instruction {
+        build(
+            accounts::ApproveAdminAction {
+                protocol_state: pda::protocol(),
+                admin_action: pda::admin_action(action_id),
+                admin: *admin,
+            },
+            instruction::ApproveAdminAction {},
+        )
+    }
+
+    pub fn set_admin_threshold(admin: &Pubkey, threshold: u8, admin_action: Option<u64>) -> Instruction {
+        build(
+            accounts::SetAdminThreshold {
+                protocol_state: pda::protocol(),
+                admin_action: admin_action.map(pda::admin_action),
+                admin: *admin,
+            },
+            instruction::SetAdminThreshold { threshold },
+        )
+    }
+
+    /// `with_roles` as for `pause_protocol`.
+    pub fn set_pause_flags(
+        admin: &Pubkey,
+        pause_flags: u8,
+        paused_content_types: u8,
+        admin_action: Option<u64>,
+        with_roles: bool,
+    ) -> Instruction {
+        build(
+            accounts::SetPauseFlags {
+                protocol_state: pda::protocol(),
+                roles: with_roles.then(pda::roles),
+                admin_action: admin_action.map(pda::admin_action),
+                admin: *admin,
+            },
+            instruction::SetPauseFlags { pause_flags, paused_content_types },
+        )
+    }
+
+    /// `app` is the `AppRegistration` address; `with_roles` as for `pause_protocol`.
+    pub fn set_app_paused(
+        admin: &Pubkey,
+        app: &Pubkey,
+        paused: bool,
+        admin_action: Option<u64>,
+        with_roles: bool,
+    ) -> Instruction {
+        build(
+            accounts::SetAppPaused {
+                protocol_state: pda::protocol(),
+                roles: with_roles.then(pda::roles),
+                app_registration: *app,
+                admin_action: admin_action.map(pda::admin_action),
+                admin: *admin,
+            },
+            instruction::SetAppPaused { paused },
+        )
+    }
+
+    pub fn reset_circuit_breaker(admin: &Pubkey, admin_action: Option<u64>) -> Instruction {
+        build(
+            accounts::ResetCircuitBreaker {
+                protocol_state: pda::protocol(),
+                admin_action: admin_action.map(pda::admin_action),
+                admin: *admin,
+            },
+            instruction::ResetCircuitBreaker {},
+        )
+    }
+
+    pub fn initialize_roles(admin: &Pubkey) -> Instruction {
+        build(
+            accounts::InitializeRoles {
+                protocol_state: pda::protocol(),
+                roles: pda::roles(),
+                admin: *admin,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeRoles {},
+        )
+    }
+
+    fn update_role(admin: &Pubkey, admin_action: Option<u64>) -> accounts::UpdateRole {
+        accounts::UpdateRole {
+            protocol_state: pda::protocol(),
+            roles: pda::roles(),
+            admin_action: admin_action.map(pda::admin_action),
+            admin: *admin,
+        }
+    }
+
+    pub fn grant_role(admin: &Pubkey, account: Pubkey, role: Role, admin_action: Option<u64>) -> Instruction {
+        build(
+            update_role(admin, admin_action),
+            instruction::GrantRole { account, role },
+        )
+    }
+
+    pub fn revoke_role(admin: &Pubkey, account: Pubkey, role: Role, admin_action: Option<u64>) -> Instruction {
+        build(
+            update_role(admin, admin_action),
+            instruction::RevokeRole { account, role },
+        )
+    }
+}
+
+macro_rules! program_events {
+    ($($name:ident),* $(,)?) => {
+        /// Every event the program emits.
+        pub enum Event {
+            $($name(lunar_scry::$name),)*
+        }
+
+        impl Event {
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Event::$name(_) => stringify!($name),)*
+                }
+            }
+
+            /// Decodes one `Program data:` payload. Returns `Ok(None)` for data
+            /// that is not a LunarScry event.
+            pub fn decode(data: &[u8]) -> Result<Option<Event>> {
+                if data.len() < 8 {
+                    return Ok(None);
+                }
+                let (discriminator, mut payload) = data.split_at(8);
+                if discriminator == lunar_scry::ContentSubmitted::discriminator() {
+                    return decode_content_submitted(payload)
+                        .map(|event| Some(Event::ContentSubmitted(event)));
+                }
+                $(
+                    if discriminator == lunar_scry::$name::discriminator() {
+                        return lunar_scry::$name::deserialize(&mut payload)
+                            .map(|event| Some(Event::$name(event)))
+                            .map_err(|err| ClientError::Event(err.to_string()));
+                    }
+                )*
+                Ok(None)
+            }
+        }
+    };
+}
+
+program_events!(
+    ProtocolInitialized,
+    ContentSubmitted,
+    ContentBatchSubmitted,
+    ContentCompressed,
+    ContentDecompressed,
+    ContentTreeCreated,
+    VoteCast,
+    DecisionFinalized,
+    RewardsClaimed,
+    RewardDistributed,
+    RewardsDistributed,
+    EpochEmissionFunded,
+    EmissionScheduleUpdated,
+    MerkleRootPosted,
+    MerkleRewardClaimed,
+    RewardVestingUpdated,
+    RewardVested,
+    VestedRewardsReleased,
+    VestingForfeited,
+    ConfigUpdateProposed,
+    ConfigUpdated,
+    ConfigUpdateCancelled,
+    ProposalCreated,
+    GovernanceVoteCast,
+    ProposalExecuted,
+    ProposalStakeWithdrawn,
+    GovernanceStakeWithdrawn,
+    ProtocolPaused,
+    ProtocolUnpaused,
+    PauseFlagsUpdated,
+    AppPauseUpdated,
+    CircuitBreakerTripped,
+    CircuitBreakerReset,
+    CircuitBreakerConfigUpdated,
+    EmergencyAdminAdded,
+    EmergencyAdminRemoved,
+    AdminActionProposed,
+    AdminActionApproved,
+    AdminActionExecuted,
+    AdminThresholdUpdated,
+    AdminTransferProposed,
+    AdminTransferred,
+    AdminRenounced,
+    RoleGranted,
+    RoleRevoked,
+    OracleAdded,
+    OracleRemoved,
+    TreasuryConfigUpdated,
+    TreasuryMovement,
+    AppCallbackUpdated,
+    CallbackDelivered,
+    CallbackFailed,
+    VoteClosed,
+    ContentClosed,
+    AccountMigrated,
+);
+
+/// `ContentSubmitted` as logged before `voting_period` and `app` were appended.
+#[derive(AnchorDeserialize)]
+struct LegacyContentSubmitted {
+    content_id: Pubkey,
+    submitter: Pubkey,
+    content_hash: [u8; 32],
+    content_type: ContentType,
+    ai_score: u8,
+    timestamp: i64,
+}
+
+/// Decodes either layout of `ContentSubmitted`. Events from before the fields
+/// were appended come back with a zero `voting_period`, which no real content
+/// has, and no app.
+fn decode_content_submitted(mut payload: &[u8]) -> Result<lunar_scry::ContentSubmitted> {
+    let decode_error = |err: std::io::Error| ClientError::Event(err.to_string());
+    let legacy = LegacyContentSubmitted::deserialize(&mut payload).map_err(decode_error)?;
+    let (voting_period, app) = if payload.is_empty() {
+        (0, None)
+    } else {
+        (
+            i64::deserialize(&mut payload).map_err(decode_error)?,
+            Option::<Pubkey>::deserialize(&mut payload).map_err(decode_error)?,
+        )
+    };
+    Ok(lunar_scry::ContentSubmitted {
+        content_id: legacy.content_id,
+        submitter: legacy.submitter,
+        content_hash: legacy.content_hash,
+        content_type: legacy.content_type,
+        ai_score: legacy.ai_score,
+        timestamp: legacy.timestamp,
+        voting_period,
+        app,
+    })
+}
+
+/// Decodes every LunarScry event in a transaction's log messages, in order.
+/// Data that does not decode is logged as a warning and skipped instead of
+/// failing the whole transaction.
+pub fn parse_logs<S: AsRef<str>>(logs: &[S]) -> Vec<Event> {
+    program_data(logs)
+        .into_iter()
+        .filter_map(|encoded| match decode_program_data(encoded) {
+            Ok(event) => event,
+            Err(err) => {
+                log::warn!("skipping undecodable event data {}: {}", encoded, err);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Decodes one base64 `Program data:` payload; see [`Event::decode`].
+pub fn decode_program_data(encoded: &str) -> Result<Option<Event>> {
+    let data = STANDARD
+        .decode(encoded)
+        .map_err(|err| ClientError::Event(err.to_string()))?;
+    Event::decode(&data)
+}
+
+/// Returns the `Program data:` payloads LunarScry itself logged, in order.
+///
+/// Any program can log bytes that look like our events, so the invoke stack
+/// is followed and only data emitted while LunarScry is the executing program
+/// is kept. Data logged under a top-level instruction that failed was rolled
+/// back with it and is dropped too.
+pub fn program_data<S: AsRef<str>>(logs: &[S]) -> Vec<&str> {
+    let program_id = PROGRAM_ID.to_string();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut pending = Vec::new();
+    let mut accepted = Vec::new();
+
+    for line in logs.iter().map(|line| line.as_ref().trim()) {
+        if let Some(encoded) = line.strip_prefix(PROGRAM_DATA_PREFIX) {
+            if stack.last() == Some(&program_id.as_str()) {
+                pending.push(encoded.trim());
+            }
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        if words.next() != Some("Program") {
+            continue;
+        }
+        let (Some(program), Some(action)) = (words.next(), words.next()) else {
+            continue;
+        };
+        match action {
+            "invoke" => {
+                // A new top-level instruction; anything left open was truncated
+                if words.next() == Some("[1]") {
+                    stack.clear();
+                    pending.clear();
+                }
+                stack.push(program);
+            }
+            "success" => {
+                stack.pop();
+                if stack.is_empty() {
+                    accepted.append(&mut pending);
+                }
+            }
+            _ if action.starts_with("failed") => {
+                stack.pop();
+                if stack.is_empty() {
+                    pending.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    accepted
+}
+
+pub struct LunarScryClient {
+    rpc: RpcClient,
+    payer: Keypair,
+}
+
+impl LunarScryClient {
+    pub fn new(rpc_url: &str, payer: Keypair) -> Self {
+        Self {
+            rpc: RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed()),
+            payer,
+        }
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    pub fn payer(&self) -> &Keypair {
+        &self.payer
+    }
+
+    /// Sends `instructions` in one transaction paid for and signed by the
+    /// payer, plus any `extra_signers` (e.g. a new emergency admin).
+    pub fn send(&self, instructions: &[Instruction], extra_signers: &[&Keypair]) -> Result<Signature> {
+        let mut signers: Vec<&Keypair> = vec![&self.payer];
+        signers.extend_from_slice(extra_signers);
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &signers,
+            self.rpc.get_latest_blockhash()?,
+        );
+        Ok(self.rpc.send_and_confirm_transaction(&transaction)?)
+    }
+
+    pub fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T> {
+        let data = self.rpc.get_account_data(address)?;
+        T::try_deserialize(&mut data.as_slice()).map_err(|err| ClientError::Deserialize(*address, err))
+    }
+
+    /// Like [`fetch`](Self::fetch), but `None` when the account does not exist.
+    pub fn fetch_optional<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<Option<T>> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(address, self.rpc.commitment())?
+            .value;
+        account
+            .map(|account| {
+                T::try_deserialize(&mut account.data.as_slice())
+                    .map_err(|err| ClientError::Deserialize(*address, err))
+            })
+            .transpose()
+    }
+
+    pub fn protocol_state(&self) -> Result<ProtocolState> {
+        self.fetch(&pda::protocol())
+    }
+
+    /// The GUARD mint, read from the reward vault.
+    pub fn guard_mint(&self) -> Result<Pubkey> {
+        Ok(self.fetch::<TokenAccount>(&pda::reward_vault())?.mint)
+    }
+
+    pub fn content(&self, address: &Pubkey) -> Result<Content> {
+        self.fetch(address)
+    }
+
+    pub fn vote(&self, content: &Pubkey, voter: &Pubkey) -> Result<Vote> {
+        self.fetch(&pda::vote(content, voter))
+    }
+
+    /// Every live content account with `status`, filtered server-side.
+    /// Accounts that fail to decode are logged as a warning and skipped.
+    pub fn contents_with_status(&self, status: ContentStatus) -> Result<Vec<(Pubkey, Content)>> {
+        let accounts = self.rpc.get_program_accounts_with_config(
+            &PROGRAM_ID,
+            RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &Content::discriminator())),
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                        CONTENT_STATUS_OFFSET,
+                        &[status as u8],
+                    )),
+                    // Accounts awaiting `migrate_content` would not deserialize
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                        Content::VERSION_OFFSET,
+                        &[constants::PROGRAM_VERSION],
+                    )),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    commitment: Some(self.rpc.commitment()),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            },
+        )?;
+        // One malformed account must not hide every other pending item
+        Ok(accounts
+            .into_iter()
+            .filter_map(|(address, account)| {
+                match Content::try_deserialize(&mut account.data.as_slice()) {
+                    Ok(content) => Some((address, content)),
+                    Err(err) => {
+                        log::warn!("skipping undecodable content account {}: {}", address, err);
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+
+    /// The cluster clock, which is what the program compares deadlines against.
+    pub fn clock(&self) -> Result<Clock> {
+        let account = self.rpc.get_account(&solana_sdk::sysvar::clock::ID)?;
+        solana_sdk::account::from_account(&account)
+            .ok_or(ClientError::Sysvar(solana_sdk::sysvar::clock::ID))
+    }
+
+    /// Events emitted by a confirmed transaction.
+    pub fn transaction_events(&self, signature: &Signature) -> Result<Vec<Event>> {
+        let transaction = self.rpc.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(self.rpc.commitment()),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        let logs = transaction
+            .transaction
+            .meta
+            .and_then(|meta| match meta.log_messages {
+                OptionSerializer::Some(logs) => Some(logs),
+                _ => None,
+            })
+            .ok_or(ClientError::MissingLogs(*signature))?;
+        Ok(parse_logs(&logs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lunar_scry::{ProtocolPaused, VoteCast, VoteType};
+
+    fn find(seeds: &[&[u8]]) -> Pubkey {
+        Pubkey::find_program_address(seeds, &PROGRAM_ID).0
+    }
+
+    #[test]
+    fn pdas_use_the_program_seeds() {
+        let (key, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let hash = [7u8; 32];
+        let cases = [
+            (pda::protocol(), find(&[b"protocol"])),
+            (pda::content(&hash), find(&[b"content", &hash])),
+            (pda::vote(&key, &other), find(&[b"vote", key.as_ref(), other.as_ref()])),
+            (pda::vote_escrow(&key), find(&[b"vote_escrow", key.as_ref()])),
+            (pda::verdict(&key), find(&[b"verdict", key.as_ref()])),
+            (pda::reward_epoch(3), find(&[b"reward_epoch", &3u64.to_le_bytes()])),
+            (pda::reward_vault(), find(&[b"reward_vault"])),
+            (pda::treasury_vault(), find(&[b"treasury_vault"])),
+            (pda::emission_schedule(), find(&[b"emission_schedule"])),
+            (pda::vesting(&key), find(&[b"vesting", key.as_ref()])),
+            (pda::roles(), find(&[b"roles"])),
+            (pda::admin_action(3), find(&[b"admin_action", &3u64.to_le_bytes()])),
+            (pda::app(&key), find(&[b"app", key.as_ref()])),
+            (pda::content_tree(3), find(&[b"content_tree", &3u64.to_le_bytes()])),
+        ];
+        for (index, (derived, expected)) in cases.iter().enumerate() {
+            assert_eq!(derived, expected, "case {}", index);
+        }
+
+        // Argument order matters and ids are little-endian u64s
+        assert_ne!(pda::vote(&key, &other), pda::vote(&other, &key));
+        assert_ne!(pda::reward_epoch(3), find(&[b"reward_epoch", &3u32.to_le_bytes()]));
+    }
+
+    #[test]
+    fn attestation_messages_commit_to_every_field() {
+        let content_data = ContentData {
+            content_hash: [5; 32],
+            content_type: ContentType::Image,
+            ai_score: 72,
+        };
+        let message = attestation_message(&content_data, 1_700_000_000);
+
+        let mut expected = b"lunarscry-attestation-v1".to_vec();
+        expected.extend_from_slice(&[5; 32]);
+        expected.extend_from_slice(&[ContentType::Image as u8, 72]);
+        expected.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+        assert_eq!(message, expected);
+
+        let with = |content_data: ContentData| attestation_message(&content_data, 1_700_000_000);
+        let changed = [
+            with(ContentData { content_hash: [6; 32], ..content_data }),
+            with(ContentData { content_type: ContentType::Text, ..content_data }),
+            with(ContentData { ai_score: 73, ..content_data }),
+            attestation_message(&content_data, 1_700_000_001),
+        ];
+        for other in changed {
+            assert_ne!(other, message);
+        }
+    }
+
+    fn vote_cast() -> VoteCast {
+        VoteCast {
+            content_id: Pubkey::new_unique(),
+            voter: Pubkey::new_unique(),
+            vote_type: VoteType::Reject,
+            stake_amount: 1_000_000,
+            timestamp: 1_700_000_000,
+            vote_number: 4,
+            reward_epoch: 2,
+            reward_weight: 1_200_000,
+            early_bonus_percentage: 20,
+        }
+    }
+
+    fn data_line(event: &impl anchor_lang::Event) -> String {
+        format!("{}{}", PROGRAM_DATA_PREFIX, STANDARD.encode(event.data()))
+    }
+
+    fn instruction(program: &Pubkey, depth: usize, body: Vec<String>, outcome: &str) -> Vec<String> {
+        let mut lines = vec![format!("Program {} invoke [{}]", program, depth)];
+        lines.extend(body);
+        lines.push(format!("Program {} {}", program, outcome));
+        lines
+    }
+
+    #[test]
+    fn events_round_trip_through_decode() {
+        let vote = vote_cast();
+        let data = anchor_lang::Event::data(&vote);
+
+        let Some(Event::VoteCast(decoded)) = Event::decode(&data).unwrap() else {
+            panic!("expected VoteCast");
+        };
+        assert_eq!(decoded.voter, vote.voter);
+        assert_eq!(decoded.reward_weight, vote.reward_weight);
+        assert!(decoded.vote_type == VoteType::Reject);
+
+        assert!(Event::decode(&data[..7]).unwrap().is_none());
+        assert!(Event::decode(&[0u8; 16]).unwrap().is_none());
+        assert!(Event::decode(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn content_submitted_decodes_with_or_without_the_appended_fields() {
+        let submitted = lunar_scry::ContentSubmitted {
+            content_id: Pubkey::new_unique(),
+            submitter: Pubkey::new_unique(),
+            content_hash: [9; 32],
+            content_type: ContentType::Link,
+            ai_score: 80,
+            timestamp: 1_700_000_000,
+            voting_period: 86_400,
+            app: None,
+        };
+        let data = anchor_lang::Event::data(&submitted);
+
+        let Some(Event::ContentSubmitted(current)) = Event::decode(&data).unwrap() else {
+            panic!("expected ContentSubmitted");
+        };
+        assert_eq!(current.voting_period, 86_400);
+
+        // The legacy layout stops after `timestamp`; a `None` app is one byte
+        let Some(Event::ContentSubmitted(legacy)) = Event::decode(&data[..data.len() - 9]).unwrap()
+        else {
+            panic!("expected ContentSubmitted");
+        };
+        assert_eq!(legacy.content_id, submitted.content_id);
+        assert_eq!(legacy.timestamp, submitted.timestamp);
+        assert_eq!(legacy.voting_period, 0);
+        assert!(legacy.app.is_none());
+
+        assert!(Event::decode(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn parse_logs_only_keeps_our_own_events() {
+        let vote = vote_cast();
+        let paused = ProtocolPaused {
+            paused_by: Pubkey::new_unique(),
+            timestamp: 1_700_000_100,
+        };
+        let other = Pubkey::new_unique();
+
+        // LunarScry CPIs into a program that forges one of our events
+        let forged = instruction(&other, 2, vec![data_line(&paused)], "success");
+        let mut body = vec!["Program log: Instruction: CastVote".to_string(), data_line(&vote)];
+        body.extend(forged);
+        let mut logs = instruction(&PROGRAM_ID, 1, body, "success");
+        // A top-level instruction of another program logging our events
+        logs.extend(instruction(&other, 1, vec![data_line(&paused)], "success"));
+        logs.extend(instruction(&PROGRAM_ID, 1, vec![data_line(&paused)], "success"));
+
+        let events = parse_logs(&logs);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], Event::VoteCast(e) if e.voter == vote.voter));
+        assert!(matches!(&events[1], Event::ProtocolPaused(e) if e.paused_by == paused.paused_by));
+    }
+
+    #[test]
+    fn parse_logs_skips_undecodable_and_rolled_back_data() {
+        let vote = vote_cast();
+        let truncated = {
+            let data = anchor_lang::Event::data(&vote);
+            format!("{}{}", PROGRAM_DATA_PREFIX, STANDARD.encode(&data[..data.len() - 1]))
+        };
+
+        let mut logs = instruction(
+            &PROGRAM_ID,
+            1,
+            vec![
+                format!("{}not base64!", PROGRAM_DATA_PREFIX),
+                truncated,
+                data_line(&vote),
+            ],
+            "success",
+        );
+        logs.extend(instruction(
+            &PROGRAM_ID,
+            1,
+            vec![data_line(&vote)],
+            "failed: custom program error: 0x1",
+        ));
+
+        let events = parse_logs(&logs);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name(), "VoteCast");
+    }
+}
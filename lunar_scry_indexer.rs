@@ -0,0 +1,1793 @@
+//! `lunar-scry-indexer`: mirrors LunarScry program events into SQL tables.
+//!
+//! The [`Indexer`] walks the program's transaction history over RPC (a local
+//! validator or a remote node replaying from genesis both work), decodes every
+//! event with `lunar_scry_client::Event` and writes it to a [`Database`]. Each
+//! event lands in the raw `events` table and, for the events dApps usually
+//! query, in a normalized table as well (`content_submissions`, `votes`,
+//! `decisions`, `reward_payouts`, ...).
+//!
+//! Transactions are indexed at `confirmed` commitment and tracked until they
+//! are finalized. If one of them turns out to belong to an abandoned fork, every
+//! row from its slot onwards is rolled back and re-indexed from the canonical
+//! chain on the next poll.
+//!
+//! SQLite is built in; Postgres is available behind the `postgres` feature.
+//! The schema and queries are written in the subset of SQL both understand.
+//! Event data that does not decode is skipped and reported through `log`.
+
+use lunar_scry_client::{decode_program_data, names, program_data, ClientError, Event, PROGRAM_ID};
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+use std::str::FromStr;
+
+pub use db::{Database, Row, SqliteDatabase, Statement, Value};
+#[cfg(feature = "postgres")]
+pub use db::PostgresDatabase;
+
+/// `getSignaturesForAddress` page size (the RPC maximum).
+const SIGNATURE_PAGE_SIZE: usize = 1000;
+
+/// `getSignatureStatuses` accepts at most this many signatures per call.
+const STATUS_BATCH_SIZE: usize = 256;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IndexerError {
+    #[error(transparent)]
+    Client(#[from] ClientError),
+    #[error("rpc error: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "postgres")]
+    #[error("postgres error: {0}")]
+    Postgres(#[from] postgres::Error),
+    #[error("invalid signature {0}")]
+    InvalidSignature(String),
+    #[error("unexpected value in column {0}")]
+    Column(&'static str),
+}
+
+pub type Result<T> = std::result::Result<T, IndexerError>;
+
+/// Storage backends and the schema they share.
+pub mod db {
+    use super::{IndexerError, Result};
+
+    /// Every table holds `signature`, `event_index` and `slot` so a rollback is
+    /// a plain `DELETE ... WHERE slot >= ?`. Pubkeys are base58 and hashes hex.
+    /// Token amounts are BIGINT; GUARD's supply fits comfortably in an i64.
+    ///
+    /// This is the first released layout and never changes; later columns are
+    /// added by [`MIGRATIONS`] so existing databases pick them up too.
+    pub const SCHEMA: &str = "
+        CREATE TABLE IF NOT EXISTS schema_version (
+            version BIGINT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS indexed_transactions (
+            signature TEXT PRIMARY KEY,
+            slot BIGINT NOT NULL,
+            block_time BIGINT,
+            position BIGINT NOT NULL,
+            finalized BIGINT NOT NULL DEFAULT 0
+        );
+        CREATE INDEX IF NOT EXISTS indexed_transactions_slot ON indexed_transactions (slot);
+
+        CREATE TABLE IF NOT EXISTS events (
+            name TEXT NOT NULL,
+            data TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+        CREATE INDEX IF NOT EXISTS events_name ON events (name);
+
+        CREATE TABLE IF NOT EXISTS content_submissions (
+            content_id TEXT NOT NULL,
+            submitter TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            content_type TEXT NOT NULL,
+            ai_score BIGINT NOT NULL,
+            submitted_at BIGINT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+        CREATE INDEX IF NOT EXISTS content_submissions_content ON content_submissions (content_id);
+        CREATE INDEX IF NOT EXISTS content_submissions_hash ON content_submissions (content_hash);
+
+        CREATE TABLE IF NOT EXISTS compressed_submissions (
+            tree TEXT NOT NULL,
+            leaf_index BIGINT NOT NULL,
+            submitter TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            content_type TEXT NOT NULL,
+            ai_score BIGINT NOT NULL,
+            submitted_at BIGINT NOT NULL,
+            root TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+
+        CREATE TABLE IF NOT EXISTS content_decompressions (
+            content_id TEXT NOT NULL,
+            tree TEXT NOT NULL,
+            leaf_index BIGINT NOT NULL,
+            escalated_by TEXT NOT NULL,
+            decompressed_at BIGINT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+
+        CREATE TABLE IF NOT EXISTS votes (
+            content_id TEXT NOT NULL,
+            voter TEXT NOT NULL,
+            vote_type TEXT NOT NULL,
+            stake_amount BIGINT NOT NULL,
+            reward_epoch BIGINT NOT NULL,
+            reward_weight BIGINT NOT NULL,
+            early_bonus_percentage BIGINT NOT NULL,
+            voted_at BIGINT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+        CREATE INDEX IF NOT EXISTS votes_content ON votes (content_id);
+        CREATE INDEX IF NOT EXISTS votes_voter ON votes (voter);
+
+        CREATE TABLE IF NOT EXISTS decisions (
+            content_id TEXT NOT NULL,
+            status TEXT NOT NULL,
+            approve_votes BIGINT NOT NULL,
+            reject_votes BIGINT NOT NULL,
+            total_stake BIGINT NOT NULL,
+            finalized_at BIGINT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+        CREATE INDEX IF NOT EXISTS decisions_content ON decisions (content_id);
+
+        CREATE TABLE IF NOT EXISTS content_closures (
+            content_id TEXT NOT NULL,
+            content_hash TEXT NOT NULL,
+            status TEXT NOT NULL,
+            rent_recipient TEXT NOT NULL,
+            closed_at BIGINT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+
+        CREATE TABLE IF NOT EXISTS callbacks (
+            content_id TEXT NOT NULL,
+            app TEXT NOT NULL,
+            outcome TEXT NOT NULL,
+            callback_program TEXT,
+            recorded_at BIGINT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+
+        CREATE TABLE IF NOT EXISTS reward_payouts (
+            recipient TEXT NOT NULL,
+            source TEXT NOT NULL,
+            content_id TEXT,
+            epoch BIGINT,
+            amount BIGINT NOT NULL,
+            paid_at BIGINT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+        CREATE INDEX IF NOT EXISTS reward_payouts_recipient ON reward_payouts (recipient);
+
+        CREATE TABLE IF NOT EXISTS reward_epochs (
+            epoch BIGINT NOT NULL,
+            total_amount BIGINT NOT NULL,
+            total_weight BIGINT NOT NULL,
+            reward_per_weight TEXT NOT NULL,
+            closed_at BIGINT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+
+        CREATE TABLE IF NOT EXISTS treasury_movements (
+            kind TEXT NOT NULL,
+            amount BIGINT NOT NULL,
+            counterparty TEXT NOT NULL,
+            authorized_by TEXT NOT NULL,
+            balance BIGINT NOT NULL,
+            moved_at BIGINT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+
+        CREATE TABLE IF NOT EXISTS admin_events (
+            event TEXT NOT NULL,
+            actor TEXT,
+            subject TEXT,
+            detail TEXT,
+            recorded_at BIGINT NOT NULL,
+            signature TEXT NOT NULL,
+            event_index BIGINT NOT NULL,
+            slot BIGINT NOT NULL,
+            PRIMARY KEY (signature, event_index)
+        );
+    ";
+
+    /// Changes to [`SCHEMA`], oldest first. Applying `MIGRATIONS[n]` takes a
+    /// database from version `n + 1` to `n + 2`; one without a recorded
+    /// version is at version 1.
+    pub const MIGRATIONS: &[&[&str]] = &[
+        // ContentSubmitted gained `voting_period` and `app`. Rows indexed from
+        // the shorter event leave both NULL.
+        &[
+            "ALTER TABLE content_submissions ADD COLUMN voting_period BIGINT",
+            "ALTER TABLE content_submissions ADD COLUMN app TEXT",
+            "CREATE INDEX IF NOT EXISTS content_submissions_app ON content_submissions (app)",
+        ],
+    ];
+
+    /// The version a database is at once every migration has run.
+    pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64 + 1;
+
+    /// Brings a database that already has [`SCHEMA`] up to [`SCHEMA_VERSION`].
+    /// Each migration runs in its own transaction together with the version
+    /// bump, so an interrupted upgrade resumes where it stopped.
+    fn migrate(db: &mut impl Database) -> Result<()> {
+        let version = match db.query("SELECT version FROM schema_version", &[])?.first() {
+            Some(row) => row[0].as_i64().ok_or(IndexerError::Column("version"))?,
+            None => 1,
+        };
+        let applied = (version - 1).max(0) as usize;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+            let mut statements: Vec<Statement> = migration
+                .iter()
+                .map(|sql| Statement::new(*sql, Vec::new()))
+                .collect();
+            statements.push(Statement::new("DELETE FROM schema_version", Vec::new()));
+            statements.push(Statement::new(
+                "INSERT INTO schema_version (version) VALUES (?)",
+                vec![(index as i64 + 2).into()],
+            ));
+            db.execute(&statements)?;
+        }
+        Ok(())
+    }
+
+    /// Every table a rollback clears.
+    pub const TABLES: [&str; 13] = [
+        "indexed_transactions",
+        "events",
+        "content_submissions",
+        "compressed_submissions",
+        "content_decompressions",
+        "votes",
+        "decisions",
+        "content_closures",
+        "callbacks",
+        "reward_payouts",
+        "reward_epochs",
+        "treasury_movements",
+        "admin_events",
+    ];
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Value {
+        Null,
+        Integer(i64),
+        Real(f64),
+        Text(String),
+    }
+
+    impl Value {
+        pub fn as_i64(&self) -> Option<i64> {
+            match self {
+                Value::Integer(value) => Some(*value),
+                _ => None,
+            }
+        }
+
+        pub fn as_f64(&self) -> Option<f64> {
+            match self {
+                Value::Real(value) => Some(*value),
+                Value::Integer(value) => Some(*value as f64),
+                _ => None,
+            }
+        }
+
+        pub fn as_str(&self) -> Option<&str> {
+            match self {
+                Value::Text(value) => Some(value),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<i64> for Value {
+        fn from(value: i64) -> Self {
+            Value::Integer(value)
+        }
+    }
+
+    /// Saturates at `i64::MAX`; no on-chain counter or amount gets near it.
+    impl From<u64> for Value {
+        fn from(value: u64) -> Self {
+            Value::Integer(i64::try_from(value).unwrap_or(i64::MAX))
+        }
+    }
+
+    impl From<u32> for Value {
+        fn from(value: u32) -> Self {
+            Value::Integer(value.into())
+        }
+    }
+
+    impl From<u8> for Value {
+        fn from(value: u8) -> Self {
+            Value::Integer(value.into())
+        }
+    }
+
+    impl From<&str> for Value {
+        fn from(value: &str) -> Self {
+            Value::Text(value.to_string())
+        }
+    }
+
+    impl From<String> for Value {
+        fn from(value: String) -> Self {
+            Value::Text(value)
+        }
+    }
+
+    impl<T: Into<Value>> From<Option<T>> for Value {
+        fn from(value: Option<T>) -> Self {
+            value.map_or(Value::Null, Into::into)
+        }
+    }
+
+    pub type Row = Vec<Value>;
+
+    /// One parameterized statement. Placeholders are written as `?`.
+    pub struct Statement {
+        pub sql: String,
+        pub params: Vec<Value>,
+    }
+
+    impl Statement {
+        pub fn new(sql: impl Into<String>, params: Vec<Value>) -> Self {
+            Self {
+                sql: sql.into(),
+                params,
+            }
+        }
+    }
+
+    /// A SQL backend. Implementations create [`SCHEMA`] and run [`MIGRATIONS`]
+    /// when opened.
+    pub trait Database {
+        /// Runs `statements` in a single transaction.
+        fn execute(&mut self, statements: &[Statement]) -> Result<()>;
+
+        /// Runs a read-only query and returns its rows, columns in select order.
+        fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>>;
+    }
+
+    pub struct SqliteDatabase {
+        connection: rusqlite::Connection,
+    }
+
+    impl SqliteDatabase {
+        pub fn open(path: &str) -> Result<Self> {
+            Self::with_connection(rusqlite::Connection::open(path)?)
+        }
+
+        /// A throwaway database, handy for tests of anything built on the index.
+        pub fn open_in_memory() -> Result<Self> {
+            Self::with_connection(rusqlite::Connection::open_in_memory()?)
+        }
+
+        fn with_connection(connection: rusqlite::Connection) -> Result<Self> {
+            connection.execute_batch(SCHEMA)?;
+            let mut db = Self { connection };
+            migrate(&mut db)?;
+            Ok(db)
+        }
+    }
+
+    impl rusqlite::ToSql for Value {
+        fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+            use rusqlite::types::{Null, ToSqlOutput};
+            Ok(match self {
+                Value::Null => ToSqlOutput::from(Null),
+                Value::Integer(value) => ToSqlOutput::from(*value),
+                Value::Real(value) => ToSqlOutput::from(*value),
+                Value::Text(value) => ToSqlOutput::from(value.as_str()),
+            })
+        }
+    }
+
+    impl rusqlite::types::FromSql for Value {
+        fn column_result(value: rusqlite::types::ValueRef<'_>) -> rusqlite::types::FromSqlResult<Self> {
+            use rusqlite::types::{FromSqlError, ValueRef};
+            match value {
+                ValueRef::Null => Ok(Value::Null),
+                ValueRef::Integer(value) => Ok(Value::Integer(value)),
+                ValueRef::Real(value) => Ok(Value::Real(value)),
+                ValueRef::Text(value) => Ok(Value::Text(String::from_utf8_lossy(value).into_owned())),
+                ValueRef::Blob(_) => Err(FromSqlError::InvalidType),
+            }
+        }
+    }
+
+    impl Database for SqliteDatabase {
+        fn execute(&mut self, statements: &[Statement]) -> Result<()> {
+            let transaction = self.connection.transaction()?;
+            for statement in statements {
+                transaction.execute(&statement.sql, rusqlite::params_from_iter(&statement.params))?;
+            }
+            transaction.commit()?;
+            Ok(())
+        }
+
+        fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
+            let mut statement = self.connection.prepare(sql)?;
+            let columns = statement.column_count();
+            let rows = statement.query_map(rusqlite::params_from_iter(params), |row| {
+                (0..columns).map(|index| row.get(index)).collect()
+            })?;
+            rows.collect::<rusqlite::Result<_>>().map_err(IndexerError::from)
+        }
+    }
+
+    #[cfg(feature = "postgres")]
+    pub struct PostgresDatabase {
+        client: postgres::Client,
+    }
+
+    #[cfg(feature = "postgres")]
+    impl PostgresDatabase {
+        /// `params` is a libpq-style connection string, e.g.
+        /// `host=localhost user=lunarscry dbname=lunarscry`.
+        pub fn connect(params: &str) -> Result<Self> {
+            let mut client = postgres::Client::connect(params, postgres::NoTls)?;
+            client.batch_execute(SCHEMA)?;
+            let mut db = Self { client };
+            migrate(&mut db)?;
+            Ok(db)
+        }
+
+        /// Rewrites `?` placeholders into Postgres' `$1, $2, ...`.
+        fn numbered(sql: &str) -> String {
+            let mut numbered = String::with_capacity(sql.len());
+            let mut index = 0;
+            for character in sql.chars() {
+                if character == '?' {
+                    index += 1;
+                    numbered.push_str(&format!("${}", index));
+                } else {
+                    numbered.push(character);
+                }
+            }
+            numbered
+        }
+
+        fn params(params: &[Value]) -> Vec<&(dyn postgres::types::ToSql + Sync)> {
+            params
+                .iter()
+                .map(|param| param as &(dyn postgres::types::ToSql + Sync))
+                .collect()
+        }
+    }
+
+    /// Values are untyped on our side, so they defer to whatever the column
+    /// expects and let the server reject a genuine mismatch.
+    #[cfg(feature = "postgres")]
+    impl postgres::types::ToSql for Value {
+        fn to_sql(
+            &self,
+            ty: &postgres::types::Type,
+            out: &mut bytes::BytesMut,
+        ) -> std::result::Result<postgres::types::IsNull, Box<dyn std::error::Error + Sync + Send>> {
+            match self {
+                Value::Null => Ok(postgres::types::IsNull::Yes),
+                Value::Integer(value) => value.to_sql(ty, out),
+                Value::Real(value) => value.to_sql(ty, out),
+                Value::Text(value) => value.as_str().to_sql(ty, out),
+            }
+        }
+
+        fn accepts(_: &postgres::types::Type) -> bool {
+            true
+        }
+
+        postgres::types::to_sql_checked!();
+    }
+
+    #[cfg(feature = "postgres")]
+    impl Database for PostgresDatabase {
+        fn execute(&mut self, statements: &[Statement]) -> Result<()> {
+            let mut transaction = self.client.transaction()?;
+            for statement in statements {
+                transaction.execute(
+                    Self::numbered(&statement.sql).as_str(),
+                    &Self::params(&statement.params),
+                )?;
+            }
+            transaction.commit()?;
+            Ok(())
+        }
+
+        fn query(&mut self, sql: &str, params: &[Value]) -> Result<Vec<Row>> {
+            use postgres::types::Type;
+
+            let rows = self
+                .client
+                .query(Self::numbered(sql).as_str(), &Self::params(params))?;
+            rows.iter()
+                .map(|row| {
+                    row.columns()
+                        .iter()
+                        .enumerate()
+                        .map(|(index, column)| {
+                            Ok(match *column.type_() {
+                                Type::INT2 => row.get::<_, Option<i16>>(index).map(i64::from).into(),
+                                Type::INT4 => row.get::<_, Option<i32>>(index).map(i64::from).into(),
+                                Type::INT8 => row.get::<_, Option<i64>>(index).into(),
+                                Type::FLOAT4 => row
+                                    .get::<_, Option<f32>>(index)
+                                    .map_or(Value::Null, |value| Value::Real(value.into())),
+                                Type::FLOAT8 => row
+                                    .get::<_, Option<f64>>(index)
+                                    .map_or(Value::Null, Value::Real),
+                                Type::TEXT | Type::VARCHAR => row.get::<_, Option<String>>(index).into(),
+                                _ => return Err(IndexerError::Column("unsupported postgres type")),
+                            })
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+    }
+}
+
+/// Where an event sits in the chain; appended to every row it produces.
+struct EventPosition<'a> {
+    signature: &'a str,
+    event_index: usize,
+    slot: u64,
+}
+
+impl EventPosition<'_> {
+    fn insert(&self, table: &str, columns: &[&str], mut values: Vec<Value>) -> Statement {
+        values.extend([
+            Value::from(self.signature),
+            Value::from(self.event_index as u64),
+            Value::from(self.slot),
+        ]);
+        Statement::new(
+            format!(
+                "INSERT INTO {} ({}, signature, event_index, slot) VALUES ({}) ON CONFLICT DO NOTHING",
+                table,
+                columns.join(", "),
+                vec!["?"; values.len()].join(", "),
+            ),
+            values,
+        )
+    }
+
+    fn payout(
+        &self,
+        recipient: &Pubkey,
+        source: &str,
+        content_id: Option<&Pubkey>,
+        epoch: Option<u64>,
+        amount: u64,
+        paid_at: i64,
+    ) -> Statement {
+        self.insert(
+            "reward_payouts",
+            &["recipient", "source", "content_id", "epoch", "amount", "paid_at"],
+            vec![
+                key(recipient),
+                source.into(),
+                content_id.map(Pubkey::to_string).into(),
+                epoch.into(),
+                amount.into(),
+                paid_at.into(),
+            ],
+        )
+    }
+
+    fn admin(
+        &self,
+        event: &Event,
+        actor: Option<&Pubkey>,
+        subject: Option<String>,
+        detail: Option<String>,
+        recorded_at: i64,
+    ) -> Statement {
+        self.insert(
+            "admin_events",
+            &["event", "actor", "subject", "detail", "recorded_at"],
+            vec![
+                event.name().into(),
+                actor.map(Pubkey::to_string).into(),
+                subject.into(),
+                detail.into(),
+                recorded_at.into(),
+            ],
+        )
+    }
+}
+
+fn key(pubkey: &Pubkey) -> Value {
+    pubkey.to_string().into()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Rows for the normalized table an event belongs in, if any. Events without
+/// one (emission, vesting and governance bookkeeping) are only kept raw.
+fn normalized(event: &Event, at: &EventPosition) -> Option<Statement> {
+    let statement = match event {
+        Event::ContentSubmitted(e) => at.insert(
+            "content_submissions",
+            &[
+                "content_id",
+                "submitter",
+                "content_hash",
+                "content_type",
+                "ai_score",
+                "voting_period",
+                "app",
+                "submitted_at",
+            ],
+            vec![
+                key(&e.content_id),
+                key(&e.submitter),
+                to_hex(&e.content_hash).into(),
+                names::content_type(e.content_type).into(),
+                e.ai_score.into(),
+                // Zero marks an event from before the field existed
+                (e.voting_period > 0).then_some(e.voting_period).into(),
+                e.app.map(|app| app.to_string()).into(),
+                e.timestamp.into(),
+            ],
+        ),
+        Event::ContentCompressed(e) => at.insert(
+            "compressed_submissions",
+            &[
+                "tree",
+                "leaf_index",
+                "submitter",
+                "content_hash",
+                "content_type",
+                "ai_score",
+                "submitted_at",
+                "root",
+            ],
+            vec![
+                key(&e.tree),
+                e.leaf.leaf_index.into(),
+                key(&e.leaf.submitter),
+                to_hex(&e.leaf.content_data.content_hash).into(),
+                names::content_type(e.leaf.content_data.content_type).into(),
+                e.leaf.content_data.ai_score.into(),
+                e.leaf.submission_time.into(),
+                to_hex(&e.root).into(),
+            ],
+        ),
+        Event::ContentDecompressed(e) => at.insert(
+            "content_decompressions",
+            &["content_id", "tree", "leaf_index", "escalated_by", "decompressed_at"],
+            vec![
+                key(&e.content_id),
+                key(&e.tree),
+                e.leaf_index.into(),
+                key(&e.escalated_by),
+                e.timestamp.into(),
+            ],
+        ),
+        Event::VoteCast(e) => at.insert(
+            "votes",
+            &[
+                "content_id",
+                "voter",
+                "vote_type",
+                "stake_amount",
+                "reward_epoch",
+                "reward_weight",
+                "early_bonus_percentage",
+                "voted_at",
+            ],
+            vec![
+                key(&e.content_id),
+                key(&e.voter),
+                names::vote_type(e.vote_type).into(),
+                e.stake_amount.into(),
+                e.reward_epoch.into(),
+                e.reward_weight.into(),
+                e.early_bonus_percentage.into(),
+                e.timestamp.into(),
+            ],
+        ),
+        Event::DecisionFinalized(e) => at.insert(
+            "decisions",
+            &["content_id", "status", "approve_votes", "reject_votes", "total_stake", "finalized_at"],
+            vec![
+                key(&e.content_id),
+                names::content_status(e.final_status).into(),
+                e.approve_votes.into(),
+                e.reject_votes.into(),
+                e.total_stake.into(),
+                e.timestamp.into(),
+            ],
+        ),
+        Event::ContentClosed(e) => at.insert(
+            "content_closures",
+            &["content_id", "content_hash", "status", "rent_recipient", "closed_at"],
+            vec![
+                key(&e.content_id),
+                to_hex(&e.content_hash).into(),
+                names::content_status(e.status).into(),
+                key(&e.rent_recipient),
+                e.timestamp.into(),
+            ],
+        ),
+        Event::CallbackDelivered(e) => at.insert(
+            "callbacks",
+            &["content_id", "app", "outcome", "callback_program", "recorded_at"],
+            vec![
+                key(&e.content_id),
+                key(&e.app),
+                "delivered".into(),
+                key(&e.callback_program),
+                e.timestamp.into(),
+            ],
+        ),
+        Event::CallbackFailed(e) => at.insert(
+            "callbacks",
+            &["content_id", "app", "outcome", "callback_program", "recorded_at"],
+            vec![
+                key(&e.content_id),
+                key(&e.app),
+                "failed".into(),
+                Value::Null,
+                e.timestamp.into(),
+            ],
+        ),
+        Event::RewardsClaimed(e) => at.payout(
+            &e.voter,
+            "vote",
+            Some(&e.content_id),
+            None,
+            e.reward_amount,
+            e.timestamp,
+        ),
+        Event::RewardDistributed(e) => at.payout(
+            &e.voter,
+            "epoch",
+            Some(&e.content_id),
+            Some(e.epoch),
+            e.amount,
+            e.timestamp,
+        ),
+        Event::MerkleRewardClaimed(e) => {
+            at.payout(&e.claimant, "merkle", None, Some(e.epoch), e.amount, e.timestamp)
+        }
+        Event::VestedRewardsReleased(e) => {
+            at.payout(&e.owner, "vesting", None, None, e.amount, e.timestamp)
+        }
+        Event::RewardsDistributed(e) => at.insert(
+            "reward_epochs",
+            &["epoch", "total_amount", "total_weight", "reward_per_weight", "closed_at"],
+            vec![
+                e.epoch.into(),
+                e.total_amount.into(),
+                e.total_weight.into(),
+                e.reward_per_weight.to_string().into(),
+                e.timestamp.into(),
+            ],
+        ),
+        Event::TreasuryMovement(e) => at.insert(
+            "treasury_movements",
+            &["kind", "amount", "counterparty", "authorized_by", "balance", "moved_at"],
+            vec![
+                names::treasury_movement(e.kind).into(),
+                e.amount.into(),
+                key(&e.counterparty),
+                key(&e.authorized_by),
+                e.balance.into(),
+                e.timestamp.into(),
+            ],
+        ),
+        Event::ProtocolPaused(e) => at.admin(event, Some(&e.paused_by), None, None, e.timestamp),
+        Event::ProtocolUnpaused(e) => at.admin(event, Some(&e.unpaused_by), None, None, e.timestamp),
+        Event::EmergencyAdminAdded(e) => at.admin(
+            event,
+            Some(&e.added_by),
+            Some(e.new_admin.to_string()),
+            None,
+            e.timestamp,
+        ),
+        Event::EmergencyAdminRemoved(e) => at.admin(
+            event,
+            Some(&e.removed_by),
+            Some(e.removed_admin.to_string()),
+            None,
+            e.timestamp,
+        ),
+        Event::AdminActionProposed(e) => at.admin(
+            event,
+            Some(&e.proposer),
+            Some(e.action_id.to_string()),
+            Some(format!("{} (threshold {})", names::admin_action(&e.kind), e.threshold)),
+            e.timestamp,
+        ),
+        Event::AdminActionApproved(e) => at.admin(
+            event,
+            Some(&e.approver),
+            Some(e.action_id.to_string()),
+            Some(format!("{} approvals", e.approvals)),
+            e.timestamp,
+        ),
+        Event::AdminActionExecuted(e) => at.admin(
+            event,
+            Some(&e.executed_by),
+            Some(e.action_id.to_string()),
+            None,
+            e.timestamp,
+        ),
+        Event::AdminThresholdUpdated(e) => at.admin(
+            event,
+            Some(&e.updated_by),
+            None,
+            Some(format!("{} -> {}", e.previous_threshold, e.new_threshold)),
+            e.timestamp,
+        ),
+        Event::RoleGranted(e) => at.admin(
+            event,
+            Some(&e.granted_by),
+            Some(e.account.to_string()),
+            Some(names::role(e.role).to_string()),
+            e.timestamp,
+        ),
+        Event::RoleRevoked(e) => at.admin(
+            event,
+            Some(&e.revoked_by),
+            Some(e.account.to_string()),
+            Some(names::role(e.role).to_string()),
+            e.timestamp,
+        ),
+        Event::OracleAdded(e) => at.admin(
+            event,
+            Some(&e.added_by),
+            Some(e.oracle.to_string()),
+            None,
+            e.timestamp,
+        ),
+        Event::OracleRemoved(e) => at.admin(
+            event,
+            Some(&e.removed_by),
+            Some(e.oracle.to_string()),
+            None,
+            e.timestamp,
+        ),
+        Event::ConfigUpdated(e) => at.admin(
+            event,
+            Some(&e.executed_by),
+            None,
+            Some(format!(
+                "stake_required={} voting_period={} quorum_percentage={} reward_per_vote={} treasury={}",
+                e.stake_required, e.voting_period, e.quorum_percentage, e.reward_per_vote, e.treasury
+            )),
+            e.timestamp,
+        ),
+        Event::PauseFlagsUpdated(e) => at.admin(
+            event,
+            Some(&e.updated_by),
+            None,
+            Some(format!(
+                "pause_flags {:#04x} -> {:#04x}, paused_content_types {:#04x} -> {:#04x}",
+                e.previous_pause_flags,
+                e.pause_flags,
+                e.previous_paused_content_types,
+                e.paused_content_types
+            )),
+            e.timestamp,
+        ),
+        Event::AppPauseUpdated(e) => at.admin(
+            event,
+            Some(&e.updated_by),
+            Some(e.app.to_string()),
+            Some(if e.paused { "paused" } else { "resumed" }.to_string()),
+            e.timestamp,
+        ),
+        Event::CircuitBreakerTripped(e) => at.admin(
+            event,
+            None,
+            e.voter.map(|voter| voter.to_string()),
+            Some(format!("observed {} over threshold {}", e.observed, e.threshold)),
+            e.timestamp,
+        ),
+        Event::CircuitBreakerReset(e) => at.admin(event, Some(&e.reset_by), None, None, e.timestamp),
+        Event::TreasuryConfigUpdated(e) => at.admin(
+            event,
+            Some(&e.updated_by),
+            None,
+            Some(format!(
+                "submission_fee={} spend_limit={} spend_period={} keeper_fee={}",
+                e.config.submission_fee,
+                e.config.spend_limit,
+                e.config.spend_period,
+                e.config.keeper_fee
+            )),
+            e.timestamp,
+        ),
+        Event::AdminTransferProposed(e) => at.admin(
+            event,
+            Some(&e.current_admin),
+            Some(e.pending_admin.to_string()),
+            None,
+            e.timestamp,
+        ),
+        Event::AdminTransferred(e) => at.admin(
+            event,
+            Some(&e.previous_admin),
+            Some(e.new_admin.to_string()),
+            None,
+            e.timestamp,
+        ),
+        Event::AdminRenounced(e) => at.admin(event, Some(&e.previous_admin), None, None, e.timestamp),
+        _ => return None,
+    };
+    Some(statement)
+}
+
+pub struct Indexer<D: Database> {
+    rpc: RpcClient,
+    db: D,
+}
+
+impl<D: Database> Indexer<D> {
+    pub fn new(rpc_url: &str, db: D) -> Self {
+        Self {
+            rpc: RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed()),
+            db,
+        }
+    }
+
+    pub fn database(&mut self) -> &mut D {
+        &mut self.db
+    }
+
+    /// Reconciles unfinalized transactions with the chain, then indexes every
+    /// program transaction since the last one seen. Returns how many were added.
+    pub fn poll(&mut self) -> Result<usize> {
+        self.reconcile()?;
+
+        let (cursor, mut position) = self.cursor()?;
+        let mut pending = Vec::new();
+        let mut before = None;
+        loop {
+            let page = self.rpc.get_signatures_for_address_with_config(
+                &PROGRAM_ID,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: cursor,
+                    limit: Some(SIGNATURE_PAGE_SIZE),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )?;
+            let last_page = page.len() < SIGNATURE_PAGE_SIZE;
+            if let Some(oldest) = page.last() {
+                before = Some(parse_signature(&oldest.signature)?);
+            }
+            pending.extend(page);
+            if last_page {
+                break;
+            }
+        }
+
+        // Pages come newest first.
+        pending.reverse();
+        for transaction in &pending {
+            position += 1;
+            self.index_transaction(transaction, position)?;
+        }
+        Ok(pending.len())
+    }
+
+    /// Deletes every row at or above `slot`. The next [`poll`](Self::poll)
+    /// re-indexes from whatever the chain now holds there.
+    pub fn rollback(&mut self, slot: u64) -> Result<()> {
+        let statements: Vec<Statement> = db::TABLES
+            .iter()
+            .map(|table| {
+                Statement::new(format!("DELETE FROM {} WHERE slot >= ?", table), vec![slot.into()])
+            })
+            .collect();
+        self.db.execute(&statements)
+    }
+
+    /// The newest indexed signature and its position.
+    fn cursor(&mut self) -> Result<(Option<Signature>, i64)> {
+        let rows = self.db.query(
+            "SELECT signature, position FROM indexed_transactions ORDER BY position DESC LIMIT 1",
+            &[],
+        )?;
+        let Some(row) = rows.first() else {
+            return Ok((None, 0));
+        };
+        let signature = row[0].as_str().ok_or(IndexerError::Column("signature"))?;
+        let position = row[1].as_i64().ok_or(IndexerError::Column("position"))?;
+        Ok((Some(parse_signature(signature)?), position))
+    }
+
+    /// Marks transactions at or below the finalized slot as final, and rolls
+    /// back from the first one the cluster no longer has at the slot we saw it.
+    fn reconcile(&mut self) -> Result<()> {
+        let finalized_slot = self.rpc.get_slot_with_commitment(CommitmentConfig::finalized())?;
+        let rows = self.db.query(
+            "SELECT signature, slot FROM indexed_transactions \
+             WHERE finalized = 0 AND slot <= ? ORDER BY position",
+            &[finalized_slot.into()],
+        )?;
+
+        let mut finalized = Vec::new();
+        let mut orphaned_slot = None;
+        'batches: for batch in rows.chunks(STATUS_BATCH_SIZE) {
+            let mut signatures = Vec::with_capacity(batch.len());
+            for row in batch {
+                let signature = row[0].as_str().ok_or(IndexerError::Column("signature"))?;
+                signatures.push(parse_signature(signature)?);
+            }
+            let statuses = self.rpc.get_signature_statuses_with_history(&signatures)?.value;
+
+            for (row, status) in batch.iter().zip(statuses) {
+                let slot = row[1].as_i64().ok_or(IndexerError::Column("slot"))? as u64;
+                match status {
+                    Some(status) if status.slot == slot => {
+                        if status.satisfies_commitment(CommitmentConfig::finalized()) {
+                            finalized.push(Statement::new(
+                                "UPDATE indexed_transactions SET finalized = 1 WHERE signature = ?",
+                                vec![row[0].clone()],
+                            ));
+                        }
+                    }
+                    _ => {
+                        orphaned_slot = Some(slot);
+                        break 'batches;
+                    }
+                }
+            }
+        }
+
+        self.db.execute(&finalized)?;
+        if let Some(slot) = orphaned_slot {
+            self.rollback(slot)?;
+        }
+        Ok(())
+    }
+
+    fn index_transaction(
+        &mut self,
+        transaction: &RpcConfirmedTransactionStatusWithSignature,
+        position: i64,
+    ) -> Result<()> {
+        let mut statements = vec![Statement::new(
+            "INSERT INTO indexed_transactions (signature, slot, block_time, position) \
+             VALUES (?, ?, ?, ?) ON CONFLICT DO NOTHING",
+            vec![
+                transaction.signature.as_str().into(),
+                transaction.slot.into(),
+                transaction.block_time.into(),
+                position.into(),
+            ],
+        )];
+
+        // Failed transactions are recorded so the cursor moves past them, but
+        // the events they logged were rolled back with them.
+        if transaction.err.is_none() {
+            let logs = self.logs(&parse_signature(&transaction.signature)?)?;
+            // Only data LunarScry itself logged counts; a line that does not
+            // decode is skipped rather than stalling the indexer on it forever.
+            let events = program_data(&logs)
+                .into_iter()
+                .filter_map(|encoded| match decode_program_data(encoded) {
+                    Ok(event) => event.map(|event| (encoded, event)),
+                    Err(err) => {
+                        log::warn!(
+                            "skipping undecodable event data in {}: {}",
+                            transaction.signature, err
+                        );
+                        None
+                    }
+                });
+
+            for (event_index, (encoded, event)) in events.enumerate() {
+                let at = EventPosition {
+                    signature: &transaction.signature,
+                    event_index,
+                    slot: transaction.slot,
+                };
+                statements.push(at.insert(
+                    "events",
+                    &["name", "data"],
+                    vec![event.name().into(), encoded.into()],
+                ));
+                statements.extend(normalized(&event, &at));
+            }
+        }
+
+        self.db.execute(&statements)
+    }
+
+    fn logs(&self, signature: &Signature) -> Result<Vec<String>> {
+        let transaction = self.rpc.get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )?;
+        transaction
+            .transaction
+            .meta
+            .and_then(|meta| match meta.log_messages {
+                OptionSerializer::Some(logs) => Some(logs),
+                _ => None,
+            })
+            .ok_or(IndexerError::Client(ClientError::MissingLogs(*signature)))
+    }
+}
+
+fn parse_signature(signature: &str) -> Result<Signature> {
+    Signature::from_str(signature).map_err(|_| IndexerError::InvalidSignature(signature.to_string()))
+}
+
+/// Read-side queries over the indexed tables, shared by the HTTP API and
+/// anything else that wants answers rather than rows. Every function works
+/// against any [`Database`], including [`SqliteDatabase::open_in_memory`].
+pub mod query {
+    use super::{Database, IndexerError, Result, Row, Value};
+    use serde::Serialize;
+
+    /// Upper bound on any page of results.
+    pub const MAX_PAGE_SIZE: u32 = 100;
+
+    /// One submission with its live tally and, once decided, the verdict.
+    /// Stakes come from `VoteCast`, so they match the on-chain tallies.
+    #[derive(Debug, Serialize)]
+    pub struct ContentRecord {
+        pub content_id: String,
+        pub content_hash: String,
+        pub content_type: String,
+        pub submitter: String,
+        pub ai_score: i64,
+        pub app: Option<String>,
+        pub submitted_at: i64,
+        /// `None` for content indexed before submissions recorded their
+        /// voting period.
+        pub voting_deadline: Option<i64>,
+        pub status: String,
+        pub vote_count: i64,
+        pub approve_stake: i64,
+        pub reject_stake: i64,
+        pub finalized_at: Option<i64>,
+        pub closed_at: Option<i64>,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct VoteRecord {
+        pub content_id: String,
+        pub vote_type: String,
+        pub stake_amount: i64,
+        pub reward_epoch: i64,
+        pub voted_at: i64,
+        /// `pending` until the content is finalized.
+        pub content_status: String,
+        /// Whether the vote matched the verdict; `None` while pending.
+        pub aligned: Option<bool>,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct LeaderboardEntry {
+        pub voter: String,
+        pub decided_votes: i64,
+        pub aligned_votes: i64,
+        pub accuracy: f64,
+        pub total_stake: i64,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct AppStats {
+        pub app: String,
+        pub submissions: i64,
+        pub pending: i64,
+        pub approved: i64,
+        pub rejected: i64,
+        pub average_ai_score: Option<f64>,
+        pub callbacks_delivered: i64,
+        pub callbacks_failed: i64,
+    }
+
+    #[derive(Debug, Default)]
+    pub struct ContentFilter {
+        pub status: Option<String>,
+        pub content_type: Option<String>,
+        pub app: Option<String>,
+        pub limit: Option<u32>,
+        pub offset: Option<u32>,
+    }
+
+    const CONTENT_SELECT: &str = "
+        SELECT s.content_id, s.content_hash, s.content_type, s.submitter, s.ai_score, s.app,
+               s.submitted_at, s.submitted_at + s.voting_period,
+               COALESCE(d.status, 'pending'),
+               COALESCE(t.vote_count, 0), COALESCE(t.approve_stake, 0), COALESCE(t.reject_stake, 0),
+               d.finalized_at, x.closed_at
+        FROM content_submissions s
+        LEFT JOIN decisions d ON d.content_id = s.content_id
+        LEFT JOIN content_closures x ON x.content_id = s.content_id
+        LEFT JOIN (
+            SELECT content_id, COUNT(*) AS vote_count,
+                   CAST(SUM(CASE WHEN vote_type = 'approve' THEN stake_amount ELSE 0 END) AS BIGINT) AS approve_stake,
+                   CAST(SUM(CASE WHEN vote_type = 'reject' THEN stake_amount ELSE 0 END) AS BIGINT) AS reject_stake
+            FROM votes GROUP BY content_id
+        ) t ON t.content_id = s.content_id";
+
+    /// 1 when a vote in `v` agrees with the decision in `d`.
+    const ALIGNED: &str = "CASE WHEN (v.vote_type = 'approve' AND d.status = 'approved') \
+                           OR (v.vote_type = 'reject' AND d.status = 'rejected') THEN 1 ELSE 0 END";
+
+    fn page(limit: Option<u32>, offset: Option<u32>) -> [Value; 2] {
+        [
+            limit.unwrap_or(MAX_PAGE_SIZE).min(MAX_PAGE_SIZE).into(),
+            offset.unwrap_or(0).into(),
+        ]
+    }
+
+    fn text(row: &Row, index: usize, column: &'static str) -> Result<String> {
+        row[index]
+            .as_str()
+            .map(str::to_string)
+            .ok_or(IndexerError::Column(column))
+    }
+
+    fn optional_text(row: &Row, index: usize, column: &'static str) -> Result<Option<String>> {
+        match &row[index] {
+            Value::Null => Ok(None),
+            _ => text(row, index, column).map(Some),
+        }
+    }
+
+    fn int(row: &Row, index: usize, column: &'static str) -> Result<i64> {
+        row[index].as_i64().ok_or(IndexerError::Column(column))
+    }
+
+    fn optional_int(row: &Row, index: usize, column: &'static str) -> Result<Option<i64>> {
+        match &row[index] {
+            Value::Null => Ok(None),
+            _ => int(row, index, column).map(Some),
+        }
+    }
+
+    fn content_record(row: &Row) -> Result<ContentRecord> {
+        Ok(ContentRecord {
+            content_id: text(row, 0, "content_id")?,
+            content_hash: text(row, 1, "content_hash")?,
+            content_type: text(row, 2, "content_type")?,
+            submitter: text(row, 3, "submitter")?,
+            ai_score: int(row, 4, "ai_score")?,
+            app: optional_text(row, 5, "app")?,
+            submitted_at: int(row, 6, "submitted_at")?,
+            voting_deadline: optional_int(row, 7, "voting_deadline")?,
+            status: text(row, 8, "status")?,
+            vote_count: int(row, 9, "vote_count")?,
+            approve_stake: int(row, 10, "approve_stake")?,
+            reject_stake: int(row, 11, "reject_stake")?,
+            finalized_at: optional_int(row, 12, "finalized_at")?,
+            closed_at: optional_int(row, 13, "closed_at")?,
+        })
+    }
+
+    /// Looks content up by its hex `content_hash`.
+    pub fn content_by_hash<D: Database + ?Sized>(
+        db: &mut D,
+        content_hash: &str,
+    ) -> Result<Option<ContentRecord>> {
+        let sql = format!("{} WHERE s.content_hash = ?", CONTENT_SELECT);
+        let rows = db.query(&sql, &[content_hash.to_lowercase().into()])?;
+        rows.first().map(content_record).transpose()
+    }
+
+    /// Newest first, narrowed by any combination of status, type and app.
+    pub fn contents<D: Database + ?Sized>(
+        db: &mut D,
+        filter: &ContentFilter,
+    ) -> Result<Vec<ContentRecord>> {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        if let Some(status) = &filter.status {
+            conditions.push("COALESCE(d.status, 'pending') = ?");
+            params.push(status.as_str().into());
+        }
+        if let Some(content_type) = &filter.content_type {
+            conditions.push("s.content_type = ?");
+            params.push(content_type.as_str().into());
+        }
+        if let Some(app) = &filter.app {
+            conditions.push("s.app = ?");
+            params.push(app.as_str().into());
+        }
+
+        let mut sql = CONTENT_SELECT.to_string();
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY s.submitted_at DESC, s.content_id LIMIT ? OFFSET ?");
+        params.extend(page(filter.limit, filter.offset));
+
+        db.query(&sql, &params)?.iter().map(content_record).collect()
+    }
+
+    /// Undecided content whose voting closes within `window` seconds of `now`,
+    /// soonest first. Content already past its deadline is included, since it
+    /// is still waiting for `finalize_decision`; content with no recorded
+    /// deadline is not.
+    pub fn pending_near_deadline<D: Database + ?Sized>(
+        db: &mut D,
+        now: i64,
+        window: i64,
+        limit: Option<u32>,
+    ) -> Result<Vec<ContentRecord>> {
+        let sql = format!(
+            "{} WHERE d.status IS NULL AND s.submitted_at + s.voting_period <= ? \
+             ORDER BY s.submitted_at + s.voting_period, s.content_id LIMIT ?",
+            CONTENT_SELECT
+        );
+        let [limit, _] = page(limit, None);
+        let rows = db.query(&sql, &[now.saturating_add(window).into(), limit])?;
+        rows.iter().map(content_record).collect()
+    }
+
+    /// A voter's votes, newest first, with how each one turned out.
+    pub fn votes_by_voter<D: Database + ?Sized>(
+        db: &mut D,
+        voter: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Vec<VoteRecord>> {
+        let sql = "
+            SELECT v.content_id, v.vote_type, v.stake_amount, v.reward_epoch, v.voted_at,
+                   COALESCE(d.status, 'pending')
+            FROM votes v
+            LEFT JOIN decisions d ON d.content_id = v.content_id
+            WHERE v.voter = ?
+            ORDER BY v.voted_at DESC, v.content_id LIMIT ? OFFSET ?";
+        let [limit, offset] = page(limit, offset);
+        let rows = db.query(sql, &[voter.into(), limit, offset])?;
+        rows.iter()
+            .map(|row| {
+                let vote_type = text(row, 1, "vote_type")?;
+                let content_status = text(row, 5, "status")?;
+                let aligned = match content_status.as_str() {
+                    "pending" => None,
+                    "approved" => Some(vote_type == "approve"),
+                    _ => Some(vote_type == "reject"),
+                };
+                Ok(VoteRecord {
+                    content_id: text(row, 0, "content_id")?,
+                    vote_type,
+                    stake_amount: int(row, 2, "stake_amount")?,
+                    reward_epoch: int(row, 3, "reward_epoch")?,
+                    voted_at: int(row, 4, "voted_at")?,
+                    content_status,
+                    aligned,
+                })
+            })
+            .collect()
+    }
+
+    /// Voters ranked by the share of their decided votes that matched the
+    /// verdict, then by how many they cast. `min_votes` keeps a single lucky
+    /// vote off the top of the board.
+    pub fn leaderboard<D: Database + ?Sized>(
+        db: &mut D,
+        min_votes: u32,
+        limit: Option<u32>,
+    ) -> Result<Vec<LeaderboardEntry>> {
+        let sql = format!(
+            "SELECT v.voter, COUNT(*), CAST(SUM({aligned}) AS BIGINT), CAST(SUM(v.stake_amount) AS BIGINT)
+             FROM votes v
+             JOIN decisions d ON d.content_id = v.content_id
+             GROUP BY v.voter
+             HAVING COUNT(*) >= ?
+             ORDER BY CAST(SUM({aligned}) AS DOUBLE PRECISION) / COUNT(*) DESC, COUNT(*) DESC, v.voter
+             LIMIT ?",
+            aligned = ALIGNED
+        );
+        let [limit, _] = page(limit, None);
+        let rows = db.query(&sql, &[min_votes.max(1).into(), limit])?;
+        rows.iter()
+            .map(|row| {
+                let decided_votes = int(row, 1, "decided_votes")?;
+                let aligned_votes = int(row, 2, "aligned_votes")?;
+                Ok(LeaderboardEntry {
+                    voter: text(row, 0, "voter")?,
+                    decided_votes,
+                    aligned_votes,
+                    accuracy: aligned_votes as f64 / decided_votes as f64,
+                    total_stake: int(row, 3, "total_stake")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Submission outcomes and callback delivery for every app, or just `app`.
+    pub fn app_stats<D: Database + ?Sized>(db: &mut D, app: Option<&str>) -> Result<Vec<AppStats>> {
+        let mut sql = "
+            SELECT s.app, COUNT(*),
+                   CAST(SUM(CASE WHEN d.status IS NULL THEN 1 ELSE 0 END) AS BIGINT),
+                   CAST(SUM(CASE WHEN d.status = 'approved' THEN 1 ELSE 0 END) AS BIGINT),
+                   CAST(SUM(CASE WHEN d.status = 'rejected' THEN 1 ELSE 0 END) AS BIGINT),
+                   AVG(CAST(s.ai_score AS DOUBLE PRECISION)),
+                   (SELECT COUNT(*) FROM callbacks c WHERE c.app = s.app AND c.outcome = 'delivered'),
+                   (SELECT COUNT(*) FROM callbacks c WHERE c.app = s.app AND c.outcome = 'failed')
+            FROM content_submissions s
+            LEFT JOIN decisions d ON d.content_id = s.content_id
+            WHERE s.app IS NOT NULL"
+            .to_string();
+        let mut params = Vec::new();
+        if let Some(app) = app {
+            sql.push_str(" AND s.app = ?");
+            params.push(app.into());
+        }
+        sql.push_str(" GROUP BY s.app ORDER BY COUNT(*) DESC, s.app");
+
+        db.query(&sql, &params)?
+            .iter()
+            .map(|row| {
+                Ok(AppStats {
+                    app: text(row, 0, "app")?,
+                    submissions: int(row, 1, "submissions")?,
+                    pending: int(row, 2, "pending")?,
+                    approved: int(row, 3, "approved")?,
+                    rejected: int(row, 4, "rejected")?,
+                    average_ai_score: row[5].as_f64(),
+                    callbacks_delivered: int(row, 6, "callbacks_delivered")?,
+                    callbacks_failed: int(row, 7, "callbacks_failed")?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::query::{self, ContentFilter};
+    use super::*;
+    use lunar_scry::{
+        CallbackDelivered, CallbackFailed, ContentStatus, ContentSubmitted, ContentType,
+        DecisionFinalized, VoteCast, VoteType,
+    };
+
+    const DAY: i64 = 86_400;
+    const T0: i64 = 1_700_000_000;
+
+    /// An in-memory index plus a counter so every event gets its own slot.
+    struct Index {
+        db: SqliteDatabase,
+        slot: u64,
+    }
+
+    impl Index {
+        fn new() -> Self {
+            Self {
+                db: SqliteDatabase::open_in_memory().unwrap(),
+                slot: 0,
+            }
+        }
+
+        fn record(&mut self, event: Event) {
+            self.slot += 1;
+            let signature = format!("signature-{}", self.slot);
+            let at = EventPosition {
+                signature: &signature,
+                event_index: 0,
+                slot: self.slot,
+            };
+            let statements: Vec<Statement> = normalized(&event, &at).into_iter().collect();
+            self.db.execute(&statements).unwrap();
+        }
+
+        fn submit(
+            &mut self,
+            content_type: ContentType,
+            ai_score: u8,
+            app: Option<Pubkey>,
+            timestamp: i64,
+            voting_period: i64,
+        ) -> Pubkey {
+            let content_id = Pubkey::new_unique();
+            self.record(Event::ContentSubmitted(ContentSubmitted {
+                content_id,
+                submitter: Pubkey::new_unique(),
+                content_hash: content_id.to_bytes(),
+                content_type,
+                ai_score,
+                timestamp,
+                voting_period,
+                app,
+            }));
+            content_id
+        }
+
+        fn vote(
+            &mut self,
+            content_id: Pubkey,
+            voter: Pubkey,
+            vote_type: VoteType,
+            stake_amount: u64,
+        ) {
+            self.record(Event::VoteCast(VoteCast {
+                content_id,
+                voter,
+                vote_type,
+                stake_amount,
+                timestamp: T0,
+                vote_number: 0,
+                reward_epoch: 0,
+                reward_weight: stake_amount,
+                early_bonus_percentage: 0,
+            }));
+        }
+
+        fn decide(&mut self, content_id: Pubkey, final_status: ContentStatus) {
+            self.record(Event::DecisionFinalized(DecisionFinalized {
+                content_id,
+                final_status,
+                approve_votes: 0,
+                reject_votes: 0,
+                total_stake: 0,
+                timestamp: T0 + DAY,
+            }));
+        }
+    }
+
+    fn ids(records: &[query::ContentRecord]) -> Vec<String> {
+        records.iter().map(|record| record.content_id.clone()).collect()
+    }
+
+    fn strings(keys: &[Pubkey]) -> Vec<String> {
+        keys.iter().map(Pubkey::to_string).collect()
+    }
+
+    #[test]
+    fn contents_filter_by_status_type_and_app() {
+        let mut index = Index::new();
+        let app = Pubkey::new_unique();
+        let approved = index.submit(ContentType::Text, 10, Some(app), T0, DAY);
+        let pending = index.submit(ContentType::Link, 50, Some(app), T0 + 1, DAY);
+        let rejected = index.submit(ContentType::Text, 90, None, T0 + 2, DAY);
+        index.decide(approved, ContentStatus::Approved);
+        index.decide(rejected, ContentStatus::Rejected);
+        index.vote(approved, Pubkey::new_unique(), VoteType::Approve, 300);
+        index.vote(approved, Pubkey::new_unique(), VoteType::Reject, 100);
+
+        let mut contents =
+            |filter: ContentFilter| ids(&query::contents(&mut index.db, &filter).unwrap());
+        assert_eq!(
+            contents(ContentFilter::default()),
+            strings(&[rejected, pending, approved])
+        );
+        assert_eq!(
+            contents(ContentFilter {
+                status: Some("pending".into()),
+                ..Default::default()
+            }),
+            strings(&[pending])
+        );
+        assert_eq!(
+            contents(ContentFilter {
+                content_type: Some("text".into()),
+                ..Default::default()
+            }),
+            strings(&[rejected, approved])
+        );
+        assert_eq!(
+            contents(ContentFilter {
+                app: Some(app.to_string()),
+                status: Some("approved".into()),
+                ..Default::default()
+            }),
+            strings(&[approved])
+        );
+        assert_eq!(
+            contents(ContentFilter {
+                limit: Some(1),
+                offset: Some(1),
+                ..Default::default()
+            }),
+            strings(&[pending])
+        );
+
+        let record = query::content_by_hash(&mut index.db, &to_hex(&approved.to_bytes()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(record.status, "approved");
+        assert_eq!(record.voting_deadline, Some(T0 + DAY));
+        assert_eq!(
+            (record.vote_count, record.approve_stake, record.reject_stake),
+            (2, 300, 100)
+        );
+        assert_eq!(record.app, Some(app.to_string()));
+    }
+
+    #[test]
+    fn pending_near_deadline_lists_undecided_content_soonest_first() {
+        let mut index = Index::new();
+        let later = index.submit(ContentType::Text, 10, None, T0 + 60, DAY);
+        let sooner = index.submit(ContentType::Text, 10, None, T0, DAY);
+        let overdue = index.submit(ContentType::Text, 10, None, T0 - DAY, DAY);
+        let decided = index.submit(ContentType::Text, 10, None, T0, DAY);
+        index.decide(decided, ContentStatus::Approved);
+        // Indexed from a ContentSubmitted without a voting period
+        let legacy = index.submit(ContentType::Text, 10, None, T0 - DAY, 0);
+
+        let now = T0 + DAY - 100;
+        let pending = |index: &mut Index, window| {
+            ids(&query::pending_near_deadline(&mut index.db, now, window, None).unwrap())
+        };
+        assert_eq!(pending(&mut index, 0), strings(&[overdue]));
+        assert_eq!(pending(&mut index, 100), strings(&[overdue, sooner]));
+        assert_eq!(pending(&mut index, 200), strings(&[overdue, sooner, later]));
+
+        let legacy = query::content_by_hash(&mut index.db, &to_hex(&legacy.to_bytes()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(legacy.voting_deadline, None);
+    }
+
+    #[test]
+    fn leaderboard_ranks_by_accuracy_then_decided_votes() {
+        let mut index = Index::new();
+        let (sharp, lucky, mixed) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let first = index.submit(ContentType::Text, 10, None, T0, DAY);
+        let second = index.submit(ContentType::Text, 90, None, T0, DAY);
+        let undecided = index.submit(ContentType::Text, 50, None, T0, DAY);
+        index.decide(first, ContentStatus::Approved);
+        index.decide(second, ContentStatus::Rejected);
+
+        index.vote(first, sharp, VoteType::Approve, 100);
+        index.vote(second, sharp, VoteType::Reject, 100);
+        index.vote(undecided, sharp, VoteType::Reject, 100);
+        index.vote(first, lucky, VoteType::Approve, 500);
+        index.vote(first, mixed, VoteType::Reject, 100);
+        index.vote(second, mixed, VoteType::Reject, 200);
+
+        let board = query::leaderboard(&mut index.db, 1, None).unwrap();
+        let ranked: Vec<_> = board.iter().map(|entry| entry.voter.clone()).collect();
+        assert_eq!(ranked, strings(&[sharp, lucky, mixed]));
+        assert_eq!((board[0].decided_votes, board[0].aligned_votes), (2, 2));
+        assert_eq!(board[0].total_stake, 200);
+        assert_eq!(board[2].accuracy, 0.5);
+
+        let board = query::leaderboard(&mut index.db, 2, None).unwrap();
+        let ranked: Vec<_> = board.iter().map(|entry| entry.voter.clone()).collect();
+        assert_eq!(ranked, strings(&[sharp, mixed]));
+    }
+
+    #[test]
+    fn app_stats_count_outcomes_and_callbacks_per_app() {
+        let mut index = Index::new();
+        let (busy, quiet) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let approved = index.submit(ContentType::Text, 20, Some(busy), T0, DAY);
+        let rejected = index.submit(ContentType::Text, 80, Some(busy), T0, DAY);
+        index.submit(ContentType::Text, 50, Some(busy), T0, DAY);
+        index.submit(ContentType::Text, 30, Some(quiet), T0, DAY);
+        index.submit(ContentType::Text, 99, None, T0, DAY);
+        index.decide(approved, ContentStatus::Approved);
+        index.decide(rejected, ContentStatus::Rejected);
+        index.record(Event::CallbackDelivered(CallbackDelivered {
+            content_id: approved,
+            app: busy,
+            callback_program: Pubkey::new_unique(),
+            timestamp: T0 + DAY,
+        }));
+        index.record(Event::CallbackFailed(CallbackFailed {
+            content_id: rejected,
+            app: busy,
+            timestamp: T0 + DAY,
+        }));
+
+        let stats = query::app_stats(&mut index.db, None).unwrap();
+        let apps: Vec<_> = stats.iter().map(|stats| stats.app.clone()).collect();
+        assert_eq!(apps, strings(&[busy, quiet]));
+
+        let busy_stats = &stats[0];
+        assert_eq!(busy_stats.submissions, 3);
+        assert_eq!(
+            (busy_stats.pending, busy_stats.approved, busy_stats.rejected),
+            (1, 1, 1)
+        );
+        assert_eq!(busy_stats.average_ai_score, Some(50.0));
+        assert_eq!(
+            (busy_stats.callbacks_delivered, busy_stats.callbacks_failed),
+            (1, 1)
+        );
+
+        let quiet_stats = query::app_stats(&mut index.db, Some(&quiet.to_string())).unwrap();
+        assert_eq!(quiet_stats.len(), 1);
+        assert_eq!(quiet_stats[0].submissions, 1);
+        assert!(query::app_stats(&mut index.db, Some(&Pubkey::new_unique().to_string()))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn opening_a_first_release_database_migrates_it() {
+        let path = std::env::temp_dir()
+            .join(format!("lunar_scry_migrate_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let path_str = path.to_str().unwrap();
+
+        // A database written before `schema_version` and the added columns
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        connection.execute_batch(db::SCHEMA).unwrap();
+        connection.execute_batch("DROP TABLE schema_version").unwrap();
+        connection
+            .execute(
+                "INSERT INTO content_submissions (content_id, submitter, content_hash, content_type, \
+                 ai_score, submitted_at, signature, event_index, slot) \
+                 VALUES ('old', 'submitter', 'abcd', 'text', 10, 5, 'signature', 0, 1)",
+                [],
+            )
+            .unwrap();
+        drop(connection);
+
+        let mut db = SqliteDatabase::open(path_str).unwrap();
+        let version = db.query("SELECT version FROM schema_version", &[]).unwrap();
+        assert_eq!(version, vec![vec![Value::Integer(db::SCHEMA_VERSION)]]);
+        let record = query::content_by_hash(&mut db, "abcd").unwrap().unwrap();
+        assert_eq!((record.voting_deadline, record.app), (None, None));
+        drop(db);
+
+        // Reopening finds nothing left to do
+        let mut db = SqliteDatabase::open(path_str).unwrap();
+        let version = db.query("SELECT version FROM schema_version", &[]).unwrap();
+        assert_eq!(version, vec![vec![Value::Integer(db::SCHEMA_VERSION)]]);
+        drop(db);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,73 @@
+//! `lunar-scry-indexerd`: runs the LunarScry event indexer.
+//!
+//! Usage: `lunar-scry-indexerd [--url <rpc>] [--database <file> | --postgres <conn>] [--once]`
+//!
+//! Polls the program's transactions and writes their events to SQLite (or
+//! Postgres when built with the `postgres` feature). With `--once` it catches
+//! up to the current slot and exits, which doubles as a full replay against a
+//! fresh database. `--rollback <slot>` drops everything indexed from that slot
+//! onwards before starting, e.g. after restoring a validator snapshot.
+
+use clap::Parser;
+use lunar_scry_indexer::{Database, Indexer, SqliteDatabase};
+use std::error::Error;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "lunar-scry-indexerd", about = "Index LunarScry program events into SQL")]
+struct Args {
+    #[arg(long, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// SQLite database file
+    #[arg(long, default_value = "lunar_scry_index.db")]
+    database: String,
+    /// Postgres connection string; replaces --database
+    #[cfg(feature = "postgres")]
+    #[arg(long)]
+    postgres: Option<String>,
+    /// Seconds between polls
+    #[arg(long, default_value_t = 2)]
+    interval: u64,
+    /// Catch up once and exit
+    #[arg(long)]
+    once: bool,
+    /// Drop indexed rows from this slot onwards before starting
+    #[arg(long)]
+    rollback: Option<u64>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Shows the indexer's warnings about event data it had to skip
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    let args = Args::parse();
+
+    #[cfg(feature = "postgres")]
+    if let Some(params) = &args.postgres {
+        let db = lunar_scry_indexer::PostgresDatabase::connect(params)?;
+        return run(Indexer::new(&args.url, db), &args);
+    }
+
+    let db = SqliteDatabase::open(&args.database)?;
+    run(Indexer::new(&args.url, db), &args)
+}
+
+fn run<D: Database>(mut indexer: Indexer<D>, args: &Args) -> Result<(), Box<dyn Error>> {
+    if let Some(slot) = args.rollback {
+        indexer.rollback(slot)?;
+        eprintln!("rolled back to slot {}", slot);
+    }
+
+    loop {
+        match indexer.poll() {
+            Ok(0) => {}
+            Ok(count) => eprintln!("indexed {} transactions", count),
+            Err(err) if !args.once => eprintln!("poll failed, retrying: {}", err),
+            Err(err) => return Err(err.into()),
+        }
+        if args.once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(args.interval));
+    }
+}
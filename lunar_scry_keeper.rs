@@ -0,0 +1,164 @@
+//! `lunar-scry-keeper`: runs the program's permissionless upkeep.
+//!
+//! Usage: `lunar-scry-keeper [--url <rpc>] [--keypair <file>] [--collect-fees] [--once]`
+//!
+//! Every tick it finalizes pending content whose voting period has ended and
+//! closes the current reward epoch once `REWARD_DISTRIBUTION_PERIOD` has
+//! passed. Deadlines are checked against the cluster clock, the same one the
+//! program uses. With `--collect-fees` the keeper also passes its GUARD token
+//! account so the treasury's `keeper_fee` is paid out to it.
+
+use clap::Parser;
+use lunar_scry::{constants, ContentStatus, RewardEpoch, TreasuryMovementKind};
+use lunar_scry_client::{ix, pda, Event, LunarScryClient};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Signature, Signer};
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use std::error::Error;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Parser)]
+#[command(name = "lunar-scry-keeper", about = "Finalize content and close reward epochs")]
+struct Args {
+    #[arg(long, default_value = "http://127.0.0.1:8899")]
+    url: String,
+    /// Fee payer and keeper identity; defaults to ~/.config/solana/id.json
+    #[arg(long)]
+    keypair: Option<PathBuf>,
+    /// Seconds between ticks
+    #[arg(long, default_value_t = 30)]
+    interval: u64,
+    /// Collect the keeper fee into the keypair's GUARD token account
+    #[arg(long)]
+    collect_fees: bool,
+    /// Run a single tick and exit
+    #[arg(long)]
+    once: bool,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // Shows the client's warnings about accounts it had to skip
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    let args = Args::parse();
+    let keypair_path = match args.keypair.clone() {
+        Some(path) => path,
+        None => dirs::home_dir()
+            .ok_or("cannot locate home directory; pass --keypair")?
+            .join(".config/solana/id.json"),
+    };
+    let payer = read_keypair_file(&keypair_path)
+        .map_err(|err| format!("reading {}: {}", keypair_path.display(), err))?;
+    let client = LunarScryClient::new(&args.url, payer);
+
+    let fee_account = if args.collect_fees {
+        Some(fee_account(&client)?)
+    } else {
+        None
+    };
+
+    loop {
+        if let Err(err) = tick(&client, fee_account.as_ref()) {
+            if args.once {
+                return Err(err);
+            }
+            eprintln!("tick failed, retrying: {}", err);
+        }
+        if args.once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(args.interval));
+    }
+}
+
+/// The keeper's GUARD associated token account, created if it is missing.
+fn fee_account(client: &LunarScryClient) -> Result<Pubkey, Box<dyn Error>> {
+    let owner = client.payer().pubkey();
+    let mint = client.guard_mint()?;
+    client.send(
+        &[create_associated_token_account_idempotent(&owner, &owner, &mint, &spl_token::ID)],
+        &[],
+    )?;
+    Ok(get_associated_token_address(&owner, &mint))
+}
+
+fn tick(client: &LunarScryClient, fee_account: Option<&Pubkey>) -> Result<(), Box<dyn Error>> {
+    let protocol = client.protocol_state()?;
+    if protocol.is_paused {
+        return Ok(());
+    }
+    let now = client.clock()?.unix_timestamp;
+
+    if protocol.pause_flags & constants::PAUSE_FINALIZATION == 0 {
+        finalize_due_content(client, &protocol.treasury, fee_account, now)?;
+    }
+
+    if protocol.pause_flags & constants::PAUSE_DISTRIBUTION == 0 {
+        let epoch: RewardEpoch = client.fetch(&pda::reward_epoch(protocol.current_epoch))?;
+        if now >= epoch.start_timestamp + constants::REWARD_DISTRIBUTION_PERIOD {
+            let instruction = ix::close_reward_epoch(
+                &client.payer().pubkey(),
+                protocol.current_epoch,
+                protocol.emission_schedule_configured,
+                &protocol.treasury,
+                fee_account,
+            );
+            match client.send(&[instruction], &[]) {
+                Ok(signature) => report(client, &signature, &format!("closed epoch {}", epoch.epoch)),
+                Err(err) => eprintln!("closing epoch {} failed: {}", epoch.epoch, err),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Finalizes, oldest deadline first, every pending item the program would
+/// accept. Items without quorum are left alone: finalizing them would fail.
+fn finalize_due_content(
+    client: &LunarScryClient,
+    treasury: &Pubkey,
+    fee_account: Option<&Pubkey>,
+    now: i64,
+) -> Result<(), Box<dyn Error>> {
+    let mut due: Vec<_> = client
+        .contents_with_status(ContentStatus::Pending)?
+        .into_iter()
+        .filter(|(_, content)| now > content.submission_time + content.voting_period)
+        .collect();
+    due.sort_by_key(|(_, content)| content.submission_time + content.voting_period);
+
+    for (address, content) in due {
+        let voted = content.approve_votes + content.reject_votes;
+        if voted < (content.total_stake * content.quorum_percentage as u64) / 100 {
+            continue;
+        }
+        match client.send(&[ix::finalize_decision(&address, treasury, fee_account)], &[]) {
+            Ok(signature) => report(client, &signature, &format!("finalized {}", address)),
+            Err(err) => eprintln!("finalizing {} failed: {}", address, err),
+        }
+    }
+    Ok(())
+}
+
+/// Logs a completed upkeep transaction along with any keeper fee it paid.
+fn report(client: &LunarScryClient, signature: &Signature, action: &str) {
+    let fee: u64 = client
+        .transaction_events(signature)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|event| match event {
+            Event::TreasuryMovement(movement) if movement.kind == TreasuryMovementKind::KeeperFee => {
+                Some(movement.amount)
+            }
+            _ => None,
+        })
+        .sum();
+    if fee > 0 {
+        eprintln!("{} ({}), earned {}", action, signature, fee);
+    } else {
+        eprintln!("{} ({})", action, signature);
+    }
+}
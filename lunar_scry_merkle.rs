@@ -0,0 +1,283 @@
+//! Builds the Merkle reward tree for a closed epoch from LunarScry program logs.
+//!
+//! Usage: `lunar_scry_merkle <program-logs> <epoch>`
+//!
+//! The logs file is any dump of the program's transaction logs, including the
+//! `Program <id> invoke` and `success` lines around each `Program data: <base64>`
+//! line (e.g. `solana logs` output or an RPC transaction replay). The tree and
+//! every claimant's proof are written to stdout as JSON, ready to be posted with
+//! `post_merkle_root` and claimed with `claim_merkle_reward`. Only aligned votes
+//! get a leaf; `post_merkle_root` sends the rest of the pool to the treasury, as
+//! `claim_epoch_rewards` does for misaligned votes. The tree is not built until
+//! every content voted on in the epoch has been finalized.
+
+use anchor_lang::prelude::Pubkey;
+use lunar_scry::{constants, merkle, ContentStatus, VoteCast, VoteType};
+use lunar_scry_client::{decode_program_data, program_data, Event};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::error::Error;
+use std::fs;
+
+#[derive(Default)]
+struct EpochEvents {
+    votes: Vec<VoteCast>,
+    verdicts: HashMap<Pubkey, ContentStatus>,
+    reward_per_weight: Option<u128>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() != 3 {
+        return Err("usage: lunar_scry_merkle <program-logs> <epoch>".into());
+    }
+    let logs = fs::read_to_string(&args[1])?;
+    let epoch: u64 = args[2].parse()?;
+
+    let events = collect_events(&logs, epoch)?;
+    let reward_per_weight = events
+        .reward_per_weight
+        .ok_or("no RewardsDistributed event found for this epoch; is it closed?")?;
+
+    let claims = compute_claims(&events, reward_per_weight)?;
+    if claims.is_empty() {
+        return Err("epoch has no rewardable votes".into());
+    }
+
+    let leaves: Vec<[u8; 32]> = claims
+        .iter()
+        .enumerate()
+        .map(|(index, (claimant, amount))| merkle::leaf_hash(index as u32, claimant, *amount))
+        .collect();
+    let levels = build_levels(leaves);
+    let root = levels.last().unwrap()[0];
+    let total_amount = claims
+        .iter()
+        .try_fold(0u64, |sum, (_, amount)| sum.checked_add(*amount))
+        .ok_or("claims total overflows")?;
+
+    let output = json!({
+        "epoch": epoch,
+        "root": to_hex(&root),
+        "total_amount": total_amount,
+        "num_leaves": claims.len(),
+        "claims": claims
+            .iter()
+            .enumerate()
+            .map(|(index, (claimant, amount))| json!({
+                "index": index,
+                "claimant": claimant.to_string(),
+                "amount": amount,
+                "proof": proof(&levels, index).iter().map(|node| to_hex(node)).collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+    });
+    println!("{}", serde_json::to_string_pretty(&output)?);
+
+    Ok(())
+}
+
+fn collect_events(logs: &str, epoch: u64) -> Result<EpochEvents, Box<dyn Error>> {
+    let lines: Vec<&str> = logs.lines().collect();
+    let mut events = EpochEvents::default();
+
+    for encoded in program_data(&lines) {
+        match decode_program_data(encoded) {
+            Ok(Some(Event::VoteCast(vote))) if vote.reward_epoch == epoch => {
+                events.votes.push(vote);
+            }
+            Ok(Some(Event::DecisionFinalized(decision))) => {
+                events.verdicts.insert(decision.content_id, decision.final_status);
+            }
+            Ok(Some(Event::RewardsDistributed(closed))) if closed.epoch == epoch => {
+                events.reward_per_weight = Some(closed.reward_per_weight);
+            }
+            Ok(_) => {}
+            Err(err) => eprintln!("warning: skipping undecodable event data {}: {}", encoded, err),
+        }
+    }
+
+    Ok(events)
+}
+
+/// Applies the same per-vote rounding as `claim_epoch_rewards`, then sums each
+/// voter's aligned votes into a single leaf. Leaves are ordered by claimant.
+/// Fails if any content voted on in the epoch is still pending, since its
+/// votes cannot be paid or forfeited yet.
+fn compute_claims(
+    events: &EpochEvents,
+    reward_per_weight: u128,
+) -> Result<Vec<(Pubkey, u64)>, Box<dyn Error>> {
+    let unfinalized: BTreeSet<Pubkey> = events
+        .votes
+        .iter()
+        .map(|vote| vote.content_id)
+        .filter(|content_id| !events.verdicts.contains_key(content_id))
+        .collect();
+    if !unfinalized.is_empty() {
+        let ids: Vec<String> = unfinalized.iter().map(Pubkey::to_string).collect();
+        return Err(format!(
+            "content voted on in this epoch is not finalized yet: {}",
+            ids.join(", ")
+        )
+        .into());
+    }
+
+    let mut totals: BTreeMap<Pubkey, u64> = BTreeMap::new();
+    for vote in &events.votes {
+        let aligned = matches!(
+            (vote.vote_type, events.verdicts[&vote.content_id]),
+            (VoteType::Approve, ContentStatus::Approved) | (VoteType::Reject, ContentStatus::Rejected)
+        );
+        if !aligned {
+            continue;
+        }
+
+        let share = (vote.reward_weight as u128)
+            .checked_mul(reward_per_weight)
+            .ok_or("reward share overflows")?
+            / constants::REWARD_PRECISION;
+        let total = totals.entry(vote.voter).or_default();
+        *total = total
+            .checked_add(u64::try_from(share)?)
+            .ok_or("claim total overflows")?;
+    }
+
+    Ok(totals.into_iter().filter(|(_, amount)| *amount > 0).collect())
+}
+
+/// Returns every level of the tree, leaves first. An unpaired node is carried
+/// up unchanged, which `merkle::verify` handles by simply having a shorter proof.
+fn build_levels(leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => merkle::node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+fn proof(levels: &[Vec<[u8; 32]>], mut index: usize) -> Vec<[u8; 32]> {
+    let mut proof = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+    proof
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vote(
+        voter: Pubkey,
+        content_id: Pubkey,
+        vote_type: VoteType,
+        reward_weight: u64,
+    ) -> VoteCast {
+        VoteCast {
+            content_id,
+            voter,
+            vote_type,
+            stake_amount: reward_weight,
+            timestamp: 0,
+            vote_number: 1,
+            reward_epoch: 0,
+            reward_weight,
+            early_bonus_percentage: 0,
+        }
+    }
+
+    #[test]
+    fn claims_sum_each_voters_aligned_votes() {
+        let (alice, bob, carol) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (approved, rejected) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let events = EpochEvents {
+            votes: vec![
+                vote(alice, approved, VoteType::Approve, 100),
+                vote(alice, rejected, VoteType::Reject, 50),
+                vote(bob, approved, VoteType::Reject, 200),
+                vote(carol, rejected, VoteType::Reject, 1),
+            ],
+            verdicts: HashMap::from([
+                (approved, ContentStatus::Approved),
+                (rejected, ContentStatus::Rejected),
+            ]),
+            reward_per_weight: None,
+        };
+        // Half a token per unit of weight; carol's share rounds down to nothing
+        let claims = compute_claims(&events, constants::REWARD_PRECISION / 2).unwrap();
+        assert_eq!(claims, vec![(alice, 75)]);
+    }
+
+    #[test]
+    fn claims_wait_for_every_voted_content_to_be_finalized() {
+        let voter = Pubkey::new_unique();
+        let (finalized, pending) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let events = EpochEvents {
+            votes: vec![
+                vote(voter, finalized, VoteType::Approve, 100),
+                vote(voter, pending, VoteType::Approve, 100),
+            ],
+            verdicts: HashMap::from([(finalized, ContentStatus::Approved)]),
+            reward_per_weight: None,
+        };
+        let err = compute_claims(&events, constants::REWARD_PRECISION).unwrap_err();
+        assert!(err.to_string().contains(&pending.to_string()));
+    }
+
+    #[test]
+    fn shares_that_do_not_fit_a_token_amount_are_refused() {
+        let (voter, content) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let events = EpochEvents {
+            votes: vec![vote(voter, content, VoteType::Approve, u64::MAX)],
+            verdicts: HashMap::from([(content, ContentStatus::Approved)]),
+            reward_per_weight: None,
+        };
+        assert!(compute_claims(&events, 2 * constants::REWARD_PRECISION).is_err());
+    }
+
+    #[test]
+    fn every_proof_verifies_against_the_root() {
+        for leaf_count in 1..=9u32 {
+            let claims: Vec<(Pubkey, u64)> = (0..leaf_count)
+                .map(|amount| (Pubkey::new_unique(), u64::from(amount) + 1))
+                .collect();
+            let leaves: Vec<[u8; 32]> = claims
+                .iter()
+                .enumerate()
+                .map(|(index, (claimant, amount))| {
+                    merkle::leaf_hash(index as u32, claimant, *amount)
+                })
+                .collect();
+            let levels = build_levels(leaves.clone());
+            let root = levels.last().unwrap()[0];
+            assert_eq!(levels.last().unwrap().len(), 1);
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                assert!(merkle::verify(&proof(&levels, index), root, *leaf));
+            }
+            // A leaf claiming a different amount does not verify
+            let (claimant, amount) = claims[0];
+            let forged = merkle::leaf_hash(0, &claimant, amount + 1);
+            assert!(!merkle::verify(&proof(&levels, 0), root, forged));
+        }
+    }
+}